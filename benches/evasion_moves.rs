@@ -0,0 +1,44 @@
+//! `evasion_moves()` と `legal_moves()` の速度比較。
+//!
+//! `cargo bench --bench evasion_moves` で実行する。
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use sfen::*;
+
+const N_ITER: u32 = 20000;
+
+fn main() -> Result<()> {
+    let sfens = [
+        "sfen 4r3k/9/9/9/9/9/9/3S5/4K4 b GP 1",
+        "sfen 1ns1k1s2/lgg1L4/p3pr2b/2pp2p2/Np1NP1Pp1/PPP5p/1K1P1P1P1/LG1SG3+p/B4RSN1 w lp 98",
+        "sfen l2s+b2np/1rk1sgl2/1p4PP1/p3P3P/2ppGN1+B1/3L5/PP2pP2N/L1G1KS+pR1/1NS5G b 4P 101",
+    ];
+
+    for sfen in sfens.iter() {
+        let (pos, _) = decode(sfen)?;
+
+        let t = Instant::now();
+        for _ in 0..N_ITER {
+            black_box(black_box(&pos).evasion_moves());
+        }
+        let t_evasion = t.elapsed();
+
+        let t = Instant::now();
+        for _ in 0..N_ITER {
+            black_box(black_box(&pos).legal_moves());
+        }
+        let t_legal = t.elapsed();
+
+        println!(
+            "{}: evasion_moves {:?}, legal_moves {:?} ({:.1}x)",
+            sfen,
+            t_evasion / N_ITER,
+            t_legal / N_ITER,
+            t_legal.as_secs_f64() / t_evasion.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
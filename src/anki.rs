@@ -0,0 +1,61 @@
+//! Anki などの暗記カードアプリ向けエクスポート。
+
+use itertools::Itertools;
+
+use crate::*;
+
+/// 1枚のカード。
+///
+/// 表面に局面、裏面に正解手順 (最善手や定跡の続き) を載せる。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Card {
+    pub pos: Position,
+    pub answer: Vec<Move>,
+    pub comment: String,
+}
+
+/// カード表面の局面の表し方。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Front {
+    /// sfen 文字列。
+    Sfen,
+    /// `Position::describe()` による説明文。
+    Description(Lang),
+}
+
+/// カード群を Anki でインポート可能な CSV (表面, 裏面, コメント) に変換する。
+///
+/// 各フィールドは常にダブルクォートで囲み、改行は HTML の `<br>` に置き換える。
+pub fn to_csv<'a>(cards: impl IntoIterator<Item = &'a Card>, front: Front) -> String {
+    cards
+        .into_iter()
+        .map(|card| {
+            let s_front = match front {
+                Front::Sfen => encode(&card.pos, &[]),
+                Front::Description(lang) => card.pos.describe(lang),
+            };
+            let s_back = card
+                .answer
+                .iter()
+                .copied()
+                .map(encode::encode_move)
+                .join(" ");
+
+            [s_front.as_str(), s_back.as_str(), card.comment.as_str()]
+                .iter()
+                .map(|field| escape_field(field))
+                .join(",")
+                + "\n"
+        })
+        .collect()
+}
+
+fn escape_field(field: &str) -> String {
+    format!(
+        "\"{}\"",
+        field
+            .replace('"', "\"\"")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    )
+}
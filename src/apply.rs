@@ -0,0 +1,185 @@
+use crate::*;
+
+/// `Position::do_move()` で適用した指し手を取り消すための情報。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct UndoInfo {
+    mv: Move,
+    captured: Option<PieceType>,
+    ply: i32,
+}
+
+impl UndoInfo {
+    /// 適用した指し手を返す。
+    pub fn mv(&self) -> Move {
+        self.mv
+    }
+
+    /// 取った駒 (盤上にあったときの駒種。成駒は成駒のまま) を返す。
+    pub fn captured(&self) -> Option<PieceType> {
+        self.captured
+    }
+}
+
+impl Position {
+    /// 指し手を適用した局面を返す。
+    ///
+    /// 駒を取った場合は成駒を元に戻して手番側の持駒に加え、手番を入れ替えて手数を 1 増やす。
+    ///
+    /// 指し手の形式的な妥当性 (移動元に手番側の駒があるか、移動先に自駒がないか、
+    /// 打つ駒を持っているか、成れる駒か、取った駒の枚数が持駒の上限 (255 枚) を超えないか)
+    /// のみをチェックする。
+    /// 駒の利きや王手放置などの合法性チェックは行わない。
+    pub fn apply_move(&self, mv: Move) -> Result<Position> {
+        let mut pos = self.clone();
+        pos.apply_move_in_place(mv)?;
+        Ok(pos)
+    }
+
+    /// 指し手をその場で適用し、取り消し用の情報を返す。
+    ///
+    /// チェック内容は `apply_move()` と同じ。エラーの場合、局面は変更されない。
+    pub fn do_move(&mut self, mv: Move) -> Result<UndoInfo> {
+        let ply = self.ply;
+        let captured = self.apply_move_in_place(mv)?;
+        Ok(UndoInfo { mv, captured, ply })
+    }
+
+    /// `do_move()` で適用した指し手を取り消す。
+    ///
+    /// `undo` は直前の `do_move()` が返したものでなければならない。
+    /// そうでない場合の結果は未規定 (panic することもある)。
+    pub fn undo_move(&mut self, undo: UndoInfo) {
+        let side = self.side.flip();
+
+        match undo.mv {
+            Move::Nondrop(nondrop) => {
+                let (src, dst) = (nondrop.src(), nondrop.dst());
+                let pt_dst = match self.board.at(dst.x(), dst.y()) {
+                    BoardCell::Piece(_, pt) => pt,
+                    BoardCell::Empty => panic!("undo_move: destination square is empty"),
+                };
+                let pt_src = if nondrop.is_promotion() {
+                    pt_dst.to_unpromoted()
+                } else {
+                    pt_dst
+                };
+
+                self.board.set(src, BoardCell::Piece(side, pt_src));
+                match undo.captured {
+                    Some(pt_cap) => {
+                        self.board.set(dst, BoardCell::Piece(side.flip(), pt_cap));
+                        self.hands[side as usize]
+                            .sub(pt_cap.to_unpromoted(), 1)
+                            .expect("undo_move: captured piece not in hand");
+                    }
+                    None => self.board.set(dst, BoardCell::Empty),
+                }
+            }
+            Move::Drop(drop) => {
+                self.board.set(drop.dst(), BoardCell::Empty);
+                self.hands[side as usize]
+                    .add(drop.pt(), 1)
+                    .expect("undo_move: too many pieces in hand");
+            }
+        }
+
+        self.side = side;
+        self.ply = undo.ply;
+    }
+
+    /// 指し手をその場で適用し、取った駒 (盤上にあったときの駒種) を返す。
+    ///
+    /// エラーの場合、局面は変更されない。
+    pub(crate) fn apply_move_in_place(&mut self, mv: Move) -> Result<Option<PieceType>> {
+        let side = self.side;
+
+        let captured = match mv {
+            Move::Nondrop(nondrop) => {
+                let (src, dst) = (nondrop.src(), nondrop.dst());
+
+                let pt = match self.board.at(src.x(), src.y()) {
+                    BoardCell::Piece(side_src, pt) if side_src == side => pt,
+                    _ => {
+                        return Err(Error::move_error(format!(
+                            "no own piece on source square: {:?}",
+                            mv
+                        )))
+                    }
+                };
+                let pt_dst = if nondrop.is_promotion() {
+                    pt.to_promoted().ok_or_else(|| {
+                        Error::move_error(format!("not promotable piece: {:?}", mv))
+                    })?
+                } else {
+                    pt
+                };
+
+                let captured = match self.board.at(dst.x(), dst.y()) {
+                    BoardCell::Empty => None,
+                    BoardCell::Piece(side_dst, pt_cap) if side_dst != side => {
+                        if pt_cap == PieceType::King {
+                            return Err(Error::move_error(format!("capturing king: {:?}", mv)));
+                        }
+                        Some(pt_cap)
+                    }
+                    BoardCell::Piece(..) => {
+                        return Err(Error::move_error(format!("capturing own piece: {:?}", mv)))
+                    }
+                };
+
+                if let Some(pt_cap) = captured {
+                    self.hands[side as usize]
+                        .add(pt_cap.to_unpromoted(), 1)
+                        .ok_or_else(|| {
+                            Error::move_error(format!("too many pieces in hand: {:?}", mv))
+                        })?;
+                }
+                self.board.set(src, BoardCell::Empty);
+                self.board.set(dst, BoardCell::Piece(side, pt_dst));
+
+                captured
+            }
+            Move::Drop(drop) => {
+                let (pt, dst) = (drop.pt(), drop.dst());
+
+                if !pt.is_hand() || self.hand(side).count(pt) == 0 {
+                    return Err(Error::move_error(format!("no piece in hand: {:?}", mv)));
+                }
+                if self.board.at(dst.x(), dst.y()) != BoardCell::Empty {
+                    return Err(Error::move_error(format!(
+                        "dropping on occupied square: {:?}",
+                        mv
+                    )));
+                }
+
+                self.hands[side as usize]
+                    .sub(pt, 1)
+                    .expect("internal error");
+                self.board.set(dst, BoardCell::Piece(side, pt));
+
+                None
+            }
+        };
+
+        self.side = side.flip();
+        self.ply += 1;
+
+        Ok(captured)
+    }
+}
+
+impl Hand {
+    /// 駒を `n` 枚加える。枚数が u8 に収まらない場合、何もせずに None を返す。
+    #[must_use]
+    pub(crate) fn add(&mut self, pt: PieceType, n: u8) -> Option<()> {
+        self.0[pt as usize] = self.0[pt as usize].checked_add(n)?;
+        Some(())
+    }
+
+    /// 駒を `n` 枚減らす。枚数が足りない場合、何もせずに None を返す。
+    #[must_use]
+    pub(crate) fn sub(&mut self, pt: PieceType, n: u8) -> Option<()> {
+        self.0[pt as usize] = self.0[pt as usize].checked_sub(n)?;
+        Some(())
+    }
+}
@@ -0,0 +1,234 @@
+//! 駒の利きと、マスの間の方向・直線に関するユーティリティ。
+
+use crate::*;
+
+/// 盤上の方向 (dx, dy)。先手から見て前方が dy = -1。
+pub(crate) type Delta = (i8, i8);
+
+const KNIGHT_STEPS: [Delta; 2] = [(-1, -2), (1, -2)];
+const SILVER_STEPS: [Delta; 5] = [(-1, -1), (0, -1), (1, -1), (-1, 1), (1, 1)];
+const GOLD_STEPS: [Delta; 6] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (0, 1)];
+const KING_STEPS: [Delta; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+const ORTHO: [Delta; 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const DIAG: [Delta; 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+const FORWARD: [Delta; 1] = [(0, -1)];
+
+/// 1マスだけ動ける方向 (先手から見た向き)。走り駒の走る方向は含まない。
+pub(crate) fn steps(pt: PieceType) -> &'static [Delta] {
+    match pt {
+        PieceType::Pawn => &FORWARD,
+        PieceType::Lance | PieceType::Bishop | PieceType::Rook => &[],
+        PieceType::Knight => &KNIGHT_STEPS,
+        PieceType::Silver => &SILVER_STEPS,
+        PieceType::Gold
+        | PieceType::ProPawn
+        | PieceType::ProLance
+        | PieceType::ProKnight
+        | PieceType::ProSilver => &GOLD_STEPS,
+        PieceType::King => &KING_STEPS,
+        PieceType::Horse => &ORTHO,
+        PieceType::Dragon => &DIAG,
+    }
+}
+
+/// 走る方向 (先手から見た向き)。
+pub(crate) fn slides(pt: PieceType) -> &'static [Delta] {
+    match pt {
+        PieceType::Lance => &FORWARD,
+        PieceType::Bishop | PieceType::Horse => &DIAG,
+        PieceType::Rook | PieceType::Dragon => &ORTHO,
+        _ => &[],
+    }
+}
+
+/// 先手から見た向きの方向を、`side` 側の駒にとっての盤上の方向に変換する。
+pub(crate) fn orient((dx, dy): Delta, side: Side) -> Delta {
+    match side {
+        Side::Sente => (dx, dy),
+        Side::Gote => (-dx, -dy),
+    }
+}
+
+/// `sq` から `delta` だけ進んだマスを返す。盤外なら None。
+pub(crate) fn offset(sq: Square, (dx, dy): Delta) -> Option<Square> {
+    let x = sq.x() as i8 + dx;
+    let y = sq.y() as i8 + dy;
+    if (0..9).contains(&x) && (0..9).contains(&y) {
+        Some(Square::new(x as u8, y as u8))
+    } else {
+        None
+    }
+}
+
+/// 盤上の8方向。先手から見た向きで表す (上が一段目側、左が9筋側)。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const ALL: [Self; 8] = [
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::UpLeft,
+        Self::UpRight,
+        Self::DownLeft,
+        Self::DownRight,
+    ];
+
+    /// この方向に1マス進んだときの (x, y) の変化量を返す。
+    pub fn delta(self) -> (i8, i8) {
+        match self {
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::Left => (1, 0),
+            Self::Right => (-1, 0),
+            Self::UpLeft => (1, -1),
+            Self::UpRight => (-1, -1),
+            Self::DownLeft => (1, 1),
+            Self::DownRight => (-1, 1),
+        }
+    }
+
+    /// 逆方向を返す。
+    pub fn reverse(self) -> Self {
+        let (dx, dy) = self.delta();
+        Self::from_delta((-dx, -dy)).expect("internal error")
+    }
+
+    fn from_delta(d: Delta) -> Option<Self> {
+        Self::ALL.iter().copied().find(|dir| dir.delta() == d)
+    }
+}
+
+/// `from` から見て `to` がある方向を返す。
+/// 2マスが同じ筋・段・斜めの線上にない場合、または同じマスの場合は None。
+pub fn direction_of(from: Square, to: Square) -> Option<Direction> {
+    let dx = to.x() as i8 - from.x() as i8;
+    let dy = to.y() as i8 - from.y() as i8;
+    if (dx, dy) == (0, 0) || !(dx == 0 || dy == 0 || dx.abs() == dy.abs()) {
+        return None;
+    }
+    Direction::from_delta((dx.signum(), dy.signum()))
+}
+
+/// 2マスの間にあるマス (両端を含まない) を `a` に近い順に返す。
+/// 2マスが同じ線上にない場合は空。
+pub fn between(a: Square, b: Square) -> Vec<Square> {
+    let dir = match direction_of(a, b) {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+    ray(a, dir).take_while(|&sq| sq != b).collect()
+}
+
+/// 2マスを通る直線上の全マス (両端を含む) を返す。
+/// 2マスが同じ線上にない場合は空。
+pub fn line_through(a: Square, b: Square) -> Vec<Square> {
+    let dir = match direction_of(a, b) {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+    let mut res: Vec<_> = ray(a, dir.reverse()).collect();
+    res.reverse();
+    res.push(a);
+    res.extend(ray(a, dir));
+    res
+}
+
+/// `sq` から方向 `dir` に盤端まで進んだマスを返す (`sq` 自身は含まない)。
+pub(crate) fn ray(sq: Square, dir: Direction) -> impl Iterator<Item = Square> {
+    let d = dir.delta();
+    std::iter::successors(offset(sq, d), move |&cur| offset(cur, d))
+}
+
+impl Board {
+    pub(crate) fn cell(&self, sq: Square) -> BoardCell {
+        self.at(sq.x(), sq.y())
+    }
+
+    /// マス `sq` にある `side` 側の駒 `pt` が利いているマスを返す (自駒のあるマスも含む)。
+    pub(crate) fn attacks_from(&self, sq: Square, side: Side, pt: PieceType) -> Vec<Square> {
+        let mut res = Vec::with_capacity(16);
+
+        for &d in steps(pt) {
+            res.extend(offset(sq, orient(d, side)));
+        }
+        for &d in slides(pt) {
+            let d = orient(d, side);
+            let mut cur = sq;
+            while let Some(next) = offset(cur, d) {
+                res.push(next);
+                if self.cell(next) != BoardCell::Empty {
+                    break;
+                }
+                cur = next;
+            }
+        }
+
+        res
+    }
+
+    /// マス `sq` に利いている `side` 側の駒のマスを返す。
+    pub(crate) fn attackers_to(&self, sq: Square, side: Side) -> Vec<Square> {
+        let mut res = Vec::new();
+
+        // 1マスの利き (隣接マスと桂馬の位置)。
+        for &d in KING_STEPS.iter().chain(KNIGHT_STEPS.iter()) {
+            let d_board = orient(d, side);
+            if let Some(src) = offset(sq, (-d_board.0, -d_board.1)) {
+                if let BoardCell::Piece(s, pt) = self.cell(src) {
+                    if s == side && (steps(pt).contains(&d) || slides(pt).contains(&d)) {
+                        res.push(src);
+                    }
+                }
+            }
+        }
+
+        // 2マス以上離れた走り駒の利き。
+        for &d in KING_STEPS.iter() {
+            let d_board = orient(d, side);
+            let back = (-d_board.0, -d_board.1);
+            let mut cur = match offset(sq, back) {
+                Some(adj) if self.cell(adj) == BoardCell::Empty => adj,
+                _ => continue,
+            };
+            while let Some(src) = offset(cur, back) {
+                match self.cell(src) {
+                    BoardCell::Empty => cur = src,
+                    BoardCell::Piece(s, pt) => {
+                        if s == side && slides(pt).contains(&d) {
+                            res.push(src);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        res
+    }
+
+    /// マス `sq` に `side` 側の駒が利いているかどうかを返す。
+    pub(crate) fn is_attacked(&self, sq: Square, side: Side) -> bool {
+        !self.attackers_to(sq, side).is_empty()
+    }
+}
@@ -0,0 +1,184 @@
+//! 棋譜 (開始局面と指し手リスト) のコンパクトなバイナリ表現。棋譜データベース向け。
+//!
+//! 1棋譜のバイナリ表現は以下の順に並べたもの:
+//!
+//! * バージョン (1 byte)
+//! * 開始局面: PackedSfen で表せて手数が負でないなら 0 (1 byte) に続けて PackedSfen (32 byte) と
+//!   手数 (varint)、それ以外は 1 (1 byte) に続けて
+//!   局面の sfen ("sfen " を除く) の長さ (varint) と ASCII 文字列
+//! * 指し手数 (varint)
+//! * 各指し手 (u16 little endian, `compact` モジュールと同じ形式)
+//!
+//! `GameWriter` は複数の棋譜を、ファイルヘッダ (`b"SFGB"` とバージョン) に続けて
+//! 「長さ (varint) + バイナリ表現」の列として書き出す。
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use crate::compact::{decode_body, move_to_u16, read_u8, read_varint, write_varint};
+use crate::*;
+
+const MAGIC: &[u8; 4] = b"SFGB";
+const VERSION: u8 = 1;
+
+/// 1棋譜のバイナリ表現の長さの上限。これを超える長さはデータの破損とみなす。
+pub const MAX_GAME_LEN: usize = 1 << 20;
+
+/// 開始局面と指し手リストをバイナリ表現にエンコードする。
+pub fn encode_game(pos: &Position, mvs: &[Move]) -> Vec<u8> {
+    let mut buf = vec![VERSION];
+
+    if let (Ok(packed), Ok(ply)) = (packed::encode(pos), u64::try_from(pos.ply())) {
+        buf.push(0);
+        buf.extend_from_slice(&packed);
+        write_varint(&mut buf, ply);
+    } else {
+        let sfen = encode(pos, &[]);
+        let body = sfen.strip_prefix("sfen ").expect("internal error");
+        buf.push(1);
+        write_varint(&mut buf, body.len() as u64);
+        buf.extend_from_slice(body.as_bytes());
+    }
+
+    write_varint(&mut buf, mvs.len() as u64);
+    for &mv in mvs {
+        buf.extend_from_slice(&move_to_u16(mv).to_le_bytes());
+    }
+
+    buf
+}
+
+/// `encode_game()` でエンコードしたバイナリ表現をデコードする。
+pub fn decode_game(buf: &[u8]) -> Result<(Position, Vec<Move>)> {
+    let mut rdr = buf;
+    let incomplete = || Error::decode_error(ErrorCode::Binary, "binary: incomplete");
+
+    let version = read_u8(&mut rdr).ok_or_else(incomplete)?;
+    if version != VERSION {
+        return Err(Error::decode_error(
+            ErrorCode::Binary,
+            format!("binary: unsupported version: {}", version),
+        ));
+    }
+
+    decode_body(rdr, "binary", ErrorCode::Binary, |rdr| {
+        if rdr.len() < 32 {
+            return Err(incomplete());
+        }
+        let (s_packed, rest) = rdr.split_at(32);
+        *rdr = rest;
+        let mut packed = [0; 32];
+        packed.copy_from_slice(s_packed);
+        let mut pos = packed::decode(&packed)?;
+        let ply = read_varint(rdr).ok_or_else(incomplete)?;
+        pos.ply = i32::try_from(ply).map_err(|_| {
+            Error::decode_error(ErrorCode::Binary, format!("binary: invalid ply: {}", ply))
+        })?;
+        Ok(pos)
+    })
+}
+
+#[derive(Debug)]
+pub struct GameWriter<W> {
+    wtr: W,
+}
+
+impl<W: Write> GameWriter<W> {
+    /// ファイルヘッダを書き出し、ライターを返す。
+    pub fn new(mut wtr: W) -> io::Result<Self> {
+        wtr.write_all(MAGIC)?;
+        wtr.write_all(&[VERSION])?;
+        Ok(Self { wtr })
+    }
+
+    /// 1棋譜を書き出す。
+    pub fn write_game(&mut self, pos: &Position, mvs: &[Move]) -> io::Result<()> {
+        let game = encode_game(pos, mvs);
+        let mut buf = Vec::with_capacity(game.len() + 3);
+        write_varint(&mut buf, game.len() as u64);
+        buf.extend_from_slice(&game);
+        self.wtr.write_all(&buf)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.wtr.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.wtr
+    }
+}
+
+/// `GameWriter` で書き出したデータを読み込む。
+///
+/// 1 byte 単位の読み込みを行うので、ファイルなどは `BufReader` で包んで渡すこと。
+/// 壊れたデータに遭遇した場合、`io::ErrorKind::InvalidData` のエラーを返す。
+#[derive(Debug)]
+pub struct GameReader<R> {
+    rdr: R,
+}
+
+impl<R: Read> GameReader<R> {
+    /// ファイルヘッダを読み込んで検証し、リーダーを返す。
+    pub fn new(mut rdr: R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        rdr.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("binary: magic number not found".to_owned()));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data(format!(
+                "binary: unsupported version: {}",
+                header[4]
+            )));
+        }
+        Ok(Self { rdr })
+    }
+
+    /// 次の棋譜を返す。ストリームの終端に達した場合、None を返す。
+    pub fn read_game(&mut self) -> io::Result<Option<(Position, Vec<Move>)>> {
+        let len = match self.read_len()? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        let mut buf = vec![0; len];
+        self.rdr.read_exact(&mut buf)?;
+        decode_game(&buf)
+            .map(Some)
+            .map_err(|e| invalid_data(e.to_string()))
+    }
+
+    /// 棋譜の長さを読み込む。ストリームの終端なら None を返す。
+    fn read_len(&mut self) -> io::Result<Option<usize>> {
+        let mut len = 0_u64;
+        for (i, shift) in (0..64).step_by(7).enumerate() {
+            let mut b = [0];
+            if self.rdr.read(&mut b)? == 0 {
+                if i == 0 {
+                    return Ok(None);
+                }
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            len |= u64::from(b[0] & 0x7F) << shift;
+            if b[0] & 0x80 == 0 {
+                return match usize::try_from(len) {
+                    Ok(len) if len <= MAX_GAME_LEN => Ok(Some(len)),
+                    _ => Err(invalid_data("binary: invalid game length".to_owned())),
+                };
+            }
+        }
+        Err(invalid_data("binary: invalid game length".to_owned()))
+    }
+}
+
+impl<R: Read> Iterator for GameReader<R> {
+    type Item = io::Result<(Position, Vec<Move>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,190 @@
+//! 81 マスの集合を u128 のビット列で表すビットボード。
+//!
+//! ビット `9 * y + x` がマス (x, y) に対応する。
+
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not};
+
+use crate::*;
+
+const MASK: u128 = (1 << 81) - 1;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Bitboard(u128);
+
+impl Bitboard {
+    /// 空集合。
+    pub const EMPTY: Self = Self(0);
+    /// 全マス。
+    pub const FULL: Self = Self(MASK);
+
+    /// 生のビット列から作る。81 ビット目以降は無視される。
+    pub fn from_bits(bits: u128) -> Self {
+        Self(bits & MASK)
+    }
+
+    pub fn bits(self) -> u128 {
+        self.0
+    }
+
+    pub fn from_square(sq: Square) -> Self {
+        Self(1 << sq.0)
+    }
+
+    /// 筋 `x` (0 が1筋) の全マス。
+    pub fn file(x: u8) -> Self {
+        (0..9).map(|y| Square::new(x, y)).collect()
+    }
+
+    /// 段 `y` (0 が1段目) の全マス。
+    pub fn rank(y: u8) -> Self {
+        (0..9).map(|x| Square::new(x, y)).collect()
+    }
+
+    pub fn contains(self, sq: Square) -> bool {
+        self.0 & (1 << sq.0) != 0
+    }
+
+    pub fn insert(&mut self, sq: Square) {
+        self.0 |= 1 << sq.0;
+    }
+
+    pub fn remove(&mut self, sq: Square) {
+        self.0 &= !(1 << sq.0);
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// 含まれるマスを昇順 (1一, 2一, ..., 9九) に返す。
+    pub fn iter(self) -> BitboardIter {
+        BitboardIter(self.0)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Self;
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self(!self.0 & MASK)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(iter: I) -> Self {
+        let mut bb = Self::EMPTY;
+        for sq in iter {
+            bb.insert(sq);
+        }
+        bb
+    }
+}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = BitboardIter;
+
+    fn into_iter(self) -> BitboardIter {
+        self.iter()
+    }
+}
+
+/// `Bitboard::iter()` が返すイテレータ。
+#[derive(Clone, Debug)]
+pub struct BitboardIter(u128);
+
+impl Iterator for BitboardIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+        let i = self.0.trailing_zeros();
+        self.0 &= self.0 - 1;
+        Some(Square(i as u8))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.count_ones() as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for BitboardIter {}
+
+/// 盤面を陣営・駒種ごとのビットボードで表したもの。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Bitboards {
+    pieces: [[Bitboard; 14]; 2],
+    sides: [Bitboard; 2],
+}
+
+impl Bitboards {
+    /// `side` 側の駒 `pt` があるマスの集合。
+    pub fn piece(&self, side: Side, pt: PieceType) -> Bitboard {
+        self.pieces[side as usize][pt as usize]
+    }
+
+    /// `side` 側の駒があるマスの集合。
+    pub fn side(&self, side: Side) -> Bitboard {
+        self.sides[side as usize]
+    }
+
+    /// 駒があるマスの集合。
+    pub fn occupied(&self) -> Bitboard {
+        self.sides[0] | self.sides[1]
+    }
+}
+
+impl Board {
+    /// 陣営・駒種ごとのビットボードに変換する。
+    pub fn to_bitboards(&self) -> Bitboards {
+        let mut pieces = [[Bitboard::EMPTY; 14]; 2];
+        let mut sides = [Bitboard::EMPTY; 2];
+        for (sq, cell) in self.iter() {
+            if let BoardCell::Piece(side, pt) = cell {
+                pieces[side as usize][pt as usize].insert(sq);
+                sides[side as usize].insert(sq);
+            }
+        }
+        Bitboards { pieces, sides }
+    }
+}
@@ -0,0 +1,246 @@
+//! BOD 形式 (KIF 形式などで使われる盤面図) の局面。
+//!
+//! ```text
+//! 後手の持駒：なし
+//!   ９ ８ ７ ６ ５ ４ ３ ２ １
+//! +---------------------------+
+//! |v香v桂v銀v金v玉v金v銀v桂v香|一
+//! | ・v飛 ・ ・ ・ ・ ・v角 ・|二
+//! ...
+//! | 香 桂 銀 金 玉 金 銀 桂 香|九
+//! +---------------------------+
+//! 先手の持駒：なし
+//! 先手番
+//! ```
+
+use crate::*;
+
+/// 持駒を並べる順。
+const HAND_ORDER: [PieceType; 7] = [
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Gold,
+    PieceType::Silver,
+    PieceType::Knight,
+    PieceType::Lance,
+    PieceType::Pawn,
+];
+
+const KANJI_DIGITS: [char; 10] = ['〇', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+/// 局面を BOD 形式で出力する。座標は全角で表記する。
+pub fn encode(pos: &Position) -> String {
+    encode_with_style(pos, CoordStyle::FullWidth)
+}
+
+/// 局面を、指定した座標の文字種で BOD 形式で出力する。
+///
+/// 手数は "手数＝N" (N は手数 - 1) の行として出力する。
+pub fn encode_with_style(pos: &Position, style: CoordStyle) -> String {
+    let mut res = String::new();
+
+    res.push_str(&encode_hand(pos, Side::Gote));
+
+    res.push(' ');
+    for x in (0..9).rev() {
+        match style {
+            CoordStyle::FullWidth => res.push(' '),
+            CoordStyle::Ascii => res.push_str("  "),
+        }
+        res.push(style.file(x));
+    }
+    res.push('\n');
+
+    res.push_str("+---------------------------+\n");
+    for y in 0..9 {
+        res.push('|');
+        for x in (0..9).rev() {
+            match pos.board().at(x, y) {
+                BoardCell::Empty => res.push_str(" ・"),
+                BoardCell::Piece(side, pt) => {
+                    res.push(if side == Side::Sente { ' ' } else { 'v' });
+                    res.push_str(pt.name(Lang::JapaneseShort));
+                }
+            }
+        }
+        res.push('|');
+        res.push(style.rank(y));
+        res.push('\n');
+    }
+    res.push_str("+---------------------------+\n");
+
+    res.push_str(&encode_hand(pos, Side::Sente));
+
+    res.push_str(&format!("手数＝{}\n", pos.ply() - 1));
+    res.push_str(&format!("{}番\n", pos.side().name(Lang::Japanese)));
+
+    res
+}
+
+pub(crate) fn encode_hand(pos: &Position, side: Side) -> String {
+    let hand = pos.hand(side);
+    let mut res = format!("{}の持駒：", side.name(Lang::Japanese));
+    if hand.is_empty() {
+        res.push_str("なし");
+    } else {
+        for &pt in HAND_ORDER.iter() {
+            let n = hand.count(pt);
+            if n == 0 {
+                continue;
+            }
+            res.push_str(pt.name(Lang::JapaneseShort));
+            if n >= 2 {
+                res.push_str(&kanji_number(n));
+            }
+            res.push('　');
+        }
+    }
+    res.push('\n');
+    res
+}
+
+/// 1..=99 の数を漢数字 ("十八" など) で表す。
+fn kanji_number(n: u8) -> String {
+    let (tens, ones) = (n / 10, n % 10);
+    let mut res = String::new();
+    if tens >= 2 {
+        res.push(KANJI_DIGITS[tens as usize]);
+    }
+    if tens >= 1 {
+        res.push('十');
+    }
+    if ones >= 1 {
+        res.push(KANJI_DIGITS[ones as usize]);
+    }
+    res
+}
+
+fn parse_kanji_number(s: &str) -> Option<u8> {
+    let digit = |c: char| KANJI_DIGITS.iter().position(|&d| d == c).map(|d| d as u8);
+    let cs: Vec<char> = s.chars().collect();
+    match cs[..] {
+        [] => Some(1),
+        [d] if d != '十' => digit(d),
+        ['十'] => Some(10),
+        ['十', o] => Some(10 + digit(o)?),
+        [t, '十'] => Some(10 * digit(t)?),
+        [t, '十', o] => Some(10 * digit(t)? + digit(o)?),
+        _ => None,
+    }
+}
+
+/// BOD 形式の局面をパースする。
+///
+/// 盤面の行 ("|" または "│" で始まる行) はちょうど9行必要。
+/// 持駒の行 ("先手の持駒：" など。駒落ちの "上手" "下手" も可)、手番の行 ("後手番" など)、
+/// 手数の行 ("手数＝N") は省略でき、省略時はそれぞれ持駒なし、先手番、手数 1 とする。
+/// それ以外の行は無視する。
+pub fn decode(bod: impl AsRef<str>) -> Result<Position> {
+    let mut rows = Vec::with_capacity(9);
+    let mut hands = [Hand::empty(), Hand::empty()];
+    let mut side = Side::Sente;
+    let mut ply = 1;
+
+    for line in bod.as_ref().lines().map(str::trim_end) {
+        if let Some(rest) = line.strip_prefix('|').or_else(|| line.strip_prefix('│')) {
+            rows.push(decode_row(rest)?);
+        } else if let Some((side, rest)) = strip_side_prefix(line, "の持駒：") {
+            hands[side as usize] = decode_hand(rest)?;
+        } else if let Some((s, "")) = strip_side_prefix(line, "番") {
+            side = s;
+        } else if let Some(rest) = line.strip_prefix("手数＝") {
+            let n: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            ply = n
+                .parse::<i32>()
+                .ok()
+                .and_then(|n| n.checked_add(1))
+                .ok_or_else(|| {
+                    Error::decode_error(ErrorCode::Bod, format!("bod: invalid ply: {}", line))
+                })?;
+        }
+    }
+
+    if rows.len() != 9 {
+        return Err(Error::decode_error(
+            ErrorCode::Bod,
+            format!("bod: expected 9 rows, found {}", rows.len()),
+        ));
+    }
+    let board = Board::new(|x, y| rows[y as usize][8 - x as usize]);
+
+    let [hand_sente, hand_gote] = hands;
+    Ok(Position::new(side, board, hand_sente, hand_gote, ply))
+}
+
+/// BOD 形式の局面の一部である行 (盤面、持駒、手番、手数の行) かどうかを返す。
+pub(crate) fn is_bod_line(line: &str) -> bool {
+    line.starts_with(['|', '│'].as_ref())
+        || strip_side_prefix(line, "の持駒：").is_some()
+        || matches!(strip_side_prefix(line, "番"), Some((_, "")))
+        || line.starts_with("手数＝")
+}
+
+/// 行頭の "先手" "後手" "下手" "上手" と `suffix` を取り除く。
+fn strip_side_prefix<'a>(line: &'a str, suffix: &str) -> Option<(Side, &'a str)> {
+    let side = if line.starts_with("先手") || line.starts_with("下手") {
+        Side::Sente
+    } else if line.starts_with("後手") || line.starts_with("上手") {
+        Side::Gote
+    } else {
+        return None;
+    };
+    let rest = line["先手".len()..].strip_prefix(suffix)?;
+    Some((side, rest))
+}
+
+/// 盤面の1行 (先頭の "|" を除いたもの) をパースする。
+fn decode_row(s: &str) -> Result<[BoardCell; 9]> {
+    let err = || Error::decode_error(ErrorCode::Bod, format!("bod: invalid row: {}", s));
+
+    let cs: Vec<char> = s.chars().collect();
+    if cs.len() < 18 {
+        return Err(err());
+    }
+    let mut row = [BoardCell::Empty; 9];
+    for (cell, pair) in row.iter_mut().zip(cs.chunks(2)) {
+        *cell = match pair {
+            [_, '・'] => BoardCell::Empty,
+            [prefix, c] => {
+                let side = match prefix {
+                    ' ' => Side::Sente,
+                    'v' => Side::Gote,
+                    _ => return Err(err()),
+                };
+                let pt =
+                    PieceType::from_name(Lang::JapaneseShort, c.to_string()).ok_or_else(err)?;
+                BoardCell::Piece(side, pt)
+            }
+            _ => return Err(err()),
+        };
+    }
+    Ok(row)
+}
+
+/// 持駒 ("角　歩二" または "なし") をパースする。
+fn decode_hand(s: &str) -> Result<Hand> {
+    let err = || Error::decode_error(ErrorCode::Bod, format!("bod: invalid hand: {}", s));
+
+    let mut hand = Hand::empty();
+    let s = s.trim_matches(|c: char| c.is_whitespace());
+    if s == "なし" || s.is_empty() {
+        return Ok(hand);
+    }
+    for item in s
+        .split(|c: char| c.is_whitespace())
+        .filter(|s| !s.is_empty())
+    {
+        let mut cs = item.chars();
+        let c = cs.next().ok_or_else(err)?;
+        let pt = PieceType::from_name(Lang::JapaneseShort, c.to_string())
+            .filter(|pt| pt.is_hand())
+            .ok_or_else(err)?;
+        let n = parse_kanji_number(cs.as_str()).ok_or_else(err)?;
+        hand.add(pt, n).ok_or_else(err)?;
+    }
+    Ok(hand)
+}
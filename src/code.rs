@@ -0,0 +1,206 @@
+//! エラーの種類を表す安定したコード。
+
+use std::fmt;
+
+use crate::*;
+
+/// エラーの種類。`Error::code()` で得られる。
+///
+/// 文字列表現 (`as_str()`) と数値表現 (`as_u16()`) は今後のバージョンでも変更しない。
+/// 種類が追加されることはある。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// sfen のトークンが足りない。
+    PositionIncomplete,
+    /// "startpos" でも "sfen" でもない。
+    PositionInvalidMagic,
+    /// 指し手リストを含まないはずの sfen に指し手リストがある。
+    PositionUnexpectedMoves,
+    /// USI の "position" コマンドが "position" で始まっていない。
+    PositionKeywordExpected,
+    /// 盤面の段数が 9 を超える。
+    BoardTooManyRows,
+    /// 盤面の段に不正な文字がある。
+    BoardRowInvalidChar,
+    /// 盤面の成れない駒に "+" が付いている。
+    BoardRowNotPromotable,
+    /// 盤面の段のマス数が 9 を超える。
+    BoardRowOverflow,
+    /// 盤面の "+" の位置が不正。
+    BoardRowInvalidPlus,
+    /// 盤面の段数が盤の段数に足りない。
+    BoardTooFewRows,
+    /// 盤面の段のマス数が盤の筋数に足りない。
+    BoardRowUnderflow,
+    /// 手番が "b" でも "w" でもない。
+    SideInvalid,
+    /// 持駒の枚数が大きすぎる。
+    HandsOverflow,
+    /// 持駒に不正な文字がある。
+    HandsInvalidChar,
+    /// 持駒になりえない駒が持駒にある。
+    HandsNotHandPiece,
+    /// 手数が整数でない。
+    PlyInvalid,
+    /// 局面の後に "moves" 以外のトークンがある。
+    MovesKeywordExpected,
+    /// 指し手の文字列が不正。
+    MoveInvalidString,
+    /// 駒打ちの駒が不正。
+    MoveInvalidPiece,
+    /// 指し手の5文字目が "+" でない。
+    MovePlusExpected,
+    /// 指し手のマスが盤の範囲外 (`decode_variant()` のみ)。
+    MoveOutOfBoard,
+    /// 16bit で表した指し手が不正 (`compact`, `binary` モジュール)。
+    MoveInvalidPacked,
+    /// マスの筋が不正。
+    SquareInvalidX,
+    /// マスの段が不正。
+    SquareInvalidY,
+    /// マスの文字列が不正。
+    SquareInvalidString,
+    /// KIF 形式のデコードエラー。
+    Kif,
+    /// KI2 形式のデコードエラー。
+    Ki2,
+    /// CSA 形式のデコードエラー。
+    Csa,
+    /// コンパクト表現のデコードエラー。
+    Compact,
+    /// BOD 形式のデコードエラー。
+    Bod,
+    /// USI のエンジン出力のデコードエラー。
+    Usi,
+    /// PackedSfen のデコードエラー。
+    Packed,
+    /// 棋譜のバイナリ表現のデコードエラー。
+    Binary,
+    /// lishogi の URL のデコードエラー。
+    Url,
+    /// 上記以外のデコードエラー。
+    Decode,
+    /// `Error::PatternError`
+    Pattern,
+    /// `Error::MoveError`
+    Move,
+    /// `Error::CodecError`
+    Codec,
+    /// `Error::EncodeError`
+    Encode,
+}
+
+impl ErrorCode {
+    /// "E_BOARD_ROW_OVERFLOW" のような文字列表現を返す。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PositionIncomplete => "E_POSITION_INCOMPLETE",
+            Self::PositionInvalidMagic => "E_POSITION_INVALID_MAGIC",
+            Self::PositionUnexpectedMoves => "E_POSITION_UNEXPECTED_MOVES",
+            Self::PositionKeywordExpected => "E_POSITION_KEYWORD_EXPECTED",
+            Self::BoardTooManyRows => "E_BOARD_TOO_MANY_ROWS",
+            Self::BoardRowInvalidChar => "E_BOARD_ROW_INVALID_CHAR",
+            Self::BoardRowNotPromotable => "E_BOARD_ROW_NOT_PROMOTABLE",
+            Self::BoardRowOverflow => "E_BOARD_ROW_OVERFLOW",
+            Self::BoardRowInvalidPlus => "E_BOARD_ROW_INVALID_PLUS",
+            Self::BoardTooFewRows => "E_BOARD_TOO_FEW_ROWS",
+            Self::BoardRowUnderflow => "E_BOARD_ROW_UNDERFLOW",
+            Self::SideInvalid => "E_SIDE_INVALID",
+            Self::HandsOverflow => "E_HANDS_OVERFLOW",
+            Self::HandsInvalidChar => "E_HANDS_INVALID_CHAR",
+            Self::HandsNotHandPiece => "E_HANDS_NOT_HAND_PIECE",
+            Self::PlyInvalid => "E_PLY_INVALID",
+            Self::MovesKeywordExpected => "E_MOVES_KEYWORD_EXPECTED",
+            Self::MoveInvalidString => "E_MOVE_INVALID_STRING",
+            Self::MoveInvalidPiece => "E_MOVE_INVALID_PIECE",
+            Self::MovePlusExpected => "E_MOVE_PLUS_EXPECTED",
+            Self::MoveOutOfBoard => "E_MOVE_OUT_OF_BOARD",
+            Self::MoveInvalidPacked => "E_MOVE_INVALID_PACKED",
+            Self::SquareInvalidX => "E_SQUARE_INVALID_X",
+            Self::SquareInvalidY => "E_SQUARE_INVALID_Y",
+            Self::SquareInvalidString => "E_SQUARE_INVALID_STRING",
+            Self::Kif => "E_KIF",
+            Self::Ki2 => "E_KI2",
+            Self::Csa => "E_CSA",
+            Self::Compact => "E_COMPACT",
+            Self::Bod => "E_BOD",
+            Self::Usi => "E_USI",
+            Self::Packed => "E_PACKED",
+            Self::Binary => "E_BINARY",
+            Self::Url => "E_URL",
+            Self::Decode => "E_DECODE",
+            Self::Pattern => "E_PATTERN",
+            Self::Move => "E_MOVE",
+            Self::Codec => "E_CODEC",
+            Self::Encode => "E_ENCODE",
+        }
+    }
+
+    /// 数値表現を返す。
+    ///
+    /// 1xx は局面全体、2xx は盤面、3xx は手番・持駒・手数、4xx は指し手とマス、
+    /// 5xx は他の棋譜形式、9xx はデコードエラー以外。
+    pub fn as_u16(self) -> u16 {
+        match self {
+            Self::PositionIncomplete => 100,
+            Self::PositionInvalidMagic => 101,
+            Self::PositionUnexpectedMoves => 102,
+            Self::PositionKeywordExpected => 103,
+            Self::BoardTooManyRows => 200,
+            Self::BoardRowInvalidChar => 201,
+            Self::BoardRowNotPromotable => 202,
+            Self::BoardRowOverflow => 203,
+            Self::BoardRowInvalidPlus => 204,
+            Self::BoardTooFewRows => 205,
+            Self::BoardRowUnderflow => 206,
+            Self::SideInvalid => 300,
+            Self::HandsOverflow => 310,
+            Self::HandsInvalidChar => 311,
+            Self::HandsNotHandPiece => 312,
+            Self::PlyInvalid => 320,
+            Self::MovesKeywordExpected => 400,
+            Self::MoveInvalidString => 401,
+            Self::MoveInvalidPiece => 402,
+            Self::MovePlusExpected => 403,
+            Self::MoveOutOfBoard => 404,
+            Self::MoveInvalidPacked => 405,
+            Self::SquareInvalidX => 410,
+            Self::SquareInvalidY => 411,
+            Self::SquareInvalidString => 412,
+            Self::Kif => 500,
+            Self::Ki2 => 501,
+            Self::Csa => 502,
+            Self::Compact => 503,
+            Self::Bod => 504,
+            Self::Usi => 505,
+            Self::Packed => 506,
+            Self::Binary => 507,
+            Self::Url => 508,
+            Self::Decode => 599,
+            Self::Pattern => 900,
+            Self::Move => 901,
+            Self::Codec => 902,
+            Self::Encode => 903,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error {
+    /// エラーの種類を返す。
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::DecodeError(code, _) => *code,
+            Self::PatternError(_) => ErrorCode::Pattern,
+            Self::MoveError(_) => ErrorCode::Move,
+            Self::CodecError(_) => ErrorCode::Codec,
+            Self::EncodeError(_) => ErrorCode::Encode,
+        }
+    }
+}
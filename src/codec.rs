@@ -0,0 +1,117 @@
+//! 駒文字の対応表を差し替えられる SFEN コーデック。
+//!
+//! 標準の SFEN とは異なる駒文字を使う方言を読み書きするために使う。
+
+use std::borrow::Cow;
+
+use crate::*;
+
+/// 成っていない駒種 (玉を含む 8 種)。
+const BASE_PTS: [PieceType; 8] = [
+    PieceType::Pawn,
+    PieceType::Lance,
+    PieceType::Knight,
+    PieceType::Silver,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Gold,
+    PieceType::King,
+];
+
+/// 駒文字の対応表を持つ SFEN コーデック。
+///
+/// 先手の駒を大文字、後手の駒を小文字で表す点、成駒を `+` で表す点は標準の SFEN と同じ。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Codec {
+    /// 成っていない駒種ごとの文字 (ASCII 大文字)。添字は `PieceType as usize`。
+    chars: [char; 8],
+}
+
+impl Codec {
+    /// 標準の SFEN の対応表。
+    pub const STANDARD: Self = Self {
+        chars: ['P', 'L', 'N', 'S', 'B', 'R', 'G', 'K'],
+    };
+
+    /// (駒種, 文字) の対応表からコーデックを作る。
+    ///
+    /// 対応表は成っていない駒種 8 種をちょうど1回ずつ含まなければならない。
+    /// 文字は ASCII 英字で、大文字小文字を区別せずに互いに異なっていなければならない。
+    pub fn new(table: &[(PieceType, char)]) -> Result<Self> {
+        let mut chars = ['\0'; 8];
+
+        for &(pt, c) in table {
+            if !BASE_PTS.contains(&pt) {
+                return Err(Error::codec_error(format!("promoted piece: {:?}", pt)));
+            }
+            if !c.is_ascii_alphabetic() {
+                return Err(Error::codec_error(format!("invalid char: {}", c)));
+            }
+            if chars[pt as usize] != '\0' {
+                return Err(Error::codec_error(format!("duplicate piece: {:?}", pt)));
+            }
+            let c = c.to_ascii_uppercase();
+            if chars.contains(&c) {
+                return Err(Error::codec_error(format!("ambiguous char: {}", c)));
+            }
+            chars[pt as usize] = c;
+        }
+
+        if let Some(&pt) = BASE_PTS.iter().find(|&&pt| chars[pt as usize] == '\0') {
+            return Err(Error::codec_error(format!("missing piece: {:?}", pt)));
+        }
+
+        Ok(Self { chars })
+    }
+
+    /// sfen をパースして (局面、指し手リスト) を返す。`decode()` を参照。
+    pub fn decode(&self, sfen: impl AsRef<str>) -> Result<(Position, Vec<Move>)> {
+        decode::decode_with(self, sfen.as_ref())
+    }
+
+    /// 局面と指し手リストを sfen に変換する。`encode()` を参照。
+    pub fn encode(&self, pos: &Position, mvs: &[Move]) -> String {
+        encode::encode_with(self, pos, mvs)
+    }
+
+    /// 駒文字 (大文字) を成っていない駒種に変換する。
+    pub(crate) fn char_to_pt(&self, c: char) -> Option<PieceType> {
+        BASE_PTS
+            .iter()
+            .copied()
+            .find(|&pt| self.chars[pt as usize] == c)
+    }
+
+    pub(crate) fn char_to_side_pt(&self, c: char) -> Option<(Side, PieceType)> {
+        let pt = self.char_to_pt(c.to_ascii_uppercase())?;
+        let side = if c.is_ascii_uppercase() {
+            Side::Sente
+        } else {
+            Side::Gote
+        };
+        Some((side, pt))
+    }
+
+    pub(crate) fn encode_pt(&self, pt: PieceType) -> Cow<'static, str> {
+        let base = pt.to_unpromoted();
+        if base == pt {
+            self.chars[pt as usize].to_string().into()
+        } else {
+            format!("+{}", self.chars[base as usize]).into()
+        }
+    }
+
+    pub(crate) fn encode_piece(&self, side: Side, pt: PieceType) -> Cow<'static, str> {
+        let s_pt = self.encode_pt(pt);
+        match side {
+            Side::Sente => s_pt,
+            Side::Gote => s_pt.to_ascii_lowercase().into(),
+        }
+    }
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
@@ -0,0 +1,262 @@
+//! QR コードに収まるサイズを想定した、棋譜全体のコンパクトなテキスト表現。
+//!
+//! バイナリ表現を base32 (RFC 4648, パディングなし) でエンコードする。
+//! base32 の文字は全て QR コードの英数字モードで表現できる。
+//!
+//! バイナリ表現は以下の順に並べたもの:
+//!
+//! * バージョン (1 byte)
+//! * 開始局面: 平手なら 0 (1 byte)、それ以外は 1 (1 byte) に続けて
+//!   局面の sfen ("sfen " を除く) の長さ (varint) と ASCII 文字列
+//! * 指し手数 (varint)
+//! * 各指し手 (u16 little endian, 下位から dst 7bit, src 7bit, 成り 1bit。
+//!   駒打ちでは src に 81 + 駒種を入れる)
+//! * 以上全体の CRC-32 (u32 little endian)
+
+use crate::crc32::crc32;
+use crate::*;
+
+const VERSION: u8 = 1;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// 開始局面と指し手リストをコンパクトなテキスト表現にエンコードする。
+pub fn encode_game(pos: &Position, mvs: &[Move]) -> String {
+    let mut buf = vec![VERSION];
+
+    if *pos == startpos() {
+        buf.push(0);
+    } else {
+        let sfen = encode(pos, &[]);
+        let body = sfen.strip_prefix("sfen ").expect("internal error");
+        buf.push(1);
+        write_varint(&mut buf, body.len() as u64);
+        buf.extend_from_slice(body.as_bytes());
+    }
+
+    write_varint(&mut buf, mvs.len() as u64);
+    for &mv in mvs {
+        buf.extend_from_slice(&move_to_u16(mv).to_le_bytes());
+    }
+
+    let crc = crc32(&buf);
+    buf.extend_from_slice(&crc.to_le_bytes());
+
+    base32_encode(&buf)
+}
+
+/// `encode_game()` でエンコードしたテキストをデコードする。
+///
+/// 英字の大文字小文字は区別せず、ASCII 空白は無視する。
+pub fn decode_game(s: impl AsRef<str>) -> Result<(Position, Vec<Move>)> {
+    let buf = base32_decode(s.as_ref())
+        .ok_or_else(|| Error::decode_error(ErrorCode::Compact, "compact: invalid base32 string"))?;
+
+    if buf.len() < 4 {
+        return Err(Error::decode_error(
+            ErrorCode::Compact,
+            "compact: too short",
+        ));
+    }
+    let (body, crc) = buf.split_at(buf.len() - 4);
+    if crc32(body).to_le_bytes() != crc {
+        return Err(Error::decode_error(
+            ErrorCode::Compact,
+            "compact: checksum mismatch",
+        ));
+    }
+
+    let mut rdr = body;
+    let incomplete = || Error::decode_error(ErrorCode::Compact, "compact: incomplete");
+
+    let version = read_u8(&mut rdr).ok_or_else(incomplete)?;
+    if version != VERSION {
+        return Err(Error::decode_error(
+            ErrorCode::Compact,
+            format!("compact: unsupported version: {}", version),
+        ));
+    }
+
+    decode_body(rdr, "compact", ErrorCode::Compact, |_| Ok(startpos()))
+}
+
+/// バージョンより後ろの、開始局面と指し手欄をデコードする。`binary` モジュールと共通。
+///
+/// 開始局面のタグ 0 は `decode_tag0` で読み込み、タグ 1 は sfen 文字列とする。
+/// `rdr` の残り全体が指し手欄でなければならない。
+/// エラーの種類は `code` で、メッセージは `name` で始まる。
+pub(crate) fn decode_body<F>(
+    mut rdr: &[u8],
+    name: &str,
+    code: ErrorCode,
+    decode_tag0: F,
+) -> Result<(Position, Vec<Move>)>
+where
+    F: FnOnce(&mut &[u8]) -> Result<Position>,
+{
+    let incomplete = || Error::decode_error(code, format!("{}: incomplete", name));
+
+    let pos = match read_u8(&mut rdr).ok_or_else(incomplete)? {
+        0 => decode_tag0(&mut rdr)?,
+        1 => {
+            let len = read_varint(&mut rdr).ok_or_else(incomplete)? as usize;
+            if rdr.len() < len {
+                return Err(incomplete());
+            }
+            let (s_body, rest) = rdr.split_at(len);
+            rdr = rest;
+            let s_body = std::str::from_utf8(s_body).map_err(|_| {
+                Error::decode_error(code, format!("{}: invalid position string", name))
+            })?;
+            decode(format!("sfen {}", s_body))?.0
+        }
+        tag => {
+            return Err(Error::decode_error(
+                code,
+                format!("{}: invalid position tag: {}", name, tag),
+            ))
+        }
+    };
+
+    let n_mv = read_varint(&mut rdr).ok_or_else(incomplete)? as usize;
+    if n_mv.checked_mul(2) != Some(rdr.len()) {
+        return Err(Error::decode_error(
+            code,
+            format!("{}: move list length mismatch", name),
+        ));
+    }
+    let mvs = rdr
+        .chunks(2)
+        .map(|bs| move_from_u16(u16::from_le_bytes([bs[0], bs[1]])))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((pos, mvs))
+}
+
+fn startpos() -> Position {
+    decode("startpos").expect("internal error").0
+}
+
+/// 指し手を 16bit 値に変換する。
+pub(crate) fn move_to_u16(mv: Move) -> u16 {
+    let sq_idx = |sq: Square| u16::from(9 * sq.y() + sq.x());
+    match mv {
+        Move::Nondrop(nondrop) => {
+            let promo = if nondrop.is_promotion() { 1 << 14 } else { 0 };
+            promo | (sq_idx(nondrop.src()) << 7) | sq_idx(nondrop.dst())
+        }
+        Move::Drop(drop) => ((81 + drop.pt() as u16) << 7) | sq_idx(drop.dst()),
+    }
+}
+
+/// `move_to_u16()` の逆変換。
+pub(crate) fn move_from_u16(x: u16) -> Result<Move> {
+    let invalid = || {
+        Error::decode_error(
+            ErrorCode::MoveInvalidPacked,
+            format!("move: invalid packed move: {:#06x}", x),
+        )
+    };
+
+    let idx_to_sq = |idx: u16| {
+        if idx < 81 {
+            Some(Square::new((idx % 9) as u8, (idx / 9) as u8))
+        } else {
+            None
+        }
+    };
+
+    if x >> 15 != 0 {
+        return Err(invalid());
+    }
+    let dst = idx_to_sq(x & 0x7F).ok_or_else(invalid)?;
+    let src = (x >> 7) & 0x7F;
+    let is_promotion = x & (1 << 14) != 0;
+
+    if let Some(src) = idx_to_sq(src) {
+        Ok(Move::nondrop(src, dst, is_promotion))
+    } else {
+        const PTS_HAND: [PieceType; 7] = [
+            PieceType::Pawn,
+            PieceType::Lance,
+            PieceType::Knight,
+            PieceType::Silver,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Gold,
+        ];
+        let pt = PTS_HAND
+            .get(usize::from(src - 81))
+            .copied()
+            .ok_or_else(invalid)?;
+        if is_promotion {
+            return Err(invalid());
+        }
+        Ok(Move::drop(pt, dst))
+    }
+}
+
+pub(crate) fn write_varint(buf: &mut Vec<u8>, mut x: u64) {
+    while x >= 0x80 {
+        buf.push((x as u8) | 0x80);
+        x >>= 7;
+    }
+    buf.push(x as u8);
+}
+
+pub(crate) fn read_varint(rdr: &mut &[u8]) -> Option<u64> {
+    let mut x = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let b = read_u8(rdr)?;
+        x |= u64::from(b & 0x7F) << shift;
+        if b & 0x80 == 0 {
+            return Some(x);
+        }
+    }
+    None
+}
+
+pub(crate) fn read_u8(rdr: &mut &[u8]) -> Option<u8> {
+    let (&b, rest) = rdr.split_first()?;
+    *rdr = rest;
+    Some(b)
+}
+
+pub(crate) fn base32_encode(data: &[u8]) -> String {
+    let mut res = String::with_capacity((data.len() * 8 + 4) / 5);
+    let mut acc = 0_u32;
+    let mut n_bit = 0;
+    for &b in data {
+        acc = (acc << 8) | u32::from(b);
+        n_bit += 8;
+        while n_bit >= 5 {
+            n_bit -= 5;
+            res.push(char::from(
+                BASE32_ALPHABET[((acc >> n_bit) & 0x1F) as usize],
+            ));
+        }
+    }
+    if n_bit > 0 {
+        res.push(char::from(
+            BASE32_ALPHABET[((acc << (5 - n_bit)) & 0x1F) as usize],
+        ));
+    }
+    res
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut res = Vec::with_capacity(s.len() * 5 / 8);
+    let mut acc = 0_u32;
+    let mut n_bit = 0;
+    for c in s.chars().filter(|c| !c.is_ascii_whitespace()) {
+        let c = c.to_ascii_uppercase();
+        let v = BASE32_ALPHABET.iter().position(|&a| char::from(a) == c)? as u32;
+        acc = (acc << 5) | v;
+        n_bit += 5;
+        if n_bit >= 8 {
+            n_bit -= 8;
+            res.push((acc >> n_bit) as u8);
+        }
+    }
+    Some(res)
+}
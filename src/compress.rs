@@ -0,0 +1,143 @@
+//! 圧縮ファイルの透過的な読み書き。
+//!
+//! gzip は `gzip` feature、zstd は `zstd` feature で有効になる。
+//! 無効な形式のデータを読み書きしようとすると `io::ErrorKind::Unsupported` のエラーとなる。
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// 先頭バイト列 (マジックナンバー) から圧縮形式を判定する。
+    pub fn detect(head: &[u8]) -> Self {
+        if head.starts_with(&[0x1F, 0x8B]) {
+            Self::Gzip
+        } else if head.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+
+    /// ファイル名の拡張子 (`.gz`, `.zst`) から圧縮形式を判定する。
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+}
+
+/// マジックナンバーから圧縮形式を判定し、展開しながら読み込むリーダーを返す。
+pub fn reader<'a>(rdr: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+    let mut rdr = BufReader::new(rdr);
+    let compression = Compression::detect(rdr.fill_buf()?);
+
+    match compression {
+        Compression::None => Ok(Box::new(rdr)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::bufread::MultiGzDecoder::new(rdr))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(zstd::Decoder::with_buffer(rdr)?)),
+        #[allow(unreachable_patterns)]
+        _ => Err(unsupported(compression)),
+    }
+}
+
+/// 指定した形式で圧縮しながら書き込むライターを返す。
+///
+/// 書き込み終えたら `Writer::finish()` を呼ぶこと。
+pub fn writer<W: Write>(wtr: W, compression: Compression) -> io::Result<Writer<W>> {
+    let inner = match compression {
+        Compression::None => Inner::None(wtr),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Inner::Gzip(flate2::write::GzEncoder::new(
+            wtr,
+            flate2::Compression::default(),
+        )),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Inner::Zstd(zstd::Encoder::new(wtr, 0)?),
+        #[allow(unreachable_patterns)]
+        _ => return Err(unsupported(compression)),
+    };
+    Ok(Writer { inner: Some(inner) })
+}
+
+/// `writer()` が返す、圧縮しながら書き込むライター。
+///
+/// `finish()` を呼ばずに drop した場合も圧縮ストリームの終端処理を試みるが、
+/// そのときのエラーは無視される。
+pub struct Writer<W: Write> {
+    // finish() で取り出した後のみ None。
+    inner: Option<Inner<W>>,
+}
+
+enum Inner<W: Write> {
+    None(W),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Writer<W> {
+    /// 圧縮ストリームを終端し、内側のライターを返す。
+    ///
+    /// 終端処理や flush のエラーはここで報告される。
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.inner.take().expect("internal error") {
+            Inner::None(mut wtr) => {
+                wtr.flush()?;
+                Ok(wtr)
+            }
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(enc) => enc.finish(),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(enc) => enc.finish(),
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn Write {
+        match self.inner.as_mut().expect("internal error") {
+            Inner::None(wtr) => wtr,
+            #[cfg(feature = "gzip")]
+            Inner::Gzip(enc) => enc,
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(enc) => enc,
+        }
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner_mut().flush()
+    }
+}
+
+impl<W: Write> Drop for Writer<W> {
+    fn drop(&mut self) {
+        // gzip は GzEncoder 自身の drop で終端される。
+        #[cfg(feature = "zstd")]
+        if let Some(Inner::Zstd(enc)) = &mut self.inner {
+            let _ = enc.do_finish();
+        }
+    }
+}
+
+#[allow(dead_code)]
+fn unsupported(compression: Compression) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("compress: feature for {:?} is not enabled", compression),
+    )
+}
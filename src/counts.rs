@@ -0,0 +1,73 @@
+//! 駒の枚数の集計。
+
+use crate::*;
+
+/// 陣営・駒種ごとの盤上と持駒の枚数。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PieceCounts {
+    board: [[u8; 14]; 2],
+    hand: [[u8; 7]; 2],
+}
+
+impl PieceCounts {
+    /// 盤上の `side` 側の駒 `pt` の枚数を返す。成駒と成る前の駒は区別する。
+    pub fn board(&self, side: Side, pt: PieceType) -> u8 {
+        self.board[side as usize][pt as usize]
+    }
+
+    /// `side` 側の持駒 `pt` の枚数を返す。`pt` が持駒になりえない駒種なら 0。
+    pub fn hand(&self, side: Side, pt: PieceType) -> u8 {
+        if pt.is_hand() {
+            self.hand[side as usize][pt as usize]
+        } else {
+            0
+        }
+    }
+
+    /// `side` 側の駒 `pt` の、成駒を含めた盤上と持駒の合計枚数を返す。
+    ///
+    /// `pt` が成駒の場合、成る前の駒種として数える。
+    pub fn total(&self, side: Side, pt: PieceType) -> usize {
+        let base = pt.to_unpromoted();
+        let n_board: usize = PieceType::ALL
+            .iter()
+            .filter(|p| p.to_unpromoted() == base)
+            .map(|&p| usize::from(self.board(side, p)))
+            .sum();
+        n_board + usize::from(self.hand(side, base))
+    }
+
+    /// `side` 側の盤上と持駒の駒の総数 (玉を含む) を返す。
+    pub fn len(&self, side: Side) -> usize {
+        let n_board: usize = self.board[side as usize]
+            .iter()
+            .map(|&n| usize::from(n))
+            .sum();
+        let n_hand: usize = self.hand[side as usize]
+            .iter()
+            .map(|&n| usize::from(n))
+            .sum();
+        n_board + n_hand
+    }
+}
+
+impl Position {
+    /// 陣営・駒種ごとの駒の枚数を集計する。
+    pub fn piece_counts(&self) -> PieceCounts {
+        let mut board = [[0; 14]; 2];
+        for (_, cell) in self.board().iter() {
+            if let BoardCell::Piece(side, pt) = cell {
+                board[side as usize][pt as usize] += 1;
+            }
+        }
+
+        let mut hand = [[0; 7]; 2];
+        for &side in [Side::Sente, Side::Gote].iter() {
+            for (pt, n) in self.hand(side).enumerate() {
+                hand[side as usize][pt as usize] = n;
+            }
+        }
+
+        PieceCounts { board, hand }
+    }
+}
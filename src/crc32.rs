@@ -0,0 +1,12 @@
+/// CRC-32 (IEEE 802.3, 多項式 0xEDB88320) を計算する。
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,394 @@
+//! CSA 形式の棋譜。
+//!
+//! 棋譜は `kif::Game` で表す。ヘッダは "N+", "$EVENT" のような CSA のキーをそのまま用いる。
+//! 終局は KIF 形式と共通の表記 ("投了" など) に変換する。
+
+use std::time::Duration;
+
+use crate::kif::Game;
+use crate::notation::{DecodeNotation, EncodeNotation};
+use crate::*;
+
+#[rustfmt::skip]
+const PIECE_CODES: [&str; 14] = [
+    "FU", "KY", "KE", "GI", "KA", "HI", "KI", "OU",
+    "TO", "NY", "NK", "NG", "UM", "RY",
+];
+
+/// 終局の表記と CSA 形式の特殊な指し手の対応。
+const TERMINALS: [(&str, &str); 9] = [
+    ("投了", "%TORYO"),
+    ("中断", "%CHUDAN"),
+    ("千日手", "%SENNICHITE"),
+    ("持将棋", "%JISHOGI"),
+    ("詰み", "%TSUMI"),
+    ("不詰", "%FUZUMI"),
+    ("切れ負け", "%TIME_UP"),
+    ("反則負け", "%ILLEGAL_MOVE"),
+    ("入玉勝ち", "%KACHI"),
+];
+
+fn pt_from_code(code: &str) -> Option<PieceType> {
+    PIECE_CODES
+        .iter()
+        .position(|&c| c == code)
+        .map(|i| PieceType::ALL[i])
+}
+
+fn pt_to_code(pt: PieceType) -> &'static str {
+    PIECE_CODES[pt as usize]
+}
+
+/// CSA 形式。`notation::convert()` などで使う。
+///
+/// 読み込みではヘッダ、消費時間、終局の表記を捨てる。
+/// 書き出しは `encode()` と同じで、ヘッダは出力しない。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Csa;
+
+impl EncodeNotation for Csa {
+    fn encode_notation(pos: &Position, mvs: &[Move]) -> Result<String> {
+        encode(&Game::new(pos.clone(), mvs.to_vec()))
+    }
+}
+
+impl DecodeNotation for Csa {
+    fn decode_notation(s: &str) -> Result<(Position, Vec<Move>)> {
+        let game = decode(s)?;
+        Ok((game.position, game.moves))
+    }
+}
+
+/// CSA 形式の棋譜をパースする。
+///
+/// 1行に "," で区切った複数の文を含んでもよい。コメント ("'" で始まる行) は無視する。
+/// 開始局面は "PI", "P1".."P9", "P+"/"P-" のいずれかで与える。手数は 1 とする。
+/// 指し手は局面上で再生され、駒の種類が盤面と合わない手や形式的に不正な手はエラーとなる
+/// (利きや王手放置などの合法性は検査しない)。
+pub fn decode(csa: impl AsRef<str>) -> Result<Game> {
+    let mut headers = Vec::new();
+    let mut board = Board::empty();
+    let mut hands = [Hand::empty(), Hand::empty()];
+    let mut start: Option<Position> = None;
+    let mut pos: Option<Position> = None;
+    let mut moves = Vec::new();
+    let mut times = Vec::new();
+    let mut terminal = None;
+
+    let stmts = csa
+        .as_ref()
+        .lines()
+        .filter(|line| !line.starts_with('\''))
+        .flat_map(|line| line.split(','))
+        .map(str::trim_end);
+
+    for stmt in stmts {
+        let err =
+            || Error::decode_error(ErrorCode::Csa, format!("csa: invalid statement: {}", stmt));
+        let overflow = || Error::decode_error(ErrorCode::Csa, "csa: hands overflow");
+
+        if stmt.is_empty() || stmt.starts_with('V') {
+            continue;
+        }
+
+        if let Some(pos) = pos.as_mut() {
+            // 指し手部分
+            if stmt.starts_with(['+', '-'].as_ref()) {
+                let mv = decode_move(stmt, pos)?;
+                pos.apply_move_in_place(mv)?;
+                moves.push(mv);
+                times.push(None);
+            } else if let Some(secs) = stmt.strip_prefix('T') {
+                let secs: u64 = secs.parse().map_err(|_| err())?;
+                *times.last_mut().ok_or_else(err)? = Some(Duration::from_secs(secs));
+            } else if stmt.starts_with('%') {
+                let term = TERMINALS
+                    .iter()
+                    .find(|&&(_, code)| code == stmt)
+                    .map_or(stmt, |&(term, _)| term);
+                terminal = Some(term.to_owned());
+                break;
+            } else {
+                return Err(err());
+            }
+            continue;
+        }
+
+        // ヘッダと開始局面
+        if let Some(name) = stmt.strip_prefix("N+").or_else(|| stmt.strip_prefix("N-")) {
+            headers.push((stmt[..2].to_owned(), name.to_owned()));
+        } else if stmt.starts_with('$') {
+            let i = stmt.find(':').ok_or_else(err)?;
+            headers.push((stmt[..i].to_owned(), stmt[i + 1..].to_owned()));
+        } else if let Some(rest) = stmt.strip_prefix("PI") {
+            let (startpos, _) = crate::decode("startpos").expect("internal error");
+            board = startpos.board().clone();
+            for chunk in rest.as_bytes().chunks(4) {
+                let chunk = std::str::from_utf8(chunk).map_err(|_| err())?;
+                let sq = match decode_sq_pt(chunk).ok_or_else(err)? {
+                    (Some(sq), pt) if matches!(board.cell(sq), BoardCell::Piece(_, p) if p == pt) => {
+                        sq
+                    }
+                    _ => return Err(err()),
+                };
+                board.clear(sq);
+            }
+        } else if stmt.starts_with("P+") || stmt.starts_with("P-") {
+            let side = if stmt.as_bytes()[1] == b'+' {
+                Side::Sente
+            } else {
+                Side::Gote
+            };
+            for chunk in stmt.as_bytes()[2..].chunks(4) {
+                let chunk = std::str::from_utf8(chunk).map_err(|_| err())?;
+                if chunk == "00AL" {
+                    let [hand_sente, hand_gote] = hands.clone();
+                    let mut pos =
+                        Position::new(Side::Sente, board.clone(), hand_sente, hand_gote, 1);
+                    pos.fill_hand_with_remaining(side);
+                    hands[side as usize] = pos.hand(side).clone();
+                    continue;
+                }
+                match decode_sq_pt(chunk).ok_or_else(err)? {
+                    (Some(sq), pt) => board.set(sq, BoardCell::Piece(side, pt)),
+                    (None, pt) if pt.is_hand() => {
+                        hands[side as usize].add(pt, 1).ok_or_else(overflow)?
+                    }
+                    _ => return Err(err()),
+                }
+            }
+        } else if let Some(row) = stmt.strip_prefix('P') {
+            let mut cs = row.chars();
+            let y = cs
+                .next()
+                .and_then(|c| c.to_digit(10))
+                .filter(|d| (1..=9).contains(d))
+                .ok_or_else(err)? as u8
+                - 1;
+            // 行末の空白は取り除かれているので補う。
+            let mut cells: Vec<char> = cs.collect();
+            if cells.len() < 27 {
+                cells.resize(27, ' ');
+            }
+            if cells.len() != 27 {
+                return Err(err());
+            }
+            for (i, cell) in cells.chunks(3).enumerate() {
+                let cell: String = cell.iter().collect();
+                let sq = Square::new(8 - i as u8, y);
+                let cell = match cell.as_str() {
+                    " * " => BoardCell::Empty,
+                    _ => {
+                        let side = match cell.as_bytes()[0] {
+                            b'+' => Side::Sente,
+                            b'-' => Side::Gote,
+                            _ => return Err(err()),
+                        };
+                        BoardCell::Piece(side, pt_from_code(&cell[1..]).ok_or_else(err)?)
+                    }
+                };
+                board.set(sq, cell);
+            }
+        } else if stmt == "+" || stmt == "-" {
+            let side = if stmt == "+" { Side::Sente } else { Side::Gote };
+            let [hand_sente, hand_gote] = hands.clone();
+            start = Some(Position::new(side, board.clone(), hand_sente, hand_gote, 1));
+            pos = start.clone();
+        } else {
+            return Err(err());
+        }
+    }
+
+    let position =
+        start.ok_or_else(|| Error::decode_error(ErrorCode::Csa, "csa: side to move not found"))?;
+
+    Ok(Game {
+        headers,
+        position,
+        moves,
+        times,
+        terminal,
+    })
+}
+
+/// "7776" のようなマスの組と駒種 ("FU" など) の4文字をパースする。"00" のマスは None とする。
+fn decode_sq_pt(s: &str) -> Option<(Option<Square>, PieceType)> {
+    if s.len() != 4 || !s.is_char_boundary(2) {
+        return None;
+    }
+    Some((decode_sq(&s[..2])?, pt_from_code(&s[2..])?))
+}
+
+/// "77" のようなマスをパースする。"00" は Some(None) とする。
+fn decode_sq(s: &str) -> Option<Option<Square>> {
+    let mut ds = s.chars().map(|c| c.to_digit(10));
+    match (ds.next()??, ds.next()??, ds.next()) {
+        (0, 0, None) => Some(None),
+        (x @ 1..=9, y @ 1..=9, None) => Some(Some(Square::new(x as u8 - 1, y as u8 - 1))),
+        _ => None,
+    }
+}
+
+/// "+7776FU" のような指し手を局面 `pos` の指し手に変換する。駒種は移動後のもの。
+fn decode_move(stmt: &str, pos: &Position) -> Result<Move> {
+    let err = || Error::decode_error(ErrorCode::Csa, format!("csa: invalid move: {}", stmt));
+
+    let side = if stmt.starts_with('+') {
+        Side::Sente
+    } else {
+        Side::Gote
+    };
+    if side != pos.side() {
+        return Err(Error::decode_error(
+            ErrorCode::Csa,
+            format!("csa: wrong side to move: {}", stmt),
+        ));
+    }
+
+    let body = &stmt[1..];
+    if body.len() != 6 || !body.is_char_boundary(2) || !body.is_char_boundary(4) {
+        return Err(err());
+    }
+    let src = decode_sq(&body[..2]).ok_or_else(err)?;
+    let (dst, pt) = match decode_sq_pt(&body[2..]).ok_or_else(err)? {
+        (Some(dst), pt) => (dst, pt),
+        (None, _) => return Err(err()),
+    };
+
+    match src {
+        None => Ok(Move::drop(pt, dst)),
+        Some(src) => {
+            let pt_src = match pos.board().cell(src) {
+                BoardCell::Piece(s, pt) if s == side => pt,
+                _ => return Err(err()),
+            };
+            if pt == pt_src {
+                Ok(Move::nondrop(src, dst, false))
+            } else if pt_src.to_promoted() == Some(pt) {
+                Ok(Move::nondrop(src, dst, true))
+            } else {
+                Err(Error::decode_error(
+                    ErrorCode::Csa,
+                    format!("csa: piece mismatch: {}", stmt),
+                ))
+            }
+        }
+    }
+}
+
+/// 棋譜を CSA 形式 (V2.2) で出力する。
+///
+/// ヘッダはキーが "N+", "N-" および "$" で始まるもののみを出力する。
+/// 開始局面は平手なら "PI"、それ以外は "P1".."P9" と "P+"/"P-" で表す。
+/// 指し手は局面上で再生され、形式的に不正な手があればエラーを返す。
+/// 終局の表記に対応する CSA の特殊な指し手がない場合はエラーを返す。
+pub fn encode(game: &Game) -> Result<String> {
+    let mut res = "V2.2\n".to_owned();
+
+    for (key, value) in &game.headers {
+        if key == "N+" || key == "N-" {
+            res.push_str(&format!("{}{}\n", key, value));
+        } else if key.starts_with('$') {
+            res.push_str(&format!("{}:{}\n", key, value));
+        }
+    }
+
+    let pos = &game.position;
+    let (startpos, _) = crate::decode("startpos").expect("internal error");
+    if pos.board() == startpos.board()
+        && pos.hand(Side::Sente).is_empty()
+        && pos.hand(Side::Gote).is_empty()
+    {
+        res.push_str("PI\n");
+    } else {
+        for y in 0..9 {
+            res.push_str(&format!("P{}", y + 1));
+            for x in (0..9).rev() {
+                match pos.board().at(x, y) {
+                    BoardCell::Empty => res.push_str(" * "),
+                    BoardCell::Piece(side, pt) => {
+                        res.push(side_char(side));
+                        res.push_str(pt_to_code(pt));
+                    }
+                }
+            }
+            res.push('\n');
+        }
+        for &side in &[Side::Sente, Side::Gote] {
+            let hand = pos.hand(side);
+            if hand.is_empty() {
+                continue;
+            }
+            res.push_str(&format!("P{}", side_char(side)));
+            for (pt, n) in hand.enumerate() {
+                for _ in 0..n {
+                    res.push_str("00");
+                    res.push_str(pt_to_code(pt));
+                }
+            }
+            res.push('\n');
+        }
+    }
+    res.push(side_char(pos.side()));
+    res.push('\n');
+
+    let mut pos = pos.clone();
+    for (i, &mv) in game.moves.iter().enumerate() {
+        let side = pos.side();
+        let (src, dst, pt) = match mv {
+            Move::Nondrop(nondrop) => {
+                let pt = match pos.board().cell(nondrop.src()) {
+                    BoardCell::Piece(s, pt) if s == side => pt,
+                    _ => return Err(Error::move_error(format!("csa: no piece to move: {}", mv))),
+                };
+                let pt = if nondrop.is_promotion() {
+                    pt.to_promoted().ok_or_else(|| {
+                        Error::move_error(format!("csa: not promotable piece: {}", mv))
+                    })?
+                } else {
+                    pt
+                };
+                (encode_sq(Some(nondrop.src())), nondrop.dst(), pt)
+            }
+            Move::Drop(drop) => (encode_sq(None), drop.dst(), drop.pt()),
+        };
+        res.push(side_char(side));
+        res.push_str(&src);
+        res.push_str(&encode_sq(Some(dst)));
+        res.push_str(pt_to_code(pt));
+        res.push('\n');
+        if let Some(time) = game.times.get(i).copied().flatten() {
+            res.push_str(&format!("T{}\n", time.as_secs()));
+        }
+        pos.apply_move_in_place(mv)?;
+    }
+
+    if let Some(terminal) = &game.terminal {
+        let code = TERMINALS
+            .iter()
+            .find(|&&(term, _)| term == terminal)
+            .map(|&(_, code)| code)
+            .or_else(|| Some(terminal.as_str()).filter(|t| t.starts_with('%')))
+            .ok_or_else(|| {
+                Error::encode_error(format!("csa: unsupported terminal: {}", terminal))
+            })?;
+        res.push_str(code);
+        res.push('\n');
+    }
+
+    Ok(res)
+}
+
+fn side_char(side: Side) -> char {
+    match side {
+        Side::Sente => '+',
+        Side::Gote => '-',
+    }
+}
+
+fn encode_sq(sq: Option<Square>) -> String {
+    match sq {
+        Some(sq) => format!("{}{}", sq.x() + 1, sq.y() + 1),
+        None => "00".to_owned(),
+    }
+}
@@ -0,0 +1,76 @@
+//! 入玉宣言法 (CSA 規定の 27 点法)。
+
+use crate::region::Region;
+use crate::*;
+
+/// 駒の点数。大駒 (飛角と竜馬) は 5 点、玉は 0 点、それ以外は 1 点。
+fn piece_points(pt: PieceType) -> u32 {
+    match pt.to_unpromoted() {
+        PieceType::Rook | PieceType::Bishop => 5,
+        PieceType::King => 0,
+        _ => 1,
+    }
+}
+
+impl Position {
+    /// 入玉宣言法での `side` 側の点数を返す。
+    ///
+    /// 敵陣にある `side` 側の駒 (玉を除く) と `side` 側の持駒の点数の合計。
+    pub fn declaration_points(&self, side: Side) -> u32 {
+        let zone = Region::promotion_zone(side);
+        let on_board: u32 = self
+            .board
+            .iter()
+            .filter(|&(sq, _)| zone.contains(sq))
+            .map(|(_, cell)| match cell {
+                BoardCell::Piece(s, pt) if s == side => piece_points(pt),
+                _ => 0,
+            })
+            .sum();
+        let in_hand: u32 = self
+            .hand(side)
+            .enumerate()
+            .map(|(pt, n)| piece_points(pt) * u32::from(n))
+            .sum();
+        on_board + in_hand
+    }
+
+    /// `side` 側が入玉宣言で勝ちになるかどうかを返す。
+    ///
+    /// CSA 規定の 27 点法に従い、以下を全て満たすとき true を返す (持ち時間の条件は扱わない)。
+    ///
+    /// * `side` 側の手番である。
+    /// * `side` 側の玉が敵陣にある。
+    /// * 敵陣にある `side` 側の駒 (玉を除く) が 10 枚以上ある。
+    /// * `declaration_points()` が先手は 28 点以上、後手は 27 点以上である。
+    /// * `side` 側の玉に王手がかかっていない。
+    pub fn can_declare_win(&self, side: Side) -> bool {
+        if self.side() != side {
+            return false;
+        }
+
+        let zone = Region::promotion_zone(side);
+        let king_in_zone = self.board().king(side).is_some_and(|sq| zone.contains(sq));
+        if !king_in_zone {
+            return false;
+        }
+
+        let n_in_zone = self
+            .board
+            .iter()
+            .filter(|&(sq, cell)| {
+                zone.contains(sq)
+                    && matches!(cell, BoardCell::Piece(s, pt) if s == side && pt != PieceType::King)
+            })
+            .count();
+        if n_in_zone < 10 {
+            return false;
+        }
+
+        let required = match side {
+            Side::Sente => 28,
+            Side::Gote => 27,
+        };
+        self.declaration_points(side) >= required && !self.is_check()
+    }
+}
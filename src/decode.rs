@@ -1,81 +1,129 @@
-use crate::*;
+use std::str::FromStr;
 
-const SFEN_STARTPOS: &str = "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+use crate::codec::Codec;
+use crate::*;
 
 /// sfen をパースして (局面、指し手リスト) を返す。
 /// 合法性チェックは一切行わない。
 pub fn decode(sfen: impl AsRef<str>) -> Result<(Position, Vec<Move>)> {
-    let mut tokens = sfen.as_ref().split_ascii_whitespace();
+    decode_with(&Codec::STANDARD, sfen.as_ref())
+}
+
+pub(crate) fn decode_with(codec: &Codec, sfen: &str) -> Result<(Position, Vec<Move>)> {
+    decode_with_variant(codec, Variant::Standard, sfen)
+}
+
+pub(crate) fn decode_with_variant(
+    codec: &Codec,
+    variant: Variant,
+    sfen: &str,
+) -> Result<(Position, Vec<Move>)> {
+    let mut tokens = sfen.split_ascii_whitespace();
 
-    let pos = tokens_to_pos(&mut tokens)?;
-    let mvs = tokens_to_moves(&mut tokens)?;
+    let pos = tokens_to_pos(codec, variant, &mut tokens)?;
+    let mvs = tokens_to_moves(codec, &mut tokens)?;
+
+    if let Some(&mv) = mvs.iter().find(|&&mv| !variant::move_in_board(variant, mv)) {
+        return Err(Error::decode_error(
+            ErrorCode::MoveOutOfBoard,
+            format!("move: out of board: {}", encode::encode_move(mv)),
+        ));
+    }
 
     Ok((pos, mvs))
 }
 
-fn tokens_to_pos<'a, I>(tokens: &mut I) -> Result<Position>
+fn tokens_to_pos<'a, I>(codec: &Codec, variant: Variant, tokens: &mut I) -> Result<Position>
 where
     I: Iterator<Item = &'a str>,
 {
     let mut next = || {
-        tokens
-            .next()
-            .ok_or_else(|| Error::decode_error("position: incomplete"))
+        tokens.next().ok_or_else(|| {
+            Error::decode_error(ErrorCode::PositionIncomplete, "position: incomplete")
+        })
     };
 
     let magic = next()?;
     match magic {
-        "startpos" => tokens_to_pos(&mut SFEN_STARTPOS.split_ascii_whitespace()),
+        "startpos" => tokens_to_pos(
+            &Codec::STANDARD,
+            variant,
+            &mut variant.startpos_sfen().split_ascii_whitespace(),
+        ),
         "sfen" => {
             let s_board = next()?;
             let s_side = next()?;
             let s_hands = next()?;
             let s_ply = next()?;
 
-            let board = decode_board(s_board)?;
+            let board = decode_board(codec, variant, s_board)?;
             let side = decode_side(s_side)?;
-            let (hand_sente, hand_gote) = decode_hands(s_hands)?;
+            let (hand_sente, hand_gote) = decode_hands(codec, s_hands)?;
             let ply = decode_ply(s_ply)?;
 
             Ok(Position::new(side, board, hand_sente, hand_gote, ply))
         }
-        _ => Err(Error::decode_error(format!(
-            "position: invalid magic: {}",
-            magic
-        ))),
+        _ => Err(Error::decode_error(
+            ErrorCode::PositionInvalidMagic,
+            format!("position: invalid magic: {}", magic),
+        )),
     }
 }
 
-fn decode_board(s_board: impl AsRef<str>) -> Result<Board> {
-    let s_board = s_board.as_ref();
+/// `variant` の大きさの盤面をパースする。段数、各段のマス数は盤の大きさと一致しなければならない。
+/// 盤の範囲外のマスは空きマスとなる。
+pub(crate) fn decode_board(codec: &Codec, variant: Variant, s_board: &str) -> Result<Board> {
+    let (width, height) = (variant.width() as usize, variant.height() as usize);
     let it = s_board.split('/');
 
-    let mut cells = [BoardCell::Empty; 81];
+    let mut rows = [[BoardCell::Empty; 9]; 9];
+    let mut n_row = 0;
     for (y, s_row) in it.enumerate() {
-        let row = decode_board_row(s_row)?;
-        let idx = 9 * y;
-        cells[idx..idx + 9].copy_from_slice(&row);
+        if y >= height {
+            return Err(Error::decode_error(
+                ErrorCode::BoardTooManyRows,
+                "board: too many rows",
+            ));
+        }
+        rows[y] = decode_board_row(codec, width, s_row)?;
+        n_row += 1;
+    }
+    if n_row < height {
+        return Err(Error::decode_error(
+            ErrorCode::BoardTooFewRows,
+            format!("board: too few rows: {}", n_row),
+        ));
     }
 
-    Ok(Board(cells))
+    // sfen の各行は左端の筋から1筋の順に並んでいる。
+    Ok(Board::new(|x, y| {
+        let x = x as usize;
+        if x < width {
+            rows[y as usize][width - 1 - x]
+        } else {
+            BoardCell::Empty
+        }
+    }))
 }
 
-fn decode_board_row(s_row: impl AsRef<str>) -> Result<[BoardCell; 9]> {
+fn decode_board_row(codec: &Codec, width: usize, s_row: impl AsRef<str>) -> Result<[BoardCell; 9]> {
     #[derive(Debug)]
     struct State {
         row: [BoardCell; 9],
         len: usize,
+        width: usize,
         promo: bool,
     }
     impl State {
-        fn new() -> Self {
+        fn new(width: usize) -> Self {
             Self {
                 row: [BoardCell::Empty; 9],
                 len: 0,
+                width,
                 promo: false,
             }
         }
-        fn eat(&mut self, c: char) -> Result<()> {
+        fn eat(&mut self, codec: &Codec, c: char) -> Result<()> {
             match c {
                 '+' => {
                     self.ensure_len_ok(1)?;
@@ -89,13 +137,19 @@ fn decode_board_row(s_row: impl AsRef<str>) -> Result<[BoardCell; 9]> {
                     self.len += n;
                 }
                 _ => {
-                    let (side, mut pt) = char_to_side_pt(c).ok_or_else(|| {
-                        Error::decode_error(format!("board row: invalid char: {}", c))
+                    let (side, mut pt) = codec.char_to_side_pt(c).ok_or_else(|| {
+                        Error::decode_error(
+                            ErrorCode::BoardRowInvalidChar,
+                            format!("board row: invalid char: {}", c),
+                        )
                     })?;
                     self.ensure_len_ok(1)?;
                     if self.promo {
                         pt = pt.to_promoted().ok_or_else(|| {
-                            Error::decode_error(format!("board row: not promotable piece: {}", c))
+                            Error::decode_error(
+                                ErrorCode::BoardRowNotPromotable,
+                                format!("board row: not promotable piece: {}", c),
+                            )
                         })?;
                         self.promo = false;
                     }
@@ -106,22 +160,34 @@ fn decode_board_row(s_row: impl AsRef<str>) -> Result<[BoardCell; 9]> {
             Ok(())
         }
         fn ensure_len_ok(&self, len_add: usize) -> Result<()> {
-            if self.len + len_add > 9 {
-                return Err(Error::decode_error("board row: overflow"));
+            if self.len + len_add > self.width {
+                return Err(Error::decode_error(
+                    ErrorCode::BoardRowOverflow,
+                    "board row: overflow",
+                ));
             }
             Ok(())
         }
         fn ensure_not_promo(&self) -> Result<()> {
             if self.promo {
-                return Err(Error::decode_error("board row: invalid '+'"));
+                return Err(Error::decode_error(
+                    ErrorCode::BoardRowInvalidPlus,
+                    "board row: invalid '+'",
+                ));
             }
             Ok(())
         }
     }
 
-    let mut state = State::new();
+    let mut state = State::new(width);
     for c in s_row.as_ref().chars() {
-        state.eat(c)?;
+        state.eat(codec, c)?;
+    }
+    if state.len < width {
+        return Err(Error::decode_error(
+            ErrorCode::BoardRowUnderflow,
+            format!("board row: too few squares: {}", s_row.as_ref()),
+        ));
     }
 
     Ok(state.row)
@@ -131,11 +197,14 @@ fn decode_side(s_side: impl AsRef<str>) -> Result<Side> {
     match s_side.as_ref() {
         "b" => Ok(Side::Sente),
         "w" => Ok(Side::Gote),
-        s => Err(Error::decode_error(format!("side: invalid string: {}", s))),
+        s => Err(Error::decode_error(
+            ErrorCode::SideInvalid,
+            format!("side: invalid string: {}", s),
+        )),
     }
 }
 
-fn decode_hands(s_hands: impl AsRef<str>) -> Result<(Hand, Hand)> {
+fn decode_hands(codec: &Codec, s_hands: impl AsRef<str>) -> Result<(Hand, Hand)> {
     let s_hands = s_hands.as_ref();
     if s_hands == "-" {
         return Ok((Hand::empty(), Hand::empty()));
@@ -153,29 +222,39 @@ fn decode_hands(s_hands: impl AsRef<str>) -> Result<(Hand, Hand)> {
                 cur: 0,
             }
         }
-        fn eat(&mut self, c: char) -> Result<()> {
+        fn eat(&mut self, codec: &Codec, c: char) -> Result<()> {
             match c {
                 '0'..='9' => {
-                    self.cur = self
-                        .cur
-                        .checked_mul(10)
-                        .ok_or_else(|| Error::decode_error("hands: overflow"))?;
+                    self.cur = self.cur.checked_mul(10).ok_or_else(|| {
+                        Error::decode_error(ErrorCode::HandsOverflow, "hands: overflow")
+                    })?;
                     self.cur = self
                         .cur
                         .checked_add(c.to_digit(10).expect("internal error") as u8)
-                        .ok_or_else(|| Error::decode_error("hands: overflow"))?;
+                        .ok_or_else(|| {
+                            Error::decode_error(ErrorCode::HandsOverflow, "hands: overflow")
+                        })?;
                 }
                 _ => {
-                    let (side, pt) = char_to_side_pt(c).ok_or_else(|| {
-                        Error::decode_error(format!("hands: invalid char: {}", c))
+                    let (side, pt) = codec.char_to_side_pt(c).ok_or_else(|| {
+                        Error::decode_error(
+                            ErrorCode::HandsInvalidChar,
+                            format!("hands: invalid char: {}", c),
+                        )
                     })?;
                     if !pt.is_hand() {
-                        return Err(Error::decode_error(format!("hands: not hand piece: {}", c)));
+                        return Err(Error::decode_error(
+                            ErrorCode::HandsNotHandPiece,
+                            format!("hands: not hand piece: {}", c),
+                        ));
                     }
                     if self.cur == 0 {
                         self.cur = 1;
                     }
-                    self.counts[side as usize][pt as usize] += self.cur;
+                    let count = &mut self.counts[side as usize][pt as usize];
+                    *count = count.checked_add(self.cur).ok_or_else(|| {
+                        Error::decode_error(ErrorCode::HandsOverflow, "hands: overflow")
+                    })?;
                     self.cur = 0;
                 }
             }
@@ -185,7 +264,7 @@ fn decode_hands(s_hands: impl AsRef<str>) -> Result<(Hand, Hand)> {
 
     let mut state = State::new();
     for c in s_hands.chars() {
-        state.eat(c)?;
+        state.eat(codec, c)?;
     }
 
     Ok((Hand(state.counts[0]), Hand(state.counts[1])))
@@ -195,33 +274,40 @@ fn decode_ply(s_ply: impl AsRef<str>) -> Result<i32> {
     s_ply
         .as_ref()
         .parse::<i32>()
-        .map_err(|e| Error::decode_error(format!("ply: parse error: {}", e)))
+        .map_err(|e| Error::decode_error(ErrorCode::PlyInvalid, format!("ply: parse error: {}", e)))
 }
 
-fn tokens_to_moves<'a, I>(tokens: &mut I) -> Result<Vec<Move>>
+fn tokens_to_moves<'a, I>(codec: &Codec, tokens: &mut I) -> Result<Vec<Move>>
 where
     I: Iterator<Item = &'a str>,
 {
     if let Some(magic) = tokens.next() {
         if magic != "moves" {
-            return Err(Error::decode_error(r#"moves: "moves" expected"#));
+            return Err(Error::decode_error(
+                ErrorCode::MovesKeywordExpected,
+                r#"moves: "moves" expected"#,
+            ));
         }
-        tokens.map(decode_move).collect::<Result<Vec<_>>>()
+        tokens
+            .map(|s_mv| decode_move_with(codec, s_mv))
+            .collect::<Result<Vec<_>>>()
     } else {
         Ok(Vec::new())
     }
 }
 
-fn decode_move(s_mv: impl AsRef<str>) -> Result<Move> {
-    let s_mv = s_mv.as_ref();
+pub(crate) fn decode_move(s_mv: impl AsRef<str>) -> Result<Move> {
+    decode_move_with(&Codec::STANDARD, s_mv.as_ref())
+}
 
+fn decode_move_with(codec: &Codec, s_mv: &str) -> Result<Move> {
     macro_rules! ensure {
         ($cond:expr) => {
             if !$cond {
-                return Err(Error::decode_error(format!(
-                    "move: invalid string: {}",
-                    s_mv
-                )));
+                return Err(Error::decode_error(
+                    ErrorCode::MoveInvalidString,
+                    format!("move: invalid string: {}", s_mv),
+                ));
             }
         };
     }
@@ -237,13 +323,20 @@ fn decode_move(s_mv: impl AsRef<str>) -> Result<Move> {
 
     if cs[1] == '*' {
         ensure!(cs_len == 4);
-        let pt = char_to_pt(cs[0])
-            .ok_or_else(|| Error::decode_error(format!("move: invalid piece: {}", cs[0])))?;
+        let pt = codec.char_to_pt(cs[0]).ok_or_else(|| {
+            Error::decode_error(
+                ErrorCode::MoveInvalidPiece,
+                format!("move: invalid piece: {}", cs[0]),
+            )
+        })?;
         let dst = chars_to_sq(cs[2], cs[3])?;
         Ok(Move::drop(pt, dst))
     } else {
         if cs_len == 5 && cs[4] != '+' {
-            return Err(Error::decode_error(format!("move: '+' expected: {}", s_mv)));
+            return Err(Error::decode_error(
+                ErrorCode::MovePlusExpected,
+                format!("move: '+' expected: {}", s_mv),
+            ));
         }
         let src = chars_to_sq(cs[0], cs[1])?;
         let dst = chars_to_sq(cs[2], cs[3])?;
@@ -254,38 +347,101 @@ fn decode_move(s_mv: impl AsRef<str>) -> Result<Move> {
 
 fn chars_to_sq(cx: char, cy: char) -> Result<Square> {
     if !('1'..='9').contains(&cx) {
-        return Err(Error::decode_error(format!("square: invalid x: {}", cx)));
+        return Err(Error::decode_error(
+            ErrorCode::SquareInvalidX,
+            format!("square: invalid x: {}", cx),
+        ));
     }
     if !('a'..='i').contains(&cy) {
-        return Err(Error::decode_error(format!("square: invalid y: {}", cy)));
+        return Err(Error::decode_error(
+            ErrorCode::SquareInvalidY,
+            format!("square: invalid y: {}", cy),
+        ));
     }
     let x = cx as u8 - b'1';
     let y = cy as u8 - b'a';
     Ok(Square::new(x, y))
 }
 
-fn char_to_side_pt(c: char) -> Option<(Side, PieceType)> {
-    let pt = char_to_pt(c.to_ascii_uppercase())?;
-    let side = if c.is_ascii_uppercase() {
-        Side::Sente
-    } else if c.is_ascii_lowercase() {
-        Side::Gote
-    } else {
-        panic!("internal error")
-    };
-    Some((side, pt))
+pub(crate) fn char_to_side_pt(c: char) -> Option<(Side, PieceType)> {
+    Codec::STANDARD.char_to_side_pt(c)
+}
+
+/// `decode()` と同様に sfen をパースし、さらに指し手リストを局面上で再生して合法性を検査する。
+///
+/// 非合法手 (移動元が空のマスである手などを含む) があればエラーを返す。
+/// 局面自体の妥当性は検査しない (`Position::validate()` を参照)。
+pub fn decode_strict(sfen: impl AsRef<str>) -> Result<(Position, Vec<Move>)> {
+    let (pos, mvs) = decode(sfen)?;
+
+    let mut cur = pos.clone();
+    for (i, &mv) in mvs.iter().enumerate() {
+        if !(cur.is_pseudo_legal(mv) && cur.is_legal(mv)) {
+            return Err(Error::move_error(format!(
+                "illegal move at index {}: {}",
+                i,
+                encode::encode_move(mv)
+            )));
+        }
+        cur.apply_move_in_place(mv)?;
+    }
+
+    Ok((pos, mvs))
+}
+
+/// USI の "position" コマンド ("position startpos moves 7g7f" など) をパースして
+/// (局面、指し手リスト) を返す。
+///
+/// 先頭の "position" を除いた部分は `decode()` と同様に扱う。合法性チェックは一切行わない。
+pub fn decode_usi_position(line: impl AsRef<str>) -> Result<(Position, Vec<Move>)> {
+    let line = line.as_ref().trim_start();
+    let rest = line
+        .strip_prefix("position")
+        .filter(|rest| rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_whitespace()))
+        .ok_or_else(|| {
+            Error::decode_error(
+                ErrorCode::PositionKeywordExpected,
+                format!("position: keyword expected: {}", line),
+            )
+        })?;
+    decode(rest)
 }
 
-fn char_to_pt(c: char) -> Option<PieceType> {
-    match c {
-        'P' => Some(PieceType::Pawn),
-        'L' => Some(PieceType::Lance),
-        'N' => Some(PieceType::Knight),
-        'S' => Some(PieceType::Silver),
-        'B' => Some(PieceType::Bishop),
-        'R' => Some(PieceType::Rook),
-        'G' => Some(PieceType::Gold),
-        'K' => Some(PieceType::King),
-        _ => None,
+impl FromStr for Position {
+    type Err = Error;
+
+    /// 指し手リストを含まない sfen をパースする。
+    fn from_str(s: &str) -> Result<Self> {
+        let (pos, mvs) = decode(s)?;
+        if !mvs.is_empty() || s.split_ascii_whitespace().any(|token| token == "moves") {
+            return Err(Error::decode_error(
+                ErrorCode::PositionUnexpectedMoves,
+                "position: unexpected moves",
+            ));
+        }
+        Ok(pos)
+    }
+}
+
+impl FromStr for Move {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        decode_move(s)
+    }
+}
+
+impl FromStr for Square {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut cs = s.chars();
+        match (cs.next(), cs.next(), cs.next()) {
+            (Some(cx), Some(cy), None) => chars_to_sq(cx, cy),
+            _ => Err(Error::decode_error(
+                ErrorCode::SquareInvalidString,
+                format!("square: invalid string: {}", s),
+            )),
+        }
     }
 }
@@ -0,0 +1,122 @@
+use crate::*;
+
+impl Position {
+    /// スクリーンリーダー向けの局面説明文を返す。
+    ///
+    /// 手番、各陣営の盤上の駒 (駒種ごとにマスを列挙)、持駒をこの順に1行ずつ出力する。
+    pub fn describe(&self, lang: Lang) -> String {
+        let mut lines = Vec::with_capacity(5);
+
+        lines.push(match lang {
+            Lang::English => format!("{} to move.", capitalize(self.side().name(lang))),
+            _ => format!("{}番。", self.side().name(lang)),
+        });
+
+        for &side in [Side::Sente, Side::Gote].iter() {
+            lines.push(describe_board(self.board(), side, lang));
+            lines.push(describe_hand(self.hand(side), side, lang));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// 盤上の駒を列挙する順序。
+const PTS_BOARD: [PieceType; 14] = [
+    PieceType::King,
+    PieceType::Rook,
+    PieceType::Dragon,
+    PieceType::Bishop,
+    PieceType::Horse,
+    PieceType::Gold,
+    PieceType::Silver,
+    PieceType::ProSilver,
+    PieceType::Knight,
+    PieceType::ProKnight,
+    PieceType::Lance,
+    PieceType::ProLance,
+    PieceType::Pawn,
+    PieceType::ProPawn,
+];
+
+/// 持駒を列挙する順序。
+const PTS_HAND: [PieceType; 7] = [
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Gold,
+    PieceType::Silver,
+    PieceType::Knight,
+    PieceType::Lance,
+    PieceType::Pawn,
+];
+
+fn describe_board(board: &Board, side: Side, lang: Lang) -> String {
+    let groups: Vec<_> = PTS_BOARD
+        .iter()
+        .filter_map(|&pt| {
+            // 先手から見て左上 (9一) から右下 (1九) の順に読み上げる。
+            let sqs: Vec<_> = (0..9)
+                .flat_map(|y| (0..9).rev().map(move |x| (x, y)))
+                .filter(|&(x, y)| board.at(x, y) == BoardCell::Piece(side, pt))
+                .map(|(x, y)| Square::new(x, y).name(lang))
+                .collect();
+            if sqs.is_empty() {
+                None
+            } else {
+                Some(format!("{} {}", pt.name(lang), sqs.join(" ")))
+            }
+        })
+        .collect();
+
+    let body = if groups.is_empty() {
+        none(lang).to_owned()
+    } else {
+        groups.join(sep(lang))
+    };
+
+    match lang {
+        Lang::English => format!("{}: {}.", capitalize(side.name(lang)), body),
+        _ => format!("{}の駒: {}。", side.name(lang), body),
+    }
+}
+
+fn describe_hand(hand: &Hand, side: Side, lang: Lang) -> String {
+    let items: Vec<_> = PTS_HAND
+        .iter()
+        .filter(|&&pt| hand.count(pt) > 0)
+        .map(|&pt| format!("{} {}", pt.name(lang), hand.count(pt)))
+        .collect();
+
+    let body = if items.is_empty() {
+        none(lang).to_owned()
+    } else {
+        items.join(sep(lang))
+    };
+
+    match lang {
+        Lang::English => format!("{} hand: {}.", capitalize(side.name(lang)), body),
+        _ => format!("{}の持駒: {}。", side.name(lang), body),
+    }
+}
+
+fn sep(lang: Lang) -> &'static str {
+    match lang {
+        Lang::English => "; ",
+        _ => "、",
+    }
+}
+
+fn none(lang: Lang) -> &'static str {
+    match lang {
+        Lang::English => "none",
+        _ => "なし",
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut cs = s.chars();
+    match cs.next() {
+        Some(c) => c.to_ascii_uppercase().to_string() + cs.as_str(),
+        None => String::new(),
+    }
+}
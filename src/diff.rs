@@ -0,0 +1,75 @@
+//! 2つの棋譜の比較。
+
+use std::collections::HashMap;
+
+use crate::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameDiff {
+    /// 開始局面 (手数を除く) が異なるかどうか。
+    pub start_differs: bool,
+    /// 指し手が最初に食い違う手数 (0 始まりの指し手インデックス)。
+    /// 一方が他方の接頭辞になっている場合は短い方の長さ。完全に一致する場合は None。
+    pub first_divergence: Option<usize>,
+    /// 分岐後に両方の棋譜に現れる同一局面 (手数を除く) の、
+    /// (a での指し手数, b での指し手数) のリスト。a の出現順に並ぶ。
+    pub transpositions: Vec<(usize, usize)>,
+}
+
+/// 2つの棋譜 (開始局面, 指し手リスト) を比較する。
+///
+/// 途中で適用できない指し手があった場合、エラーを返す。
+pub fn diff(
+    pos_a: &Position,
+    mvs_a: &[Move],
+    pos_b: &Position,
+    mvs_b: &[Move],
+) -> Result<GameDiff> {
+    let start_differs = pos_a.key_without_ply() != pos_b.key_without_ply();
+
+    let first_divergence = if start_differs {
+        Some(0)
+    } else {
+        let n_common = mvs_a.iter().zip(mvs_b).take_while(|(a, b)| a == b).count();
+        if n_common == mvs_a.len() && n_common == mvs_b.len() {
+            None
+        } else {
+            Some(n_common)
+        }
+    };
+
+    let transpositions = match first_divergence {
+        None => Vec::new(),
+        Some(div) => {
+            // b の分岐後の各局面について、最初に現れた指し手数を記録する。
+            let mut seen_b = HashMap::new();
+            for (i, pos) in replay(pos_b, mvs_b)?.iter().enumerate().skip(div + 1) {
+                seen_b.entry(pos.key_without_ply()).or_insert(i);
+            }
+
+            replay(pos_a, mvs_a)?
+                .iter()
+                .enumerate()
+                .skip(div + 1)
+                .filter_map(|(i, pos)| seen_b.get(&pos.key_without_ply()).map(|&j| (i, j)))
+                .collect()
+        }
+    };
+
+    Ok(GameDiff {
+        start_differs,
+        first_divergence,
+        transpositions,
+    })
+}
+
+/// 開始局面と、各指し手を適用した後の局面を順に返す。
+fn replay(pos: &Position, mvs: &[Move]) -> Result<Vec<Position>> {
+    let mut res = Vec::with_capacity(mvs.len() + 1);
+    res.push(pos.clone());
+    for &mv in mvs {
+        let next = res.last().expect("internal error").apply_move(mv)?;
+        res.push(next);
+    }
+    Ok(res)
+}
@@ -0,0 +1,70 @@
+//! 他の sfen パーサとの差分テスト。
+//!
+//! 比較対象の実装を `Reference` トレイトで包み、同じ入力をこのクレートと比較対象の両方で
+//! デコードして結果が食い違う入力を報告する。結果は `encode()` と同じ正規形の文字列で比較する。
+
+use crate::*;
+
+/// 比較対象のパーサ。
+pub trait Reference {
+    /// `input` をデコードし、`encode()` と同じ正規形の sfen 文字列に変換して返す。
+    /// デコードに失敗した場合はエラーメッセージを返す。
+    fn normalize(&self, input: &str) -> std::result::Result<String, String>;
+}
+
+impl<F> Reference for F
+where
+    F: Fn(&str) -> std::result::Result<String, String>,
+{
+    fn normalize(&self, input: &str) -> std::result::Result<String, String> {
+        self(input)
+    }
+}
+
+/// 結果が食い違った入力。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    pub input: String,
+    /// このクレートでの結果。
+    pub ours: std::result::Result<String, String>,
+    /// 比較対象での結果。
+    pub theirs: std::result::Result<String, String>,
+}
+
+/// このクレートで `input` をデコードし、正規形の sfen 文字列に変換する。
+pub fn normalize(input: &str) -> std::result::Result<String, String> {
+    decode(input)
+        .map(|(pos, mvs)| encode(&pos, &mvs))
+        .map_err(|e| e.to_string())
+}
+
+/// 各入力をこのクレートと `reference` でデコードし、結果が食い違った入力を返す。
+///
+/// 両方がエラーになった場合は (エラーメッセージが異なっていても) 一致とみなす。
+pub fn compare<'a, R, I>(reference: &R, inputs: I) -> Vec<Divergence>
+where
+    R: Reference + ?Sized,
+    I: IntoIterator<Item = &'a str>,
+{
+    inputs
+        .into_iter()
+        .filter_map(|input| {
+            let ours = normalize(input);
+            let theirs = reference.normalize(input);
+            let agree = match (&ours, &theirs) {
+                (Ok(a), Ok(b)) => a == b,
+                (Err(_), Err(_)) => true,
+                _ => false,
+            };
+            if agree {
+                None
+            } else {
+                Some(Divergence {
+                    input: input.to_owned(),
+                    ours,
+                    theirs,
+                })
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,131 @@
+use std::ops::Range;
+
+use crate::span::split_whitespace_indices;
+use crate::*;
+
+/// エディタ向けの、編集可能なパース済み sfen 文字列。
+///
+/// トークン位置とデコード結果をキャッシュし、指し手リスト部分のみの編集では
+/// 編集箇所付近の指し手だけを再パースする。
+#[derive(Debug)]
+pub struct ParsedDocument {
+    text: String,
+    spans: Vec<(Span, TokenKind)>,
+    /// `spans` 中で最初の指し手トークンのインデックス。
+    /// `moves` キーワードがない場合は `spans.len()`。
+    moves_start: usize,
+    pos: Result<Position>,
+    mvs: Vec<Result<Move>>,
+}
+
+impl ParsedDocument {
+    pub fn new(text: impl Into<String>) -> Self {
+        let mut doc = Self {
+            text: text.into(),
+            spans: Vec::new(),
+            moves_start: 0,
+            pos: Err(Error::decode_error(
+                ErrorCode::PositionIncomplete,
+                "position: incomplete",
+            )),
+            mvs: Vec::new(),
+        };
+        doc.reparse();
+        doc
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn spans(&self) -> &[(Span, TokenKind)] {
+        &self.spans
+    }
+
+    /// 局面部分 (指し手リストより前) のデコード結果を返す。
+    pub fn position(&self) -> std::result::Result<&Position, &Error> {
+        self.pos.as_ref()
+    }
+
+    /// 各指し手トークンのデコード結果を返す。
+    pub fn moves(&self) -> &[Result<Move>] {
+        &self.mvs
+    }
+
+    /// `range` の範囲のテキストを `replacement` で置き換え、再パースする。
+    ///
+    /// `range` が文字境界上にない場合、panic する。
+    pub fn apply_edit(&mut self, range: Range<usize>, replacement: &str) {
+        self.text.replace_range(range.clone(), replacement);
+
+        let kw_end = self.spans[..self.moves_start]
+            .last()
+            .filter(|(_, kind)| *kind == TokenKind::MovesKeyword)
+            .map(|(span, _)| span.end);
+        match kw_end {
+            Some(kw_end) if range.start > kw_end => self.reparse_moves(range, replacement.len()),
+            _ => self.reparse(),
+        }
+    }
+
+    fn reparse(&mut self) {
+        self.spans = parse_spans(&self.text);
+        self.moves_start = self
+            .spans
+            .iter()
+            .position(|(_, kind)| *kind == TokenKind::MovesKeyword)
+            .map_or(self.spans.len(), |i| i + 1);
+
+        let pos_end = self.spans[..self.moves_start]
+            .last()
+            .filter(|(_, kind)| *kind == TokenKind::MovesKeyword)
+            .map_or(self.text.len(), |(span, _)| span.start);
+        self.pos = decode(&self.text[..pos_end]).map(|(pos, _)| pos);
+
+        let text = &self.text;
+        self.mvs = self.spans[self.moves_start..]
+            .iter()
+            .map(|(span, _)| decode::decode_move(&text[span.range()]))
+            .collect();
+    }
+
+    /// 指し手リスト部分の編集 (旧テキストでの範囲 `range` が長さ `len_new` の文字列に
+    /// 置き換わった) について、影響を受ける指し手トークンのみを再パースする。
+    fn reparse_moves(&mut self, range: Range<usize>, len_new: usize) {
+        let delta = len_new as isize - range.len() as isize;
+        let shift = |x: usize| (x as isize + delta) as usize;
+
+        // 編集範囲に接するトークンはトークン境界が変わりうるので再パース対象とする。
+        let spans_mv = &self.spans[self.moves_start..];
+        let first = spans_mv.partition_point(|(span, _)| span.end < range.start);
+        let last = spans_mv.partition_point(|(span, _)| span.start <= range.end);
+
+        let win = if first < last {
+            let start = spans_mv[first].0.start.min(range.start);
+            let end = spans_mv[last - 1].0.end.max(range.end);
+            start..shift(end)
+        } else {
+            range.start..shift(range.end)
+        };
+
+        let tokens: Vec<_> = split_whitespace_indices(&self.text[win.clone()])
+            .map(|(start, token)| {
+                let span = Span::new(win.start + start, win.start + start + token.len());
+                (span, decode::decode_move(token))
+            })
+            .collect();
+        let n_new = tokens.len();
+
+        let idx = self.moves_start;
+        self.spans.splice(
+            idx + first..idx + last,
+            tokens.iter().map(|&(span, _)| (span, TokenKind::Move)),
+        );
+        self.mvs
+            .splice(first..last, tokens.into_iter().map(|(_, mv)| mv));
+
+        for (span, _) in &mut self.spans[idx + first + n_new..] {
+            *span = Span::new(shift(span.start), shift(span.end));
+        }
+    }
+}
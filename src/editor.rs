@@ -0,0 +1,154 @@
+//! 局面編集のための、取り消し・やり直し可能な編集履歴。
+
+use crate::*;
+
+/// 局面に対する1つの編集操作。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Edit {
+    /// マスの状態を変える (駒の配置・除去)。
+    SetCell(Square, BoardCell),
+    /// 持駒の枚数を変える。駒種は持駒になりうるものでなければならない。
+    SetHand(Side, PieceType, u8),
+    /// 手番を変える。
+    SetSide(Side),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Op {
+    edit: Edit,
+    inverse: Edit,
+}
+
+/// 編集履歴付きの局面。
+///
+/// 編集は合法性チェックなしでそのまま適用される。
+#[derive(Clone, Debug)]
+pub struct Editor {
+    pos: Position,
+    undo_stack: Vec<Op>,
+    redo_stack: Vec<Op>,
+    /// 最後に `mark_clean()` した時点の undo_stack の長さ。その状態に戻れなくなったら None。
+    clean_len: Option<usize>,
+}
+
+impl Editor {
+    pub fn new(pos: Position) -> Self {
+        Self {
+            pos,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            clean_len: Some(0),
+        }
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.pos
+    }
+
+    /// 現在の局面の sfen を返す。
+    pub fn to_sfen(&self) -> String {
+        encode(&self.pos, &[])
+    }
+
+    /// 編集を適用する。やり直し履歴は破棄される。
+    ///
+    /// `Edit::SetHand` の駒種が持駒になりえないものの場合、panic する。
+    pub fn apply(&mut self, edit: Edit) {
+        let inverse = self.apply_raw(edit);
+        self.undo_stack.push(Op { edit, inverse });
+        self.redo_stack.clear();
+        if self
+            .clean_len
+            .is_some_and(|len| len >= self.undo_stack.len())
+        {
+            self.clean_len = None;
+        }
+    }
+
+    /// マス `sq` に `side` 側の駒 `pt` を置く。
+    pub fn place(&mut self, sq: Square, side: Side, pt: PieceType) {
+        self.apply(Edit::SetCell(sq, BoardCell::Piece(side, pt)));
+    }
+
+    /// マス `sq` の駒を取り除く。
+    pub fn remove(&mut self, sq: Square) {
+        self.apply(Edit::SetCell(sq, BoardCell::Empty));
+    }
+
+    /// `side` 側の持駒 `pt` の枚数を `n` にする。
+    pub fn set_hand(&mut self, side: Side, pt: PieceType, n: u8) {
+        self.apply(Edit::SetHand(side, pt, n));
+    }
+
+    /// 手番を入れ替える。
+    pub fn flip_side(&mut self) {
+        self.apply(Edit::SetSide(self.pos.side().flip()));
+    }
+
+    /// 直前の編集を取り消す。取り消す編集がなければ false を返す。
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(op) => {
+                self.apply_raw(op.inverse);
+                self.redo_stack.push(op);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 直前に取り消した編集をやり直す。やり直す編集がなければ false を返す。
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(op) => {
+                self.apply_raw(op.edit);
+                self.undo_stack.push(op);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// 最後に `mark_clean()` した時点 (または作成時) から局面が変わっているかどうかを返す。
+    ///
+    /// 編集と取り消しで元の状態に戻った場合は false。
+    pub fn is_dirty(&self) -> bool {
+        self.clean_len != Some(self.undo_stack.len())
+    }
+
+    /// 現在の状態を保存済みとする。
+    pub fn mark_clean(&mut self) {
+        self.clean_len = Some(self.undo_stack.len());
+    }
+
+    /// 編集を適用し、その逆操作を返す。
+    fn apply_raw(&mut self, edit: Edit) -> Edit {
+        match edit {
+            Edit::SetCell(sq, cell) => {
+                let old = self.pos.board.cell(sq);
+                self.pos.board.set(sq, cell);
+                Edit::SetCell(sq, old)
+            }
+            Edit::SetHand(side, pt, n) => {
+                assert!(pt.is_hand(), "not hand piece: {:?}", pt);
+                let hand = &mut self.pos.hands[side as usize];
+                let old = hand.count(pt);
+                hand.0[pt as usize] = n;
+                Edit::SetHand(side, pt, old)
+            }
+            Edit::SetSide(side) => {
+                let old = self.pos.side;
+                self.pos.side = side;
+                Edit::SetSide(old)
+            }
+        }
+    }
+}
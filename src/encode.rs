@@ -1,22 +1,38 @@
 use std::borrow::Cow;
+use std::fmt;
 
 use itertools::Itertools;
 
+use crate::codec::Codec;
 use crate::*;
 
 pub fn encode(pos: &Position, mvs: &[Move]) -> String {
-    let s_pos = encode_pos(pos);
+    encode_with(&Codec::STANDARD, pos, mvs)
+}
+
+pub(crate) fn encode_with(codec: &Codec, pos: &Position, mvs: &[Move]) -> String {
+    encode_with_variant(codec, Variant::Standard, pos, mvs)
+}
+
+/// 盤の範囲外の駒と指し手は検査しない。
+pub(crate) fn encode_with_variant(
+    codec: &Codec,
+    variant: Variant,
+    pos: &Position,
+    mvs: &[Move],
+) -> String {
+    let s_pos = encode_pos(codec, variant, pos);
     if mvs.is_empty() {
         s_pos.into_owned()
     } else {
-        [s_pos, encode_moves(mvs)].join(" ")
+        [s_pos, encode_moves(codec, mvs)].join(" ")
     }
 }
 
-fn encode_pos(pos: &Position) -> Cow<'static, str> {
-    let s_board = encode_board(pos.board());
+fn encode_pos(codec: &Codec, variant: Variant, pos: &Position) -> Cow<'static, str> {
+    let s_board = encode_board(codec, variant, pos.board());
     let s_side = encode_side(pos.side());
-    let s_hands = encode_hands(pos.hand(Side::Sente), pos.hand(Side::Gote));
+    let s_hands = encode_hands(codec, pos.hand(Side::Sente), pos.hand(Side::Gote));
     let s_ply = encode_ply(pos.ply());
 
     ["sfen", &s_board, &s_side, &s_hands, &s_ply]
@@ -24,39 +40,38 @@ fn encode_pos(pos: &Position) -> Cow<'static, str> {
         .into()
 }
 
-fn encode_board(board: &Board) -> Cow<'static, str> {
-    board.0.chunks(9).map(encode_board_row).join("/").into()
+/// `variant` の盤の範囲内の盤面をエンコードする。
+pub(crate) fn encode_board(codec: &Codec, variant: Variant, board: &Board) -> Cow<'static, str> {
+    (0..variant.height())
+        .map(|y| encode_board_row(codec, (0..variant.width()).rev().map(|x| board.at(x, y))))
+        .join("/")
+        .into()
 }
 
-fn encode_board_row(row: &[BoardCell]) -> Cow<'static, str> {
+/// 左端の筋から1筋の順に並んだ1行分のマスをエンコードする。
+fn encode_board_row(codec: &Codec, row: impl Iterator<Item = BoardCell>) -> Cow<'static, str> {
     #[derive(Debug)]
     struct State {
         s_row: String,
         n_empty: u32,
-        idx: u32,
     }
     impl State {
         fn new() -> Self {
             Self {
                 s_row: String::with_capacity(16),
                 n_empty: 0,
-                idx: 0,
             }
         }
-        fn eat(&mut self, cell: &BoardCell) {
+        fn eat(&mut self, codec: &Codec, cell: BoardCell) {
             match cell {
                 BoardCell::Empty => {
                     self.n_empty += 1;
                 }
                 BoardCell::Piece(side, pt) => {
                     self.flush_emptys();
-                    self.s_row.push_str(&encode_piece(*side, *pt));
+                    self.s_row.push_str(&codec.encode_piece(side, pt));
                 }
             }
-            self.idx += 1;
-            if self.idx == 9 {
-                self.flush_emptys();
-            }
         }
         fn flush_emptys(&mut self) {
             if self.n_empty > 0 {
@@ -69,8 +84,9 @@ fn encode_board_row(row: &[BoardCell]) -> Cow<'static, str> {
 
     let mut state = State::new();
     for cell in row {
-        state.eat(cell);
+        state.eat(codec, cell);
     }
+    state.flush_emptys();
 
     state.s_row.into()
 }
@@ -83,7 +99,11 @@ fn encode_side(side: Side) -> Cow<'static, str> {
     .into()
 }
 
-fn encode_hands(hand_sente: &Hand, hand_gote: &Hand) -> Cow<'static, str> {
+pub(crate) fn encode_hands(
+    codec: &Codec,
+    hand_sente: &Hand,
+    hand_gote: &Hand,
+) -> Cow<'static, str> {
     const PTS: [PieceType; 7] = [
         PieceType::Rook,
         PieceType::Bishop,
@@ -108,7 +128,7 @@ fn encode_hands(hand_sente: &Hand, hand_gote: &Hand) -> Cow<'static, str> {
             if n >= 2 {
                 s_hands.push_str(&n.to_string());
             }
-            s_hands.push_str(&encode_piece(*side, *pt));
+            s_hands.push_str(&codec.encode_piece(*side, *pt));
         }
     }
 
@@ -119,17 +139,21 @@ fn encode_ply(ply: i32) -> Cow<'static, str> {
     ply.to_string().into()
 }
 
-fn encode_moves(mvs: &[Move]) -> Cow<'static, str> {
+fn encode_moves(codec: &Codec, mvs: &[Move]) -> Cow<'static, str> {
     std::iter::once("moves".into())
-        .chain(mvs.iter().copied().map(encode_move))
+        .chain(mvs.iter().map(|&mv| encode_move_with(codec, mv)))
         .join(" ")
         .into()
 }
 
-fn encode_move(mv: Move) -> Cow<'static, str> {
+pub(crate) fn encode_move(mv: Move) -> Cow<'static, str> {
+    encode_move_with(&Codec::STANDARD, mv)
+}
+
+fn encode_move_with(codec: &Codec, mv: Move) -> Cow<'static, str> {
     fn push_sq(s: &mut String, sq: Square) {
-        s.push(char::from(sq.x() + b'1'));
-        s.push(char::from(sq.y() + b'a'));
+        s.push(sq_x_char(sq));
+        s.push(sq_y_char(sq));
     }
 
     let mut s_mv = String::with_capacity(5);
@@ -143,7 +167,7 @@ fn encode_move(mv: Move) -> Cow<'static, str> {
             }
         }
         Move::Drop(drop) => {
-            s_mv.push_str(&encode_pt(drop.pt));
+            s_mv.push_str(&codec.encode_pt(drop.pt));
             s_mv.push('*');
             push_sq(&mut s_mv, drop.dst);
         }
@@ -152,30 +176,29 @@ fn encode_move(mv: Move) -> Cow<'static, str> {
     s_mv.into()
 }
 
-fn encode_piece(side: Side, pt: PieceType) -> Cow<'static, str> {
-    let s_pt = encode_pt(pt);
-    match side {
-        Side::Sente => s_pt,
-        Side::Gote => s_pt.to_ascii_lowercase().into(),
+fn sq_x_char(sq: Square) -> char {
+    char::from(sq.x() + b'1')
+}
+
+fn sq_y_char(sq: Square) -> char {
+    char::from(sq.y() + b'a')
+}
+
+/// 指し手リストを含まない sfen を出力する。
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&encode_pos(&Codec::STANDARD, Variant::Standard, self))
     }
 }
 
-fn encode_pt(pt: PieceType) -> Cow<'static, str> {
-    match pt {
-        PieceType::Pawn => "P",
-        PieceType::Lance => "L",
-        PieceType::Knight => "N",
-        PieceType::Silver => "S",
-        PieceType::Bishop => "B",
-        PieceType::Rook => "R",
-        PieceType::Gold => "G",
-        PieceType::King => "K",
-        PieceType::ProPawn => "+P",
-        PieceType::ProLance => "+L",
-        PieceType::ProKnight => "+N",
-        PieceType::ProSilver => "+S",
-        PieceType::Horse => "+B",
-        PieceType::Dragon => "+R",
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&encode_move(*self))
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", sq_x_char(*self), sq_y_char(*self))
     }
-    .into()
 }
@@ -0,0 +1,62 @@
+//! ニューラルネットワーク入力用の特徴量テンソル。
+//!
+//! 1局面あたり `N_PLANE` 枚の 9x9 平面からなる。平面の並びは以下の通り:
+//!
+//! * 0..28: 盤上の駒。`14 * 陣営 + 駒種` 番目の平面で、その駒があるマスが 1.0
+//! * 28..42: 持駒。`28 + 7 * 陣営 + 駒種` 番目の平面で、全マスが枚数の値
+//! * 42: 手番。後手番なら全マスが 1.0
+//!
+//! 陣営は先手が 0、後手が 1。駒種の番号は `PieceType` の discriminant (歩 = 0, ..., 龍 = 13)。
+//! 平面内の要素 `9 * y + x` がマス (x, y) に対応する (x = 0 が1筋、y = 0 が1段目)。
+
+use crate::*;
+
+/// 1局面あたりの平面数。
+pub const N_PLANE: usize = 43;
+
+const PLANE_SIZE: usize = 81;
+
+/// 局面列を (shape, data) の形のテンソルに変換する。
+///
+/// shape は `[局面数, N_PLANE, 9, 9]` で、data は row-major で格納される。
+pub fn to_tensor<'a>(positions: impl IntoIterator<Item = &'a Position>) -> ([usize; 4], Vec<f32>) {
+    let mut data = Vec::new();
+    let mut n = 0;
+
+    for pos in positions {
+        let base = data.len();
+        data.resize(base + N_PLANE * PLANE_SIZE, 0.0);
+        write_planes(pos, &mut data[base..]);
+        n += 1;
+    }
+
+    ([n, N_PLANE, 9, 9], data)
+}
+
+fn write_planes(pos: &Position, planes: &mut [f32]) {
+    for y in 0..9 {
+        for x in 0..9 {
+            if let BoardCell::Piece(side, pt) = pos.board().at(x, y) {
+                let plane = 14 * side as usize + pt as usize;
+                planes[PLANE_SIZE * plane + 9 * y as usize + x as usize] = 1.0;
+            }
+        }
+    }
+
+    for &side in [Side::Sente, Side::Gote].iter() {
+        for (pt, n) in pos.hand(side).enumerate() {
+            let plane = 28 + 7 * side as usize + pt as usize;
+            fill_plane(planes, plane, f32::from(n));
+        }
+    }
+
+    if pos.side() == Side::Gote {
+        fill_plane(planes, 42, 1.0);
+    }
+}
+
+fn fill_plane(planes: &mut [f32], plane: usize, value: f32) {
+    for v in &mut planes[PLANE_SIZE * plane..PLANE_SIZE * (plane + 1)] {
+        *v = value;
+    }
+}
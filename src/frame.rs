@@ -0,0 +1,169 @@
+//! バイナリレコード列のための、チェックサム付きフレーム形式。
+//!
+//! 各レコードは以下の形で書き出される:
+//!
+//! * 同期マーカー `b"SFRM"` (4 byte)
+//! * ペイロード長 (u32 little endian)
+//! * ペイロードの CRC-32 (u32 little endian)
+//! * ペイロード
+//!
+//! 読み込み時は CRC-32 を検証する。`ReadMode::Skip` では壊れたレコードを読み飛ばし、
+//! 次の同期マーカーから読み込みを再開する。
+
+use std::io::{self, Read, Write};
+
+use crate::crc32::crc32;
+
+const SYNC: &[u8; 4] = b"SFRM";
+const HEADER_LEN: usize = 12;
+
+/// 1レコードのペイロード長の上限。これを超える長さはヘッダの破損とみなす。
+pub const MAX_PAYLOAD_LEN: usize = 1 << 28;
+
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    wtr: W,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(wtr: W) -> Self {
+        Self { wtr }
+    }
+
+    /// 1レコードを書き出す。
+    ///
+    /// `payload` の長さが `MAX_PAYLOAD_LEN` を超える場合、エラーを返す。
+    pub fn write_record(&mut self, payload: &[u8]) -> io::Result<()> {
+        if payload.len() > MAX_PAYLOAD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame: payload too large",
+            ));
+        }
+
+        self.wtr.write_all(SYNC)?;
+        self.wtr.write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.wtr.write_all(&crc32(payload).to_le_bytes())?;
+        self.wtr.write_all(payload)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.wtr.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.wtr
+    }
+}
+
+/// 壊れたレコードに遭遇したときの挙動。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReadMode {
+    /// `io::ErrorKind::InvalidData` のエラーを返す。
+    Strict,
+    /// 読み飛ばして次のレコードを探す。
+    Skip,
+}
+
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    rdr: R,
+    mode: ReadMode,
+    buf: Vec<u8>,
+    eof: bool,
+    n_skipped_byte: u64,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(rdr: R, mode: ReadMode) -> Self {
+        Self {
+            rdr,
+            mode,
+            buf: Vec::new(),
+            eof: false,
+            n_skipped_byte: 0,
+        }
+    }
+
+    /// `ReadMode::Skip` で読み飛ばしたバイト数を返す。
+    pub fn skipped_bytes(&self) -> u64 {
+        self.n_skipped_byte
+    }
+
+    /// 次のレコードのペイロードを返す。ストリームの終端に達した場合、None を返す。
+    pub fn read_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            self.fill(HEADER_LEN)?;
+            if self.buf.is_empty() {
+                return Ok(None);
+            }
+            if self.buf.len() < HEADER_LEN {
+                self.corrupt("frame: truncated header", self.buf.len())?;
+                continue;
+            }
+
+            if &self.buf[..4] != SYNC {
+                self.corrupt("frame: sync marker not found", 1)?;
+                continue;
+            }
+
+            let len = u32::from_le_bytes([self.buf[4], self.buf[5], self.buf[6], self.buf[7]]);
+            let len = len as usize;
+            if len > MAX_PAYLOAD_LEN {
+                self.corrupt("frame: invalid payload length", 1)?;
+                continue;
+            }
+            let crc = u32::from_le_bytes([self.buf[8], self.buf[9], self.buf[10], self.buf[11]]);
+
+            self.fill(HEADER_LEN + len)?;
+            if self.buf.len() < HEADER_LEN + len {
+                // 末尾のレコードが途中で切れている可能性もあるが、長さフィールドの破損の
+                // 可能性もあるので、1 byte だけ進めて同期マーカーを探し直す。
+                self.corrupt("frame: truncated payload", 1)?;
+                continue;
+            }
+            if crc32(&self.buf[HEADER_LEN..HEADER_LEN + len]) != crc {
+                self.corrupt("frame: checksum mismatch", 1)?;
+                continue;
+            }
+
+            let payload = self.buf[HEADER_LEN..HEADER_LEN + len].to_vec();
+            self.buf.drain(..HEADER_LEN + len);
+            return Ok(Some(payload));
+        }
+    }
+
+    /// 壊れたデータへの対処を行う。Skip モードでは先頭 `n_skip` byte を捨てる。
+    fn corrupt(&mut self, msg: &'static str, n_skip: usize) -> io::Result<()> {
+        match self.mode {
+            ReadMode::Strict => Err(io::Error::new(io::ErrorKind::InvalidData, msg)),
+            ReadMode::Skip => {
+                self.buf.drain(..n_skip);
+                self.n_skipped_byte += n_skip as u64;
+                Ok(())
+            }
+        }
+    }
+
+    /// バッファが `len` byte 以上になるか終端に達するまで読み込む。
+    fn fill(&mut self, len: usize) -> io::Result<()> {
+        let mut chunk = [0; 8192];
+        while !self.eof && self.buf.len() < len {
+            match self.rdr.read(&mut chunk) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
@@ -0,0 +1,102 @@
+//! 駒落ちの開始局面。
+
+use crate::*;
+
+/// 手合割。
+///
+/// 駒落ちの局面では上手 (駒を落とす側) が後手で、上手から指す。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Handicap {
+    /// 平手
+    Even,
+    /// 香落ち (1一の香を落とす)
+    Lance,
+    /// 右香落ち (9一の香を落とす)
+    RightLance,
+    /// 角落ち
+    Bishop,
+    /// 飛車落ち
+    Rook,
+    /// 飛香落ち
+    RookLance,
+    /// 二枚落ち
+    TwoPieces,
+    /// 三枚落ち
+    ThreePieces,
+    /// 四枚落ち
+    FourPieces,
+    /// 六枚落ち
+    SixPieces,
+    /// 八枚落ち
+    EightPieces,
+    /// 十枚落ち
+    TenPieces,
+}
+
+impl Handicap {
+    pub const ALL: [Self; 12] = [
+        Self::Even,
+        Self::Lance,
+        Self::RightLance,
+        Self::Bishop,
+        Self::Rook,
+        Self::RookLance,
+        Self::TwoPieces,
+        Self::ThreePieces,
+        Self::FourPieces,
+        Self::SixPieces,
+        Self::EightPieces,
+        Self::TenPieces,
+    ];
+
+    /// KIF 形式の手合割の名前を返す。
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Even => "平手",
+            Self::Lance => "香落ち",
+            Self::RightLance => "右香落ち",
+            Self::Bishop => "角落ち",
+            Self::Rook => "飛車落ち",
+            Self::RookLance => "飛香落ち",
+            Self::TwoPieces => "二枚落ち",
+            Self::ThreePieces => "三枚落ち",
+            Self::FourPieces => "四枚落ち",
+            Self::SixPieces => "六枚落ち",
+            Self::EightPieces => "八枚落ち",
+            Self::TenPieces => "十枚落ち",
+        }
+    }
+
+    /// KIF 形式の手合割の名前から手合割を得る。
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|h| h.name() == name)
+    }
+
+    /// 開始局面の sfen を返す。
+    pub fn sfen(self) -> &'static str {
+        match self {
+            Self::Even => "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            Self::Lance => "sfen lnsgkgsn1/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::RightLance => {
+                "sfen 1nsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"
+            }
+            Self::Bishop => "sfen lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::Rook => "sfen lnsgkgsnl/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::RookLance => "sfen lnsgkgsn1/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::TwoPieces => "sfen lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::ThreePieces => "sfen lnsgkgsn1/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::FourPieces => "sfen 1nsgkgsn1/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::SixPieces => "sfen 2sgkgs2/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::EightPieces => "sfen 3gkg3/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            Self::TenPieces => "sfen 4k4/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+        }
+    }
+}
+
+impl Position {
+    /// 手合割 `handicap` の開始局面を返す。
+    pub fn handicap(handicap: Handicap) -> Self {
+        let (pos, _) = decode(handicap.sfen()).expect("internal error");
+        pos
+    }
+}
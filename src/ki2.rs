@@ -0,0 +1,275 @@
+//! KI2 形式の棋譜。
+//!
+//! KI2 形式は移動元のマスを持たず、同種の駒を "右", "上" などの修飾で区別する
+//! (`relative::RelativeMove` を参照)。そのため読み書きには局面上での指し手の再生が必要となる。
+//! ヘッダは KIF 形式と共通で、棋譜は `kif::Game` で表す。
+
+use crate::kif::{self, Game};
+use crate::notation::{DecodeNotation, EncodeNotation};
+use crate::relative::{Motion, RelativeMove, RelativePos};
+use crate::*;
+
+/// 1行あたりの指し手数。
+const MOVES_PER_LINE: usize = 6;
+
+/// KI2 形式。`notation::convert()` などで使う。
+///
+/// 読み込みではヘッダと終局の表記を捨てる。
+/// 書き出しは `encode()` と同じで、ヘッダは出力しない。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Ki2;
+
+impl EncodeNotation for Ki2 {
+    fn encode_notation(pos: &Position, mvs: &[Move]) -> Result<String> {
+        encode(&Game::new(pos.clone(), mvs.to_vec()))
+    }
+}
+
+impl DecodeNotation for Ki2 {
+    fn decode_notation(s: &str) -> Result<(Position, Vec<Move>)> {
+        let game = decode(s)?;
+        Ok((game.position, game.moves))
+    }
+}
+
+/// KI2 形式の棋譜をパースする。
+///
+/// 文字コードの変換は行わないので、Shift_JIS のファイルは事前に変換しておくこと。
+/// コメント行と変化 (分岐) は無視する。消費時間は全て None となる。
+/// 末尾の "まで77手で先手の勝ち" のような行は、勝敗が記されていれば "投了"、
+/// "中断" などの終局表記があればその表記として `terminal` に格納する。
+pub fn decode(ki2: impl AsRef<str>) -> Result<Game> {
+    let mut moves = Vec::new();
+    let mut terminal = None;
+
+    let mut lines = ki2.as_ref().lines().map(str::trim_end).peekable();
+    let (headers, position) = kif::decode_headers(&mut lines, is_move_line)?;
+
+    // 指し手
+    let mut pos = position.clone();
+    let mut last_dst = None;
+    for line in lines {
+        if kif::is_comment(line) {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("まで") {
+            terminal = decode_terminal(rest);
+            break;
+        }
+        if !is_move_line(line) {
+            break;
+        }
+        // "同　銀" のように指し手中に空白を含みうるので、手番の記号で区切る。
+        let starts: Vec<_> = line
+            .match_indices(SIDE_MARKS.as_ref())
+            .map(|(i, _)| i)
+            .chain(std::iter::once(line.len()))
+            .collect();
+        for w in starts.windows(2) {
+            let token = kif::trim_ja(&line[w[0]..w[1]]);
+            let mv = decode_move(token, &pos, last_dst)?;
+            pos.apply_move_in_place(mv)?;
+            last_dst = Some(kif::move_dst(mv));
+            moves.push(mv);
+        }
+    }
+
+    let times = vec![None; moves.len()];
+    Ok(Game {
+        headers,
+        position,
+        moves,
+        times,
+        terminal,
+    })
+}
+
+fn is_move_line(line: &str) -> bool {
+    line.trim_start().starts_with(SIDE_MARKS.as_ref())
+}
+
+const SIDE_MARKS: [char; 5] = ['▲', '△', '▽', '☗', '☖'];
+
+fn decode_terminal(s: &str) -> Option<String> {
+    if s.ends_with("の勝ち") {
+        return Some("投了".to_owned());
+    }
+    kif::TERMINALS
+        .iter()
+        .find(|&&term| s.ends_with(term))
+        .map(|&term| term.to_owned())
+}
+
+/// "▲７六歩" や "△同　銀右" のような指し手を、局面 `pos` の指し手に変換する。
+fn decode_move(token: &str, pos: &Position, last_dst: Option<Square>) -> Result<Move> {
+    let err = || Error::decode_error(ErrorCode::Ki2, format!("ki2: invalid move: {}", token));
+
+    let mut cs = token.chars();
+    let side = match cs.next() {
+        Some('▲') | Some('☗') => Side::Sente,
+        Some('△') | Some('▽') | Some('☖') => Side::Gote,
+        _ => return Err(err()),
+    };
+    if side != pos.side() {
+        return Err(Error::decode_error(
+            ErrorCode::Ki2,
+            format!("ki2: wrong side to move: {}", token),
+        ));
+    }
+
+    let body = cs.as_str();
+    let (dst, rest) = if let Some(rest) = body.strip_prefix('同') {
+        (last_dst.ok_or_else(err)?, kif::trim_ja(rest))
+    } else {
+        let mut cs = body.chars();
+        let x = cs.next().and_then(kif::file_from_char).ok_or_else(err)?;
+        let y = cs.next().and_then(kif::rank_from_char).ok_or_else(err)?;
+        (Square::new(x, y), cs.as_str())
+    };
+
+    let (pt, mut rest) = kif::split_piece(rest).ok_or_else(err)?;
+
+    let mut eat = |s: &str| match rest.strip_prefix(s) {
+        Some(r) => {
+            rest = r;
+            true
+        }
+        None => false,
+    };
+    let relative = [RelativePos::Left, RelativePos::Right, RelativePos::Straight]
+        .iter()
+        .copied()
+        .find(|r| eat(r.name()));
+    let motion = [Motion::Up, Motion::Down, Motion::Sideways]
+        .iter()
+        .copied()
+        .find(|m| eat(m.name()));
+    let (promotion, is_drop) = if eat("不成") {
+        (Some(false), false)
+    } else if eat("成") {
+        (Some(true), false)
+    } else if eat("打") {
+        (None, true)
+    } else {
+        (None, false)
+    };
+    if !rest.is_empty() {
+        return Err(err());
+    }
+
+    RelativeMove {
+        pt,
+        dst,
+        motion,
+        relative,
+        promotion,
+        is_drop,
+    }
+    .to_move(pos)
+}
+
+/// 棋譜を KI2 形式で出力する。座標は全角で表記する。
+///
+/// `encode_with_style()` を参照。
+pub fn encode(game: &Game) -> Result<String> {
+    encode_with_style(game, CoordStyle::FullWidth)
+}
+
+/// 棋譜を、指定した座標の文字種で KI2 形式で出力する。
+///
+/// 開始局面は手合割の開始局面なら手合割で、そうでなければ盤面図 (BOD 形式) で表す。
+/// 指し手は局面上で再生され、非合法手や修飾で区別できない手があればエラーを返す。
+/// 消費時間は出力しない。
+pub fn encode_with_style(game: &Game, style: CoordStyle) -> Result<String> {
+    let mut res = kif::encode_headers(game, style);
+
+    let mut pos = game.position.clone();
+    let mut last_dst = None;
+    for chunk in game.moves.chunks(MOVES_PER_LINE) {
+        let mut line = String::new();
+        for (j, &mv) in chunk.iter().enumerate() {
+            let s_mv = encode_move(&pos, mv, last_dst, style)?;
+            line.push_str(&s_mv);
+            if j + 1 < chunk.len() {
+                line.push_str(&" ".repeat(12usize.saturating_sub(kif::display_width(&s_mv))));
+            }
+            pos.apply_move_in_place(mv)?;
+            last_dst = Some(kif::move_dst(mv));
+        }
+        res.push_str(&line);
+        res.push('\n');
+    }
+
+    if let Some(terminal) = &game.terminal {
+        let n = game.moves.len();
+        let result = if terminal == "投了" {
+            format!("{}の勝ち", pos.side().flip().name(Lang::Japanese))
+        } else {
+            terminal.clone()
+        };
+        res.push_str(&format!("まで{}手で{}\n", n, result));
+    }
+
+    Ok(res)
+}
+
+impl Move {
+    /// 局面 `pos` でのこの指し手を "▲７六歩" のような日本語の表記にする。
+    ///
+    /// 表記は KI2 形式と同じで、必要に応じて "右" "上" などの修飾、"成" "不成"、"打" が付く。
+    /// 非合法手や修飾で区別できない手であればエラーを返す。
+    /// 直前の指し手を考慮した "同" の表記は `to_japanese_after()` を参照。
+    pub fn to_japanese(&self, pos: &Position) -> Result<String> {
+        encode_move(pos, *self, None, CoordStyle::FullWidth)
+    }
+
+    /// `to_japanese()` と同様だが、直前の指し手 `prev` と移動先が同じなら "△同　銀" のように表記する。
+    pub fn to_japanese_after(&self, pos: &Position, prev: Option<Move>) -> Result<String> {
+        encode_move(pos, *self, prev.map(kif::move_dst), CoordStyle::FullWidth)
+    }
+}
+
+/// 局面 `pos` での指し手 `mv` を KI2 形式の表記 ("▲７六歩", "△同　銀右" など) にする。
+fn encode_move(
+    pos: &Position,
+    mv: Move,
+    last_dst: Option<Square>,
+    style: CoordStyle,
+) -> Result<String> {
+    let rel = RelativeMove::from_move(pos, mv)?;
+
+    let mut res = String::new();
+    res.push(match pos.side() {
+        Side::Sente => '▲',
+        Side::Gote => '△',
+    });
+    if last_dst == Some(rel.dst) {
+        res.push_str("同　");
+    } else {
+        res.push_str(&rel.dst.coord(style));
+    }
+    res.push_str(rel.pt.name(Lang::Japanese));
+    if let Some(relative) = rel.relative {
+        res.push_str(relative.name());
+    }
+    if let Some(motion) = rel.motion {
+        res.push_str(motion.name());
+    }
+    match rel.promotion {
+        Some(true) => res.push('成'),
+        Some(false) => res.push_str("不成"),
+        None => {}
+    }
+    // 盤上の駒を動かす手と紛れる場合のみ "打" を付ける。
+    if rel.is_drop {
+        let implicit = RelativeMove {
+            is_drop: false,
+            ..rel
+        };
+        if implicit.to_move(pos).ok() != Some(mv) {
+            res.push('打');
+        }
+    }
+
+    Ok(res)
+}
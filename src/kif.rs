@@ -0,0 +1,448 @@
+//! KIF 形式の棋譜。
+
+use std::time::Duration;
+
+use crate::notation::{DecodeNotation, EncodeNotation};
+use crate::*;
+
+/// 棋譜。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Game {
+    /// ヘッダ (手合割を除く) のキーと値。出現順に並ぶ。
+    pub headers: Vec<(String, String)>,
+    /// 開始局面。
+    pub position: Position,
+    pub moves: Vec<Move>,
+    /// 各指し手の消費時間。`moves` と同じ長さで、不明なものは None。
+    pub times: Vec<Option<Duration>>,
+    /// 終局の表記 ("投了" など)。
+    pub terminal: Option<String>,
+}
+
+impl Game {
+    /// ヘッダと消費時間のない棋譜を作る。
+    pub fn new(position: Position, moves: Vec<Move>) -> Self {
+        let times = vec![None; moves.len()];
+        Self {
+            headers: Vec::new(),
+            position,
+            moves,
+            times,
+            terminal: None,
+        }
+    }
+}
+
+/// KIF 形式。`notation::convert()` などで使う。
+///
+/// 読み込みではヘッダ、消費時間、終局の表記を捨てる。
+/// 書き出しは `encode()` と同じで、ヘッダと消費時間は出力しない。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Kif;
+
+impl EncodeNotation for Kif {
+    fn encode_notation(pos: &Position, mvs: &[Move]) -> Result<String> {
+        encode(&Game::new(pos.clone(), mvs.to_vec()))
+    }
+}
+
+impl DecodeNotation for Kif {
+    fn decode_notation(s: &str) -> Result<(Position, Vec<Move>)> {
+        let game = decode(s)?;
+        Ok((game.position, game.moves))
+    }
+}
+
+/// 終局を表す指し手欄の表記。
+pub(crate) const TERMINALS: [&str; 12] = [
+    "中断",
+    "投了",
+    "持将棋",
+    "千日手",
+    "詰み",
+    "切れ負け",
+    "反則勝ち",
+    "反則負け",
+    "入玉勝ち",
+    "不戦勝",
+    "不戦敗",
+    "不詰",
+];
+
+/// KIF 形式の棋譜をパースする。
+///
+/// 文字コードの変換は行わないので、Shift_JIS のファイルは事前に変換しておくこと。
+/// 開始局面は盤面図 (BOD 形式) があればそれを、なければ手合割を用いる。
+/// コメント行と変化 (分岐) は無視する。
+/// 指し手は局面上で再生され、駒の種類が盤面と合わない手や形式的に不正な手はエラーとなる
+/// (利きや王手放置などの合法性は検査しない)。
+pub fn decode(kif: impl AsRef<str>) -> Result<Game> {
+    let mut moves = Vec::new();
+    let mut times = Vec::new();
+    let mut terminal = None;
+
+    let mut lines = kif.as_ref().lines().map(|line| line.trim_end()).peekable();
+    let (headers, position) = decode_headers(&mut lines, is_move_line)?;
+
+    // 指し手
+    let mut pos = position.clone();
+    let mut last_dst = None;
+    for line in lines {
+        if is_comment(line) {
+            continue;
+        }
+        if !is_move_line(line) {
+            break;
+        }
+        match decode_move_line(line, &pos, last_dst)? {
+            Entry::Move(mv, time) => {
+                pos.apply_move_in_place(mv)?;
+                last_dst = Some(move_dst(mv));
+                moves.push(mv);
+                times.push(time);
+            }
+            Entry::Terminal(s) => {
+                terminal = Some(s);
+                break;
+            }
+        }
+    }
+
+    Ok(Game {
+        headers,
+        position,
+        moves,
+        times,
+        terminal,
+    })
+}
+
+/// 棋譜を KIF 形式で出力する。座標は全角で表記する。
+///
+/// `encode_with_style()` を参照。
+pub fn encode(game: &Game) -> Result<String> {
+    encode_with_style(game, CoordStyle::FullWidth)
+}
+
+/// 棋譜を、指定した座標の文字種で KIF 形式で出力する。
+///
+/// 開始局面は手合割の開始局面なら手合割で、そうでなければ盤面図 (BOD 形式) で表す。
+/// 指し手は局面上で再生され、形式的に不正な手があればエラーを返す。
+/// 消費時間が None の指し手は消費時間を出力しない。
+pub fn encode_with_style(game: &Game, style: CoordStyle) -> Result<String> {
+    let mut res = encode_headers(game, style);
+    res.push_str("手数----指手---------消費時間--\n");
+
+    let mut pos = game.position.clone();
+    let mut last_dst = None;
+    let mut totals = [Duration::default(); 2];
+    for (i, &mv) in game.moves.iter().enumerate() {
+        let s_mv = encode_move(&pos, mv, last_dst, style)?;
+        let line = format!("{:>4} {}", i + 1, s_mv);
+        match game.times.get(i).copied().flatten() {
+            Some(time) => {
+                let total = &mut totals[pos.side() as usize];
+                *total += time;
+                res.push_str(&format!(
+                    "{}{} ({:>2}:{:02}/{})\n",
+                    line,
+                    " ".repeat(14usize.saturating_sub(display_width(&s_mv))),
+                    time.as_secs() / 60,
+                    time.as_secs() % 60,
+                    format_hms(*total)
+                ));
+            }
+            None => {
+                res.push_str(&line);
+                res.push('\n');
+            }
+        }
+        pos.apply_move_in_place(mv)?;
+        last_dst = Some(move_dst(mv));
+    }
+
+    if let Some(terminal) = &game.terminal {
+        res.push_str(&format!("{:>4} {}\n", game.moves.len() + 1, terminal));
+    }
+
+    Ok(res)
+}
+
+/// ヘッダ行と開始局面を出力する (KI2 形式と共通)。
+///
+/// 開始局面が手合割の開始局面なら手合割の行を、そうでなければ盤面図 (BOD 形式) を出力する。
+pub(crate) fn encode_headers(game: &Game, style: CoordStyle) -> String {
+    let mut res = String::new();
+    for (key, value) in &game.headers {
+        res.push_str(&format!("{}：{}\n", key, value));
+    }
+
+    let handicap = Handicap::ALL
+        .iter()
+        .copied()
+        .find(|h| crate::decode(h.sfen()).expect("internal error").0 == game.position);
+    match handicap {
+        Some(handicap) => res.push_str(&format!("手合割：{}\n", handicap.name())),
+        None => res.push_str(&bod::encode_with_style(&game.position, style)),
+    }
+
+    res
+}
+
+/// 局面 `pos` での指し手 `mv` を KIF 形式の表記 ("７六歩(77)", "同　角成(88)" など) にする。
+fn encode_move(
+    pos: &Position,
+    mv: Move,
+    last_dst: Option<Square>,
+    style: CoordStyle,
+) -> Result<String> {
+    let side = pos.side();
+    let dst = move_dst(mv);
+    let mut res = if last_dst == Some(dst) {
+        "同　".to_owned()
+    } else {
+        dst.coord(style)
+    };
+
+    match mv {
+        Move::Nondrop(nondrop) => {
+            let src = nondrop.src();
+            let pt = match pos.board().cell(src) {
+                BoardCell::Piece(s, pt) if s == side => pt,
+                _ => return Err(Error::move_error(format!("kif: no piece to move: {}", mv))),
+            };
+            res.push_str(pt.name(Lang::Japanese));
+            if nondrop.is_promotion() {
+                res.push('成');
+            } else if movegen::can_promote(pt, src, dst, side) {
+                res.push_str("不成");
+            }
+            res.push_str(&format!("({}{})", src.x() + 1, src.y() + 1));
+        }
+        Move::Drop(drop) => {
+            res.push_str(drop.pt().name(Lang::Japanese));
+            res.push('打');
+        }
+    }
+
+    Ok(res)
+}
+
+/// 半角を幅 1、それ以外を幅 2 とした表示幅。
+pub(crate) fn display_width(s: &str) -> usize {
+    s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+fn format_hms(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+enum Entry {
+    Move(Move, Option<Duration>),
+    Terminal(String),
+}
+
+/// 空行、コメント行、しおりの行かどうか (KI2 形式と共通)。
+pub(crate) fn is_comment(line: &str) -> bool {
+    line.is_empty() || line.starts_with(['#', '*', '&'].as_ref())
+}
+
+fn is_move_line(line: &str) -> bool {
+    // 盤面図の筋の行 ("  9  8  7 ...") は除く。
+    line.trim_start().starts_with(|c: char| c.is_ascii_digit())
+        && !line.chars().all(|c| c.is_ascii_digit() || c == ' ')
+}
+
+/// 指し手の行の手前までのヘッダと開始局面を読む (KI2 形式と共通)。
+///
+/// 開始局面は盤面図 (BOD 形式) があればそれを、なければ手合割を用いる。
+pub(crate) fn decode_headers<'a, I>(
+    lines: &mut std::iter::Peekable<I>,
+    is_move_line: fn(&str) -> bool,
+) -> Result<(Vec<(String, String)>, Position)>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut headers = Vec::new();
+    let mut handicap = Handicap::Even;
+    let mut bod_lines = Vec::new();
+
+    while let Some(&line) = lines.peek() {
+        if is_move_line(line) {
+            break;
+        }
+        lines.next();
+        if is_comment(line) {
+            continue;
+        }
+        if bod::is_bod_line(line) {
+            bod_lines.push(line);
+        } else {
+            read_header(line, &mut headers, &mut handicap)?;
+        }
+    }
+
+    let position = if bod_lines.is_empty() {
+        crate::decode(handicap.sfen()).expect("internal error").0
+    } else {
+        bod::decode(bod_lines.join("\n"))?
+    };
+
+    Ok((headers, position))
+}
+
+/// ヘッダ行を読み、手合割なら `handicap` に、それ以外なら `headers` に格納する。
+///
+/// "キー：値" 形式でない行は無視する。
+fn read_header(
+    line: &str,
+    headers: &mut Vec<(String, String)>,
+    handicap: &mut Handicap,
+) -> Result<()> {
+    if let Some((key, value)) = split_header(line) {
+        if key == "手合割" {
+            *handicap = Handicap::from_name(value).ok_or_else(|| {
+                Error::decode_error(
+                    ErrorCode::Kif,
+                    format!("kif: unsupported handicap: {}", value),
+                )
+            })?;
+        } else {
+            headers.push((key.to_owned(), value.to_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// "キー：値" 形式のヘッダ行を分割する。
+fn split_header(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_at(line.find('：')?);
+    Some((key.trim(), trim_ja(&value['：'.len_utf8()..])))
+}
+
+/// 半角・全角の空白を取り除く。
+pub(crate) fn trim_ja(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_whitespace() || c == '　')
+}
+
+fn decode_move_line(line: &str, pos: &Position, last_dst: Option<Square>) -> Result<Entry> {
+    let err = || Error::decode_error(ErrorCode::Kif, format!("kif: invalid move line: {}", line));
+
+    let line = line.trim_start();
+    let body = trim_ja(line.trim_start_matches(|c: char| c.is_ascii_digit()));
+
+    if let Some(&term) = TERMINALS.iter().find(|&&term| body.starts_with(term)) {
+        return Ok(Entry::Terminal(term.to_owned()));
+    }
+
+    let mut cs = body.chars().peekable();
+
+    let dst = if cs.peek() == Some(&'同') {
+        cs.next();
+        while cs.peek() == Some(&'　') || cs.peek() == Some(&' ') {
+            cs.next();
+        }
+        last_dst.ok_or_else(err)?
+    } else {
+        let x = cs.next().and_then(file_from_char).ok_or_else(err)?;
+        let y = cs.next().and_then(rank_from_char).ok_or_else(err)?;
+        Square::new(x, y)
+    };
+
+    let rest: String = cs.collect();
+    let (pt, rest) = split_piece(&rest).ok_or_else(err)?;
+
+    let (is_promotion, is_drop, rest) = if let Some(rest) = rest.strip_prefix("不成") {
+        (false, false, rest)
+    } else if let Some(rest) = rest.strip_prefix('成') {
+        (true, false, rest)
+    } else if let Some(rest) = rest.strip_prefix('打') {
+        (false, true, rest)
+    } else {
+        (false, false, rest)
+    };
+
+    // 移動元の表記がなく、打つ駒を持っていれば "打" が省略された駒打ちとみなす。
+    let (src, rest) = if is_drop {
+        (None, rest)
+    } else if let Some(rest) = rest.strip_prefix('(') {
+        let mut ds = rest.char_indices();
+        let x = ds
+            .next()
+            .and_then(|(_, c)| c.to_digit(10))
+            .filter(|&d| d >= 1);
+        let y = ds
+            .next()
+            .and_then(|(_, c)| c.to_digit(10))
+            .filter(|&d| d >= 1);
+        match (x, y, ds.next()) {
+            (Some(x), Some(y), Some((i, ')'))) => {
+                (Some(Square::new(x as u8 - 1, y as u8 - 1)), &rest[i + 1..])
+            }
+            _ => return Err(err()),
+        }
+    } else if pt.is_hand() && pos.hand(pos.side()).count(pt) > 0 && !is_promotion {
+        (None, rest)
+    } else {
+        return Err(err());
+    };
+
+    let mv = match src {
+        Some(src) => {
+            if pos.board().cell(src) != BoardCell::Piece(pos.side(), pt) {
+                return Err(Error::decode_error(
+                    ErrorCode::Kif,
+                    format!("kif: piece mismatch: {}", line),
+                ));
+            }
+            Move::nondrop(src, dst, is_promotion)
+        }
+        None => Move::drop(pt, dst),
+    };
+
+    Ok(Entry::Move(mv, decode_time(rest)))
+}
+
+/// "( 0:16/00:00:16)" 形式の消費時間をパースする。
+fn decode_time(s: &str) -> Option<Duration> {
+    let s = s.trim_start().strip_prefix('(')?;
+    let s = s[..s.find('/')?].trim();
+    let (m, sec) = s.split_at(s.find(':')?);
+    let m: u64 = m.parse().ok()?;
+    let sec: u64 = sec[1..].parse().ok()?;
+    Some(Duration::from_secs(m.checked_mul(60)?.checked_add(sec)?))
+}
+
+/// 先頭の駒名を読み、(駒種, 残り) を返す。
+pub(crate) fn split_piece(s: &str) -> Option<(PieceType, &str)> {
+    let ends = s.char_indices().map(|(i, c)| i + c.len_utf8()).take(2);
+    ends.collect::<Vec<_>>().into_iter().rev().find_map(|end| {
+        let name = &s[..end];
+        PieceType::from_name(Lang::Japanese, name)
+            .or_else(|| PieceType::from_name(Lang::JapaneseShort, name))
+            .map(|pt| (pt, &s[end..]))
+    })
+}
+
+pub(crate) fn file_from_char(c: char) -> Option<u8> {
+    match c {
+        '１'..='９' => Some(c as u32 - '１' as u32),
+        '1'..='9' => Some(c as u32 - '1' as u32),
+        _ => None,
+    }
+    .map(|x| x as u8)
+}
+
+pub(crate) fn rank_from_char(c: char) -> Option<u8> {
+    (0..9)
+        .find(|&y| CoordStyle::FullWidth.rank(y) == c)
+        .or_else(|| file_from_char(c))
+}
+
+pub(crate) fn move_dst(mv: Move) -> Square {
+    match mv {
+        Move::Nondrop(nondrop) => nondrop.dst(),
+        Move::Drop(drop) => drop.dst(),
+    }
+}
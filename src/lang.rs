@@ -0,0 +1,151 @@
+use crate::*;
+
+/// 駒名などの表記言語。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Lang {
+    /// 英語 ("pawn", "promoted silver", ...)
+    English,
+    /// 日本語 (歩, 香, ..., 成銀, 馬, 龍)。KIF の指し手表記と同じ。
+    Japanese,
+    /// 日本語の1文字略記 (歩, 香, ..., 全, 馬, 龍)。BOD の盤面表記と同じ。
+    JapaneseShort,
+}
+
+impl PieceType {
+    pub(crate) const ALL: [PieceType; 14] = [
+        PieceType::Pawn,
+        PieceType::Lance,
+        PieceType::Knight,
+        PieceType::Silver,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Gold,
+        PieceType::King,
+        PieceType::ProPawn,
+        PieceType::ProLance,
+        PieceType::ProKnight,
+        PieceType::ProSilver,
+        PieceType::Horse,
+        PieceType::Dragon,
+    ];
+
+    #[rustfmt::skip]
+    const NAMES_EN: [&'static str; 14] = [
+        "pawn", "lance", "knight", "silver", "bishop", "rook", "gold", "king",
+        "tokin", "promoted lance", "promoted knight", "promoted silver", "horse", "dragon",
+    ];
+
+    #[rustfmt::skip]
+    const NAMES_JA: [&'static str; 14] = [
+        "歩", "香", "桂", "銀", "角", "飛", "金", "玉",
+        "と", "成香", "成桂", "成銀", "馬", "龍",
+    ];
+
+    #[rustfmt::skip]
+    const NAMES_JA_SHORT: [&'static str; 14] = [
+        "歩", "香", "桂", "銀", "角", "飛", "金", "玉",
+        "と", "杏", "圭", "全", "馬", "龍",
+    ];
+
+    /// 指定した言語での駒名を返す。
+    pub fn name(self, lang: Lang) -> &'static str {
+        let names = match lang {
+            Lang::English => &Self::NAMES_EN,
+            Lang::Japanese => &Self::NAMES_JA,
+            Lang::JapaneseShort => &Self::NAMES_JA_SHORT,
+        };
+        names[self as usize]
+    }
+
+    /// 指定した言語での駒名をパースする。
+    ///
+    /// 英語は大文字小文字を区別しない。日本語では 王, 竜 などの異表記も受け付ける。
+    pub fn from_name(lang: Lang, name: impl AsRef<str>) -> Option<Self> {
+        let name = name.as_ref();
+
+        let alias = match lang {
+            Lang::English => match name.to_ascii_lowercase().as_str() {
+                "promoted pawn" => Some(Self::ProPawn),
+                "promoted bishop" => Some(Self::Horse),
+                "promoted rook" => Some(Self::Dragon),
+                _ => None,
+            },
+            Lang::Japanese | Lang::JapaneseShort => match name {
+                "王" => Some(Self::King),
+                "竜" => Some(Self::Dragon),
+                _ => None,
+            },
+        };
+        if alias.is_some() {
+            return alias;
+        }
+
+        Self::ALL.iter().copied().find(|pt| match lang {
+            Lang::English => pt.name(lang).eq_ignore_ascii_case(name),
+            _ => pt.name(lang) == name,
+        })
+    }
+}
+
+impl Side {
+    /// 指定した言語での手番名を返す。
+    pub fn name(self, lang: Lang) -> &'static str {
+        match (lang, self) {
+            (Lang::English, Side::Sente) => "sente",
+            (Lang::English, Side::Gote) => "gote",
+            (_, Side::Sente) => "先手",
+            (_, Side::Gote) => "後手",
+        }
+    }
+}
+
+/// 日本語表記の座標 (筋と段) の文字種。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum CoordStyle {
+    /// 全角数字の筋と漢数字の段 ("７六")。
+    #[default]
+    FullWidth,
+    /// ASCII 数字の筋と段 ("76")。
+    Ascii,
+}
+
+impl CoordStyle {
+    /// 筋 `x` (0 が1筋) の表記を返す。
+    pub fn file(self, x: u8) -> char {
+        const FILES_JA: [char; 9] = ['１', '２', '３', '４', '５', '６', '７', '８', '９'];
+        match self {
+            Self::FullWidth => FILES_JA[x as usize],
+            Self::Ascii => char::from(b'1' + x),
+        }
+    }
+
+    /// 段 `y` (0 が1段目) の表記を返す。
+    pub fn rank(self, y: u8) -> char {
+        const RANKS_JA: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+        match self {
+            Self::FullWidth => RANKS_JA[y as usize],
+            Self::Ascii => char::from(b'1' + y),
+        }
+    }
+}
+
+impl Square {
+    /// 指定した言語でのマス名を返す。
+    ///
+    /// 英語では "7f" のような USI 形式、日本語では "７六" のような形式となる。
+    pub fn name(self, lang: Lang) -> String {
+        match lang {
+            Lang::English => [char::from(b'1' + self.x()), char::from(b'a' + self.y())]
+                .iter()
+                .collect(),
+            Lang::Japanese | Lang::JapaneseShort => self.coord(CoordStyle::FullWidth),
+        }
+    }
+
+    /// 日本語表記の座標を、指定した文字種で返す ("７六" または "76")。
+    pub fn coord(self, style: CoordStyle) -> String {
+        [style.file(self.x()), style.rank(self.y())]
+            .iter()
+            .collect()
+    }
+}
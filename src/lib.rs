@@ -1,34 +1,152 @@
+pub mod anki;
+mod apply;
+pub mod attack;
+pub mod binary;
+pub mod bitboard;
+pub mod bod;
+mod code;
+pub mod codec;
+pub mod compact;
+pub mod compress;
+mod counts;
+mod crc32;
+pub mod csa;
+mod declaration;
 mod decode;
+mod describe;
+pub mod diff;
+pub mod differential;
+mod document;
+pub mod editor;
 mod encode;
+pub mod features;
+pub mod frame;
+mod handicap;
+pub mod ki2;
+pub mod kif;
+mod lang;
+pub mod lsh;
+pub mod material;
+mod minimize;
+mod mix;
+mod movegen;
+pub mod notation;
+pub mod packed;
+pub mod pattern;
+mod perft;
+mod pin;
+pub mod policy;
+mod pretty;
+mod reachability;
+pub mod region;
+pub mod relative;
+#[cfg(feature = "svg")]
+pub mod render;
+mod repetition;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod shuffle;
+pub mod similarity;
+mod span;
+pub mod stats;
+#[cfg(feature = "suites")]
+pub mod suites;
+#[cfg(feature = "testdata")]
+pub mod testdata;
+mod transform;
+pub mod tsume;
+pub mod url;
+pub mod usi;
+mod validate;
+pub mod validator;
+mod variant;
+mod western;
 
-pub use decode::decode;
+pub use apply::UndoInfo;
+pub use code::ErrorCode;
+pub use counts::PieceCounts;
+pub use decode::{decode, decode_strict, decode_usi_position};
+pub use document::ParsedDocument;
 pub use encode::encode;
+pub use handicap::Handicap;
+pub use lang::{CoordStyle, Lang};
+pub use minimize::minimize;
+pub use movegen::StagedMoves;
+pub use perft::perft;
+pub use repetition::{detect_repetition, Repetition, RepetitionKind};
+pub use span::{parse_spans, Span, TokenKind};
+#[cfg(feature = "testdata")]
+pub use testdata::testdata;
+pub use validate::ValidationError;
+pub use variant::{decode_variant, encode_variant, Variant};
 
+/// エラー。バリアントが追加されることはある。
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
-    #[error("sfen decode error: {0}")]
-    DecodeError(String),
+    /// デコードエラー。エラーの種類 (`Error::code()` で得られる) とメッセージを持つ。
+    #[error("sfen decode error: {1}")]
+    DecodeError(ErrorCode, String),
+
+    #[error("pattern parse error: {0}")]
+    PatternError(String),
+
+    #[error("invalid move: {0}")]
+    MoveError(String),
+
+    #[error("codec error: {0}")]
+    CodecError(String),
+
+    #[error("encode error: {0}")]
+    EncodeError(String),
 }
 
 impl Error {
-    fn decode_error(msg: impl Into<String>) -> Self {
-        Self::DecodeError(msg.into())
+    fn decode_error(code: ErrorCode, msg: impl Into<String>) -> Self {
+        Self::DecodeError(code, msg.into())
+    }
+
+    fn pattern_error(msg: impl Into<String>) -> Self {
+        Self::PatternError(msg.into())
+    }
+
+    fn move_error(msg: impl Into<String>) -> Self {
+        Self::MoveError(msg.into())
+    }
+
+    fn codec_error(msg: impl Into<String>) -> Self {
+        Self::CodecError(msg.into())
+    }
+
+    fn encode_error(msg: impl Into<String>) -> Self {
+        Self::EncodeError(msg.into())
     }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
     Sente = 0,
     Gote,
 }
 
+impl Side {
+    /// 相手側を返す。
+    pub fn flip(self) -> Self {
+        match self {
+            Self::Sente => Self::Gote,
+            Self::Gote => Self::Sente,
+        }
+    }
+}
+
 fn xy2idx(x: u8, y: u8) -> usize {
     (9 * y + x) as usize
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Square(u8);
 
 impl Square {
@@ -79,7 +197,8 @@ impl Square {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn = 0,
     Lance,
@@ -111,7 +230,7 @@ impl PieceType {
         )
     }
 
-    fn to_promoted(&self) -> Option<Self> {
+    fn to_promoted(self) -> Option<Self> {
         match self {
             Self::Pawn => Some(Self::ProPawn),
             Self::Lance => Some(Self::ProLance),
@@ -122,15 +241,29 @@ impl PieceType {
             _ => None,
         }
     }
+
+    /// 成駒なら成る前の駒種を、そうでなければ自身を返す。
+    fn to_unpromoted(self) -> Self {
+        match self {
+            Self::ProPawn => Self::Pawn,
+            Self::ProLance => Self::Lance,
+            Self::ProKnight => Self::Knight,
+            Self::ProSilver => Self::Silver,
+            Self::Horse => Self::Bishop,
+            Self::Dragon => Self::Rook,
+            _ => self,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BoardCell {
     Empty,
     Piece(Side, PieceType),
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Board([BoardCell; 81]);
 
 impl Board {
@@ -150,12 +283,66 @@ impl Board {
         Self(cells)
     }
 
+    /// 駒が1つもない盤面を返す。
+    pub fn empty() -> Self {
+        Self([BoardCell::Empty; 81])
+    }
+
     pub fn at(&self, x: u8, y: u8) -> BoardCell {
         self.0[xy2idx(x, y)]
     }
+
+    /// マス `sq` の状態を `cell` にする。合法性チェックは一切行わない。
+    pub fn set(&mut self, sq: Square, cell: BoardCell) {
+        self.0[sq.0 as usize] = cell;
+    }
+
+    /// マス `sq` を空きマスにする。
+    pub fn clear(&mut self, sq: Square) {
+        self.set(sq, BoardCell::Empty);
+    }
+
+    /// 2つのマスの状態を入れ替える。
+    pub fn swap(&mut self, a: Square, b: Square) {
+        self.0.swap(a.0 as usize, b.0 as usize);
+    }
+
+    /// 全マスを (マス, マスの状態) の形で返す。順序は 1一, 2一, ..., 9一, 1二, ... 。
+    pub fn iter(&self) -> impl Iterator<Item = (Square, BoardCell)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(i, &cell)| (Square(i as u8), cell))
+    }
+
+    /// `side` 側の駒を (マス, 駒種) の形で返す。
+    pub fn pieces(&self, side: Side) -> impl Iterator<Item = (Square, PieceType)> + '_ {
+        self.iter().filter_map(move |(sq, cell)| match cell {
+            BoardCell::Piece(s, pt) if s == side => Some((sq, pt)),
+            _ => None,
+        })
+    }
+
+    /// `side` 側の玉のマスを返す。玉がない場合は None、複数ある場合はいずれか1つ。
+    pub fn king(&self, side: Side) -> Option<Square> {
+        self.pieces_of(side, PieceType::King).next()
+    }
+
+    /// 盤上の `side` 側の駒 `pt` の枚数を返す。成駒と成る前の駒は区別する。
+    pub fn count(&self, side: Side, pt: PieceType) -> usize {
+        self.pieces_of(side, pt).count()
+    }
+
+    /// `side` 側の駒 `pt` があるマスを返す。
+    pub fn pieces_of(&self, side: Side, pt: PieceType) -> impl Iterator<Item = Square> + '_ {
+        self.pieces(side)
+            .filter(move |&(_, p)| p == pt)
+            .map(|(sq, _)| sq)
+    }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hand([u8; 7]);
 
 impl Hand {
@@ -200,7 +387,7 @@ impl Hand {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Position {
     side: Side,
     board: Board,
@@ -233,9 +420,19 @@ impl Position {
     pub fn ply(&self) -> i32 {
         self.ply
     }
+
+    /// 手数を除いた局面の同一性判定用キーを返す。
+    pub(crate) fn key_without_ply(&self) -> (Side, Board, Hand, Hand) {
+        (
+            self.side,
+            self.board.clone(),
+            self.hands[0].clone(),
+            self.hands[1].clone(),
+        )
+    }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct MoveNondrop {
     src: Square,
     dst: Square,
@@ -256,7 +453,7 @@ impl MoveNondrop {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct MoveDrop {
     pt: PieceType,
     dst: Square,
@@ -272,7 +469,7 @@ impl MoveDrop {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Move {
     Nondrop(MoveNondrop),
     Drop(MoveDrop),
@@ -319,4 +516,2311 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_board_coordinates() -> Result<()> {
+        let (pos, _) = decode("startpos")?;
+        let board = pos.board();
+
+        assert_eq!(
+            board.at(1, 7),
+            BoardCell::Piece(Side::Sente, PieceType::Rook)
+        );
+        assert_eq!(
+            board.at(7, 7),
+            BoardCell::Piece(Side::Sente, PieceType::Bishop)
+        );
+        assert_eq!(
+            board.at(7, 1),
+            BoardCell::Piece(Side::Gote, PieceType::Rook)
+        );
+        assert_eq!(
+            board.at(1, 1),
+            BoardCell::Piece(Side::Gote, PieceType::Bishop)
+        );
+        assert_eq!(board.cell("2h".parse()?), board.at(1, 7));
+
+        // sfen の各行の先頭は9筋 (x = 8)。
+        let (pos, _) = decode("sfen 8k/9/9/9/9/9/9/9/K8 b - 1")?;
+        assert_eq!(
+            pos.board().at(8, 8),
+            BoardCell::Piece(Side::Sente, PieceType::King)
+        );
+        assert_eq!(
+            pos.board().cell("1a".parse()?),
+            BoardCell::Piece(Side::Gote, PieceType::King)
+        );
+
+        // Board::new() で作った盤面も同じ座標系でエンコードされる。
+        let board = Board::new(|x, y| match (x, y) {
+            (0, 0) => BoardCell::Piece(Side::Gote, PieceType::King),
+            (8, 8) => BoardCell::Piece(Side::Sente, PieceType::King),
+            _ => BoardCell::Empty,
+        });
+        let pos = Position::new(Side::Sente, board, Hand::empty(), Hand::empty(), 1);
+        assert_eq!(encode(&pos, &[]), "sfen 8k/9/9/9/9/9/9/9/K8 b - 1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_piece_names() {
+        for &lang in [Lang::English, Lang::Japanese, Lang::JapaneseShort].iter() {
+            for &pt in PieceType::ALL.iter() {
+                assert_eq!(PieceType::from_name(lang, pt.name(lang)), Some(pt));
+            }
+        }
+
+        assert_eq!(PieceType::ProSilver.name(Lang::English), "promoted silver");
+        assert_eq!(PieceType::ProSilver.name(Lang::Japanese), "成銀");
+        assert_eq!(PieceType::ProSilver.name(Lang::JapaneseShort), "全");
+
+        assert_eq!(
+            PieceType::from_name(Lang::Japanese, "王"),
+            Some(PieceType::King)
+        );
+        assert_eq!(
+            PieceType::from_name(Lang::JapaneseShort, "竜"),
+            Some(PieceType::Dragon)
+        );
+        assert_eq!(
+            PieceType::from_name(Lang::English, "promoted rook"),
+            Some(PieceType::Dragon)
+        );
+        assert_eq!(
+            PieceType::from_name(Lang::English, "Promoted Bishop"),
+            Some(PieceType::Horse)
+        );
+        assert_eq!(
+            PieceType::from_name(Lang::English, "KING"),
+            Some(PieceType::King)
+        );
+        assert_eq!(PieceType::from_name(Lang::English, "王"), None);
+        assert_eq!(PieceType::from_name(Lang::JapaneseShort, "成銀"), None);
+        assert_eq!(PieceType::from_name(Lang::Japanese, ""), None);
+    }
+
+    #[test]
+    fn test_anki_csv() -> Result<()> {
+        use crate::anki::{self, Card, Front};
+
+        let (pos, answer) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 1 moves G*5b")?;
+        let cards = [
+            Card {
+                pos: pos.clone(),
+                answer,
+                comment: "\"頭金\"で詰み\n一手詰".to_owned(),
+            },
+            Card {
+                pos,
+                answer: Vec::new(),
+                comment: "a\r\nb".to_owned(),
+            },
+        ];
+
+        assert_eq!(
+            anki::to_csv(&cards, Front::Sfen),
+            "\
+\"sfen 4k4/9/9/9/9/9/9/9/4K4 b G 1\",\"G*5b\",\"\"\"頭金\"\"で詰み<br>一手詰\"
+\"sfen 4k4/9/9/9/9/9/9/9/4K4 b G 1\",\"\",\"a<br>b\"
+"
+        );
+        assert_eq!(
+            anki::to_csv(&cards[..1], Front::Description(Lang::English)),
+            "\
+\"Sente to move.<br>Sente: king 5i.<br>Sente hand: gold 1.<br>\
+Gote: king 5a.<br>Gote hand: none.\",\"G*5b\",\"\"\"頭金\"\"で詰み<br>一手詰\"
+"
+        );
+        assert_eq!(anki::to_csv(&[], Front::Sfen), "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity() -> Result<()> {
+        use crate::similarity;
+
+        let board = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL";
+        let (p, _) = decode("startpos")?;
+        let (q, _) = decode(format!("sfen {} w P 5", board))?;
+        let (r, _) = decode(format!("sfen {} b 2P 1", board))?;
+        let (empty, _) = decode("sfen 9/9/9/9/9/9/9/9/9 b - 1")?;
+
+        assert_eq!(p.similarity(&p), 1.0);
+        assert_eq!(empty.similarity(&empty), 1.0);
+        // 手番と手数は考慮せず、持駒の違いだけ類似度が下がる。
+        assert_eq!(p.similarity(&q), 40.0 / 41.0);
+        assert_eq!(p.similarity(&r), 40.0 / 42.0);
+        assert_eq!(q.similarity(&r), 41.0 / 42.0);
+        assert_eq!(p.similarity(&empty), 0.0);
+
+        let corpus = [r.clone(), empty, p.clone(), q];
+        assert_eq!(
+            similarity::knn(&p, &corpus, 3),
+            [(2, 1.0), (3, 40.0 / 41.0), (0, 40.0 / 42.0)]
+        );
+        assert_eq!(similarity::knn(&p, &corpus, 1), [(2, 1.0)]);
+        assert_eq!(similarity::knn(&p, &corpus, 10).len(), corpus.len());
+        assert!(similarity::knn(&p, &corpus, 0).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_features() -> Result<()> {
+        use crate::features::{self, N_PLANE};
+
+        let plane = |data: &[f32], i: usize| data[81 * i..81 * (i + 1)].to_vec();
+        let sum = |v: Vec<f32>| v.iter().sum::<f32>();
+
+        let (start, _) = decode("startpos")?;
+        let (shape, data) = features::to_tensor(std::iter::once(&start));
+        assert_eq!(shape, [1, N_PLANE, 9, 9]);
+        assert_eq!(N_PLANE, 43);
+        assert_eq!(data.len(), 43 * 81);
+        // 先手の歩は7段目に9枚。
+        assert_eq!(sum(plane(&data, 0)), 9.0);
+        assert!((0..9).all(|x| plane(&data, 0)[9 * 6 + x] == 1.0));
+        // 先手の飛は 2h (x = 1, y = 7)、後手の飛は 8b (x = 7, y = 1)。
+        assert_eq!(sum(plane(&data, 5)), 1.0);
+        assert_eq!(plane(&data, 5)[9 * 7 + 1], 1.0);
+        assert_eq!(sum(plane(&data, 14 + 5)), 1.0);
+        assert_eq!(plane(&data, 14 + 5)[9 + 7], 1.0);
+        // 持駒と手番の平面は全て 0。
+        assert!(data[81 * 28..].iter().all(|&v| v == 0.0));
+
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 w 2Pb 1")?;
+        let (shape, data) = features::to_tensor(&[start, pos]);
+        assert_eq!(shape, [2, N_PLANE, 9, 9]);
+        let data = &data[43 * 81..];
+        assert_eq!(plane(data, 7)[9 * 8 + 4], 1.0);
+        assert_eq!(plane(data, 14 + 7)[4], 1.0);
+        assert!(plane(data, 28).iter().all(|&v| v == 2.0));
+        assert!(plane(data, 28 + 7 + 4).iter().all(|&v| v == 1.0));
+        assert!(plane(data, 42).iter().all(|&v| v == 1.0));
+        assert_eq!(sum(data[81 * 28..81 * 42].to_vec()), 3.0 * 81.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe() -> Result<()> {
+        let (pos, _) = decode("sfen 4k4/9/4+P4/9/9/9/9/9/3GK4 w R2Pb 1")?;
+        assert_eq!(
+            pos.describe(Lang::Japanese),
+            "\
+後手番。
+先手の駒: 玉 ５九、金 ６九、と ５三。
+先手の持駒: 飛 1、歩 2。
+後手の駒: 玉 ５一。
+後手の持駒: 角 1。"
+        );
+        assert_eq!(
+            pos.describe(Lang::English),
+            "\
+Gote to move.
+Sente: king 5i; gold 6i; tokin 5c.
+Sente hand: rook 1; pawn 2.
+Gote: king 5a.
+Gote hand: bishop 1."
+        );
+
+        let (pos, _) = decode("sfen 9/9/9/9/9/9/9/9/9 b - 1")?;
+        assert!(pos.describe(Lang::Japanese).contains("先手の駒: なし。"));
+        assert!(pos.describe(Lang::English).contains("Gote hand: none."));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pattern() -> Result<()> {
+        use crate::pattern::Pattern;
+
+        let (pos, _) = decode("startpos")?;
+        let board = pos.board();
+
+        assert!(Pattern::parse("K@5i G@[4-6]i .=5[d-f]")?.matches(board));
+        assert!(!Pattern::parse("p=*c *=[1-9][g-i]")?.matches(board));
+        assert!(Pattern::parse("P=*g b@2b r@8b")?.matches(board));
+        assert!(!Pattern::parse("+R@*")?.matches(board));
+
+        assert!(Pattern::parse("K@0i").is_err());
+        assert!(Pattern::parse("+K@5i").is_err());
+        assert!(Pattern::parse("K5i").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lsh() -> Result<()> {
+        use crate::lsh::LshIndex;
+
+        let (pos_a, _) = decode("startpos")?;
+        let (pos_b, _) =
+            decode("sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL w - 2")?;
+        let (pos_c, _) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 b 2r2b4g4s4n4l18p 1")?;
+
+        let mut index = LshIndex::new(16, 2, 0);
+        assert_eq!(index.insert(&pos_a), 0);
+        assert_eq!(index.insert(&pos_b), 1);
+        assert_eq!(index.insert(&pos_c), 2);
+
+        let mut buf = Vec::new();
+        index.write_to(&mut buf).unwrap();
+        let index = LshIndex::read_from(buf.as_slice()).unwrap();
+        assert_eq!(index.len(), 3);
+
+        let res = index.probe(&pos_a);
+        assert_eq!(res[0], (0, 1.0));
+        assert!(res.iter().any(|&(id, _)| id == 1));
+        assert!(res.iter().all(|&(id, _)| id != 2));
+
+        // 壊れたヘッダは確保を試みずにエラーになる。
+        let mut header = buf[..36].to_vec();
+        header[12..20].copy_from_slice(&[0xFF; 8]);
+        let err = LshIndex::read_from(header.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spans() {
+        let sfen = "sfen 4k4/9 b S2p 1 moves 7g7f  G*5b";
+        let spans: Vec<_> = parse_spans(sfen)
+            .into_iter()
+            .map(|(span, kind)| (&sfen[span.range()], kind))
+            .collect();
+
+        assert_eq!(
+            spans,
+            [
+                ("sfen", TokenKind::SfenKeyword),
+                ("4k4", TokenKind::BoardRow),
+                ("9", TokenKind::BoardRow),
+                ("b", TokenKind::Side),
+                ("S", TokenKind::HandGroup),
+                ("2p", TokenKind::HandGroup),
+                ("1", TokenKind::Ply),
+                ("moves", TokenKind::MovesKeyword),
+                ("7g7f", TokenKind::Move),
+                ("G*5b", TokenKind::Move),
+            ]
+        );
+
+        assert_eq!(
+            parse_spans("startpos 7g7f"),
+            [
+                (Span::new(0, 8), TokenKind::Startpos),
+                (Span::new(9, 13), TokenKind::Unknown),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_document() {
+        fn assert_same(doc: &ParsedDocument) {
+            let fresh = ParsedDocument::new(doc.text());
+            assert_eq!(doc.spans(), fresh.spans());
+            assert_eq!(doc.position().ok(), fresh.position().ok());
+            let mvs = |doc: &ParsedDocument| -> Vec<_> {
+                doc.moves()
+                    .iter()
+                    .map(|mv| mv.as_ref().ok().copied())
+                    .collect()
+            };
+            assert_eq!(mvs(doc), mvs(&fresh));
+        }
+
+        let mut doc = ParsedDocument::new("startpos moves 7g7f 3c3d 2g2f");
+        assert_eq!(doc.moves().len(), 3);
+
+        // 指し手の書き換え
+        doc.apply_edit(20..24, "8c8d");
+        assert_same(&doc);
+        assert_eq!(&doc.text()[20..24], "8c8d");
+
+        // 指し手の分割と結合
+        doc.apply_edit(22..22, " ");
+        assert_same(&doc);
+        assert_eq!(doc.moves().len(), 4);
+        doc.apply_edit(22..23, "");
+        assert_same(&doc);
+
+        // 末尾への追加と不正な指し手
+        doc.apply_edit(29..29, " 8d8e xx");
+        assert_same(&doc);
+        assert!(doc.moves()[4].is_err());
+
+        // 局面部分の編集
+        doc.apply_edit(0..8, "sfen 9/9/9/9/9/9/9/9/9 b - 1");
+        assert_same(&doc);
+        assert!(doc.position().is_ok());
+    }
+
+    #[test]
+    fn test_url() -> Result<()> {
+        let (pos, _) = decode("startpos")?;
+        let url = url::lishogi_analysis_url(&pos);
+        assert_eq!(
+            url,
+            "https://lishogi.org/analysis/lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL_b_-_1"
+        );
+        assert_eq!(url::parse_lishogi_url(&url)?, pos);
+
+        let (pos, _) = decode(
+            "sfen 8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124",
+        )?;
+        assert_eq!(url::parse_lishogi_url(url::lishogi_editor_url(&pos))?, pos);
+        assert!(url::parse_lishogi_url("https://example.com/analysis/9").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact() -> Result<()> {
+        for sfen in [
+            "startpos",
+            "startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e B*8e 4e3d 8e7f",
+            "sfen 8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124 moves G*4a",
+        ]
+        .iter()
+        {
+            let (pos, mvs) = decode(sfen)?;
+            let s = compact::encode_game(&pos, &mvs);
+            assert!(s.bytes().all(|b| b.is_ascii_uppercase() || (b'2'..=b'7').contains(&b)));
+            assert_eq!(compact::decode_game(&s)?, (pos, mvs));
+        }
+
+        let (pos, mvs) = decode("startpos moves 7g7f 3c3d")?;
+        let mut s = compact::encode_game(&pos, &mvs).into_bytes();
+        s[3] = if s[3] == b'A' { b'B' } else { b'A' };
+        assert!(compact::decode_game(String::from_utf8(s).unwrap()).is_err());
+
+        // 指し手数 2^63 (2 倍するとオーバーフローする)
+        let mut buf = vec![1, 0];
+        buf.extend_from_slice(&[0x80; 9]);
+        buf.push(0x01);
+        let crc = crate::crc32::crc32(&buf);
+        buf.extend_from_slice(&crc.to_le_bytes());
+        assert!(compact::decode_game(compact::base32_encode(&buf)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shuffle() -> Result<()> {
+        assert_eq!(shuffle::position(0), Some(decode("startpos")?.0));
+        assert_eq!(
+            encode(&shuffle::position(47).unwrap(), &[]),
+            "sfen gsnlklnsg/1b5r1/ppppppppp/9/9/9/PPPPPPPPP/1R5B1/GSNLKLNSG b - 1"
+        );
+        assert_eq!(shuffle::position(shuffle::COUNT), None);
+
+        let mut sfens: Vec<_> = (0..shuffle::COUNT)
+            .map(|i| encode(&shuffle::position(i).unwrap(), &[]))
+            .collect();
+        sfens.sort();
+        sfens.dedup();
+        assert_eq!(sfens.len(), shuffle::COUNT);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_index() {
+        use crate::policy::POLICY_SIZE;
+
+        for &side in [Side::Sente, Side::Gote].iter() {
+            for idx in 0..POLICY_SIZE {
+                let mv = Move::from_policy_index(idx, side).unwrap();
+                assert_eq!(mv.to_policy_index(side), idx);
+            }
+            assert_eq!(Move::from_policy_index(POLICY_SIZE, side), None);
+        }
+
+        // 先手の 7g7f と後手の 3c3d は同じインデックスになる。
+        let mv_sente = Move::nondrop(Square::new(6, 6), Square::new(6, 5), false);
+        let mv_gote = Move::nondrop(Square::new(2, 2), Square::new(2, 3), false);
+        assert_eq!(
+            mv_sente.to_policy_index(Side::Sente),
+            mv_gote.to_policy_index(Side::Gote)
+        );
+    }
+
+    #[test]
+    fn test_frame() {
+        use crate::frame::{FrameReader, FrameWriter, ReadMode};
+
+        let mut wtr = FrameWriter::new(Vec::new());
+        for payload in [&b"foo"[..], b"bar", b"", b"baz"].iter() {
+            wtr.write_record(payload).unwrap();
+        }
+        let mut buf = wtr.into_inner();
+
+        let read_all =
+            |buf: &[u8], mode| FrameReader::new(buf, mode).collect::<std::io::Result<Vec<_>>>();
+        assert_eq!(
+            read_all(&buf, ReadMode::Strict).unwrap(),
+            [&b"foo"[..], b"bar", b"", b"baz"]
+        );
+
+        // 2番目のレコードのペイロードを壊し、末尾を切り詰める。
+        buf[12 + 3 + 12] ^= 0xFF;
+        buf.truncate(buf.len() - 1);
+        assert!(read_all(&buf, ReadMode::Strict).is_err());
+        assert_eq!(read_all(&buf, ReadMode::Skip).unwrap(), [&b"foo"[..], b""]);
+    }
+
+    #[test]
+    fn test_compress() {
+        use crate::compress::{self, Compression};
+        use std::io::{Read, Write};
+
+        let data = b"startpos moves 7g7f 3c3d\n".repeat(100);
+
+        for &compression in [Compression::None, Compression::Gzip, Compression::Zstd].iter() {
+            let mut wtr = match compress::writer(Vec::new(), compression) {
+                Ok(wtr) => wtr,
+                Err(e) => {
+                    assert_eq!(e.kind(), std::io::ErrorKind::Unsupported);
+                    continue;
+                }
+            };
+            wtr.write_all(&data).unwrap();
+            let buf = wtr.finish().unwrap();
+            assert_eq!(Compression::detect(&buf), compression);
+
+            // finish() を呼ばずに drop しても終端される。
+            let mut buf_dropped = Vec::new();
+            compress::writer(&mut buf_dropped, compression)
+                .unwrap()
+                .write_all(&data)
+                .unwrap();
+            assert_eq!(buf_dropped, buf);
+
+            // 終端処理での書き込みエラーは finish() で報告される。
+            let mut small = [0_u8; 8];
+            let mut wtr = compress::writer(&mut small[..], compression).unwrap();
+            let res = wtr.write_all(&data).and_then(|()| wtr.finish().map(drop));
+            assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::WriteZero);
+
+            let mut res = Vec::new();
+            compress::reader(buf.as_slice())
+                .unwrap()
+                .read_to_end(&mut res)
+                .unwrap();
+            assert_eq!(res, data);
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        use crate::stats::{self, SprtDecision, Wdl};
+
+        let approx = |a: f64, b: f64| (a - b).abs() < 1e-3;
+
+        assert!(approx(Wdl::new(10, 0, 0).los(), 0.99922));
+        assert!(approx(Wdl::new(60, 20, 20).elo(), 147.19));
+        let (lo, hi) = Wdl::new(60, 20, 20).elo_interval(0.95);
+        assert!(lo < 147.19 && 147.19 < hi);
+
+        assert_eq!(Wdl::new(0, 5, 0).los(), 0.5);
+        assert_eq!(Wdl::default().los(), 0.5);
+
+        let sprt = stats::sprt(&Wdl::new(600, 1000, 400), 0.0, 10.0);
+        assert!(sprt.llr > 0.0);
+        assert_eq!(sprt.decide(0.05, 0.05), SprtDecision::AcceptH1);
+        assert_eq!(
+            stats::sprt(&Wdl::new(500, 1000, 510), 0.0, 10.0).decide(0.05, 0.05),
+            SprtDecision::Continue
+        );
+        assert_eq!(
+            stats::sprt(&Wdl::new(400, 1000, 600), 0.0, 10.0).decide(0.05, 0.05),
+            SprtDecision::AcceptH0
+        );
+
+        // 負けがなくても分散が正なら判定できる。
+        assert_eq!(
+            stats::sprt(&Wdl::new(50, 50, 0), 0.0, 10.0).decide(0.05, 0.05),
+            SprtDecision::AcceptH1
+        );
+        // 全局引き分けや対局数 0 では分散が 0 なので判定保留。
+        assert_eq!(stats::sprt(&Wdl::new(0, 100, 0), 0.0, 10.0).llr, 0.0);
+        assert_eq!(stats::sprt(&Wdl::default(), 0.0, 10.0).llr, 0.0);
+    }
+
+    #[cfg(feature = "suites")]
+    #[test]
+    fn test_suites() {
+        for opening in suites::STANDARD_OPENINGS {
+            let (mut pos, mvs) = opening.decode();
+            assert!(!mvs.is_empty());
+            for &mv in &mvs {
+                assert!(pos.is_legal(mv), "{}: {}", opening.name, mv);
+                pos = pos.apply_move(mv).unwrap();
+            }
+            assert_eq!(opening.position(), pos);
+            assert_eq!(opening.position().ply(), 1 + mvs.len() as i32);
+        }
+
+        let opening = suites::STANDARD_OPENINGS
+            .iter()
+            .find(|o| o.name == "三間飛車")
+            .unwrap();
+        assert_eq!(
+            encode(&opening.position(), &[]),
+            "sfen lnsgkgsnl/1r5b1/p1ppppppp/1p7/9/2P6/PP1PPPPPP/1BR6/LNSGKGSNL w - 4"
+        );
+    }
+
+    #[test]
+    fn test_apply_move() -> Result<()> {
+        let (mut pos, mvs) = decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e B*8e 4e3d 8e7f")?;
+        for mv in mvs {
+            pos = pos.apply_move(mv)?;
+        }
+        assert_eq!(
+            encode(&pos, &[]),
+            "sfen lnsgkg1nl/1r5s1/pppppp1pp/6B2/9/2b6/PP1PPPPPP/7R1/LNSGKGSNL b Pp 9"
+        );
+
+        // 移動元が空、自駒の取得、持っていない駒の打ち込み、成れない駒の成り
+        let (pos, _) = decode("startpos")?;
+        for s_mv in ["5e5d", "2h2i", "P*5e", "5i5h+"].iter() {
+            let mv = decode::decode_move(s_mv)?;
+            assert!(pos.apply_move(mv).is_err());
+        }
+
+        // 持駒の枚数が u8 に収まらない場合はエラーとし、局面は変更しない。
+        let (mut pos, _) = decode("sfen 4k4/9/9/9/9/9/9/4p4/4R4 b 255P 1")?;
+        let mv = decode::decode_move("5i5h")?;
+        assert!(matches!(pos.apply_move(mv), Err(Error::MoveError(_))));
+        let before = pos.clone();
+        assert!(pos.do_move(mv).is_err());
+        assert_eq!(pos, before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() -> Result<()> {
+        let (pos_a, mvs_a) = decode("startpos moves 7g7f 3c3d 2g2f 8c8d")?;
+        let (pos_b, mvs_b) = decode("startpos moves 7g7f 8c8d 2g2f 3c3d 2f2e")?;
+
+        let d = diff::diff(&pos_a, &mvs_a, &pos_b, &mvs_b)?;
+        assert!(!d.start_differs);
+        assert_eq!(d.first_divergence, Some(1));
+        assert_eq!(d.transpositions, [(4, 4)]);
+
+        let d = diff::diff(&pos_a, &mvs_a, &pos_a, &mvs_a)?;
+        assert_eq!(d.first_divergence, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_do_undo_move() -> Result<()> {
+        let (pos_start, mvs) =
+            decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e B*8e 4e3d 8e7f 3d2b+ 8b2b")?;
+
+        let mut pos = pos_start.clone();
+        let mut history = vec![pos.clone()];
+        let mut undos = Vec::new();
+        for mv in mvs {
+            undos.push(pos.do_move(mv)?);
+            history.push(pos.clone());
+        }
+
+        while let Some(undo) = undos.pop() {
+            assert_eq!(pos, history[undos.len() + 1]);
+            pos.undo_move(undo);
+        }
+        assert_eq!(pos, pos_start);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimize() -> Result<()> {
+        let (pos, mvs) =
+            decode("startpos moves 7g7f 3c3d 2g2f 8c8d 2f2e 8d8e 6i7h 4a3b 2e2d 2c2d")?;
+
+        // 「2g2f と 8d8e の両方を含む」を満たす最小の指し手リスト
+        let target = |mv: &str| decode::decode_move(mv).unwrap();
+        let res = minimize(&pos, &mvs, |_, mvs| {
+            mvs.contains(&target("2g2f")) && mvs.contains(&target("8d8e"))
+        });
+        assert_eq!(res, [target("2g2f"), target("8d8e")]);
+
+        // 「適用できない指し手がある」を満たす最小の指し手リスト
+        let mut mvs = mvs;
+        mvs.push(target("5e5d"));
+        let res = minimize(&pos, &mvs, |pos, mvs| {
+            let mut pos = pos.clone();
+            mvs.iter().any(|&mv| pos.do_move(mv).is_err())
+        });
+        assert_eq!(res, [target("5e5d")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_movegen() -> Result<()> {
+        fn count(pos: &Position, depth: u32) -> u64 {
+            if depth == 0 {
+                return 1;
+            }
+            pos.legal_moves()
+                .into_iter()
+                .map(|mv| count(&pos.apply_move(mv).unwrap(), depth - 1))
+                .sum()
+        }
+
+        let (pos, _) = decode("startpos")?;
+        assert_eq!(count(&pos, 1), 30);
+        assert_eq!(count(&pos, 2), 900);
+        assert_eq!(count(&pos, 3), 25470);
+
+        // 打ち歩詰めは擬似合法手だが合法手ではない。
+        let (pos, _) = decode("sfen 8k/9/6NG1/9/9/9/9/9/K8 b P 1")?;
+        let mv = decode::decode_move("P*1b")?;
+        assert!(pos.is_pseudo_legal(mv));
+        assert!(pos.pseudo_legal_moves().contains(&mv));
+        assert!(!pos.is_legal(mv));
+        assert!(!pos.legal_moves().contains(&mv));
+
+        // 二歩、行き所のない駒、王手放置
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/4r4/4P4/9/4K4 b NP 1")?;
+        assert!(!pos.is_pseudo_legal(decode::decode_move("P*5d")?));
+        assert!(!pos.is_pseudo_legal(decode::decode_move("N*1b")?));
+        assert!(pos.is_pseudo_legal(decode::decode_move("N*1c")?));
+        let mv = decode::decode_move("5i4i")?;
+        assert!(pos.is_pseudo_legal(mv) && pos.is_legal(mv));
+        let mv = decode::decode_move("5g5f")?;
+        assert!(pos.is_pseudo_legal(mv) && pos.is_legal(mv));
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/4r4/9/4G4/4K4 b - 1")?;
+        let mv = decode::decode_move("5h4h")?;
+        assert!(pos.is_pseudo_legal(mv) && !pos.is_legal(mv));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reachability() -> Result<()> {
+        let reachable = |sfen: &str| -> Result<bool> {
+            let (pos, _) = decode(sfen)?;
+            Ok(pos.is_reachable_from_startpos(10000))
+        };
+
+        assert!(reachable("startpos")?);
+        assert!(reachable(
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/2P6/PP1PPPPPP/1B5R1/LNSGKGSNL w - 2"
+        )?);
+
+        // 駒の枚数超過、手数の偶奇、手番でない側への王手
+        assert!(!reachable(
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b R 1"
+        )?);
+        assert!(!reachable(
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 2"
+        )?);
+        assert!(!reachable("sfen 4k4/9/9/9/9/9/9/9/r3K4 w - 2")?);
+
+        // 直前の手が存在しない両王手
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/4gs3/4K4 b - 1")?;
+        assert!(!pos.is_reachable_from_startpos(10000));
+        assert!(pos.is_reachable_from_startpos(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check() -> Result<()> {
+        let (pos, _) = decode("startpos")?;
+        assert!(!pos.is_check());
+        assert!(!pos.is_checkmate());
+
+        // 頭金
+        let (pos, _) = decode("sfen 4k4/4G4/4P4/9/9/9/9/9/9 w - 1")?;
+        assert!(pos.is_check());
+        assert_eq!(pos.checkers(), vec![Square::new(4, 1)]);
+        assert!(pos.is_checkmate());
+
+        // 王手だが逃げられる
+        let (pos, _) = decode("sfen 4k4/4G4/9/9/9/9/9/9/9 w - 1")?;
+        assert!(pos.is_check());
+        assert!(!pos.is_checkmate());
+
+        // 手番側の玉がない
+        let (pos, _) = decode("sfen 9/9/9/9/9/9/9/9/4K4 w - 1")?;
+        assert!(!pos.is_check());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_notation() -> Result<()> {
+        use crate::notation::{convert, DecodeNotation, EncodeNotation, Sfen};
+
+        let sfen =
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d";
+        let (pos, mvs) = Sfen::decode_notation(sfen)?;
+        assert_eq!(Sfen::encode_notation(&pos, &mvs)?, encode(&pos, &mvs));
+        assert_eq!(convert::<Sfen, Sfen>(sfen)?, sfen);
+
+        let csa = convert::<Sfen, csa::Csa>(sfen)?;
+        assert_eq!(csa, "V2.2\nPI\n+\n+7776FU\n-3334FU\n");
+        let kif = convert::<csa::Csa, kif::Kif>(&csa)?;
+        assert!(kif.contains("   1 ７六歩(77)\n   2 ３四歩(33)\n"));
+        let ki2 = convert::<kif::Kif, ki2::Ki2>(&kif)?;
+        assert!(ki2.contains("▲７六歩    △３四歩"));
+        assert_eq!(convert::<ki2::Ki2, Sfen>(&ki2)?, sfen);
+
+        // 変換元の形式のエラーはそのまま返る。
+        assert!(convert::<kif::Kif, Sfen>("1 ７六歩(78)\n").is_err());
+        // 変換先で再生できない手 (移動元に駒がない) はエラー。
+        assert!(convert::<Sfen, csa::Csa>("startpos moves 5e5d").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        let (pos, _) = decode("startpos")?;
+        assert_eq!(pos.validate(), Ok(()));
+
+        let (pos, _) = decode("sfen 4k3P/9/9/9/9/4P4/4P4/9/4K4 b 3R 1")?;
+        let errors = pos.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::TooManyPieces {
+                    pt: PieceType::Rook,
+                    count: 3
+                },
+                ValidationError::DoublePawn {
+                    side: Side::Sente,
+                    x: 4
+                },
+                ValidationError::DeadPiece {
+                    sq: Square::new(0, 0),
+                    side: Side::Sente,
+                    pt: PieceType::Pawn
+                },
+            ]
+        );
+
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/9/3KK4 b - 1")?;
+        assert_eq!(
+            pos.validate(),
+            Err(vec![ValidationError::DuplicateKing {
+                side: Side::Sente,
+                count: 2
+            }])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec() -> Result<()> {
+        use crate::codec::Codec;
+
+        // 桂を H、香を Y で表す方言。
+        let codec = Codec::new(&[
+            (PieceType::Pawn, 'P'),
+            (PieceType::Lance, 'Y'),
+            (PieceType::Knight, 'H'),
+            (PieceType::Silver, 'S'),
+            (PieceType::Bishop, 'B'),
+            (PieceType::Rook, 'R'),
+            (PieceType::Gold, 'G'),
+            (PieceType::King, 'K'),
+        ])?;
+        let sfen =
+            "sfen yhsgkgshy/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/YHSGKGSHY b 2Hy 1 moves H*5e";
+        let (pos, mvs) = codec.decode(sfen)?;
+        let (startpos, _) = decode("startpos")?;
+        assert_eq!(pos.board(), startpos.board());
+        assert_eq!(pos.hand(Side::Sente).count(PieceType::Knight), 2);
+        assert_eq!(mvs, vec![Move::drop(PieceType::Knight, Square::new(4, 4))]);
+        assert_eq!(codec.encode(&pos, &mvs), sfen);
+        assert_eq!(codec.decode("startpos")?.0, startpos);
+
+        assert_eq!(Codec::default(), Codec::STANDARD);
+
+        // 曖昧な対応表、欠けている駒種
+        assert!(Codec::new(&[(PieceType::Pawn, 'P'), (PieceType::Lance, 'p')]).is_err());
+        assert!(Codec::new(&[(PieceType::Pawn, 'P')]).is_err());
+        assert!(Codec::new(&[(PieceType::Horse, 'H')]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_strict() -> Result<()> {
+        let sfen = "startpos moves 7g7f 3c3d 8h2b+ 3a2b";
+        assert_eq!(decode_strict(sfen)?, decode(sfen)?);
+
+        // 移動元が空、自駒を取る、王手放置
+        assert!(decode_strict("startpos moves 7f7e").is_err());
+        assert!(decode_strict("startpos moves 7i6i").is_err());
+        assert!(decode_strict("sfen 4k4/9/9/9/9/9/9/4r4/3GK4 b - 1 moves 6i6h").is_err());
+        assert!(decode("startpos moves 7f7e").is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_usi_position() -> Result<()> {
+        let sfen = "startpos moves 7g7f 3c3d";
+        assert_eq!(
+            decode_usi_position(format!("position {}", sfen))?,
+            decode(sfen)?
+        );
+        let sfen = "sfen 4k4/9/9/9/9/9/9/9/4K4 b G 1 moves G*5b";
+        assert_eq!(
+            decode_usi_position(format!("  position {}\n", sfen))?,
+            decode(sfen)?
+        );
+
+        assert_eq!(
+            decode_usi_position(sfen).unwrap_err().code(),
+            ErrorCode::PositionKeywordExpected
+        );
+        assert!(decode_usi_position("positionstartpos").is_err());
+        assert!(decode_usi_position("position").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_region() {
+        use crate::region::Region;
+
+        let zone = Region::promotion_zone(Side::Sente);
+        assert_eq!(zone, Region::camp(Side::Gote));
+        assert!(zone.contains(Square::new(4, 2)));
+        assert!(!zone.contains(Square::new(4, 3)));
+        assert_eq!(zone.squares().count(), 27);
+        assert_eq!(Region::all().squares().count(), 81);
+
+        let r = Region::files(6..8).intersection(&Region::camp(Side::Sente));
+        assert_eq!(
+            r.squares().collect::<Vec<_>>(),
+            vec![
+                Square::new(6, 6),
+                Square::new(7, 6),
+                Square::new(6, 7),
+                Square::new(7, 7),
+                Square::new(6, 8),
+                Square::new(7, 8),
+            ]
+        );
+        assert!(Region::center()
+            .intersection(&Region::camp(Side::Gote))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_from_str_display() -> Result<()> {
+        let sq: Square = "7g".parse()?;
+        assert_eq!(sq, Square::new(6, 6));
+        assert_eq!(sq.to_string(), "7g");
+        assert!("7j".parse::<Square>().is_err());
+        assert!("7g7f".parse::<Square>().is_err());
+
+        let mv: Move = "7g7f".parse()?;
+        assert_eq!(
+            mv,
+            Move::nondrop(Square::new(6, 6), Square::new(6, 5), false)
+        );
+        assert_eq!(mv.to_string(), "7g7f");
+        assert_eq!("P*5e".parse::<Move>()?.to_string(), "P*5e");
+
+        let pos: Position = "startpos".parse()?;
+        let sfen = "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+        assert_eq!(pos.to_string(), sfen);
+        assert_eq!(sfen.parse::<Position>()?, pos);
+        assert!("startpos moves 7g7f".parse::<Position>().is_err());
+        assert!("startpos moves".parse::<Position>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_attack_lines() {
+        use crate::attack::{between, direction_of, line_through, Direction};
+
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+
+        assert_eq!(direction_of(sq("5e"), sq("5a")), Some(Direction::Up));
+        assert_eq!(direction_of(sq("5e"), sq("9e")), Some(Direction::Left));
+        assert_eq!(direction_of(sq("5e"), sq("1i")), Some(Direction::DownRight));
+        assert_eq!(direction_of(sq("5e"), sq("4c")), None);
+        assert_eq!(direction_of(sq("5e"), sq("5e")), None);
+
+        assert_eq!(between(sq("5e"), sq("2b")), vec![sq("4d"), sq("3c")]);
+        assert!(between(sq("5e"), sq("4d")).is_empty());
+        assert!(between(sq("5e"), sq("4c")).is_empty());
+
+        let line = line_through(sq("2b"), sq("3c"));
+        assert_eq!(line.len(), 9);
+        assert_eq!(line.first(), Some(&sq("1a")));
+        assert_eq!(line.last(), Some(&sq("9i")));
+        assert!(line_through(sq("5e"), sq("4c")).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() -> Result<()> {
+        let (pos, mvs) = decode("startpos moves 7g7f P*5e")?;
+
+        let json = serde_json::to_string(&(&pos, &mvs)).unwrap();
+        assert_eq!(
+            json,
+            r#"["sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",["7g7f","P*5e"]]"#
+        );
+        let (pos2, mvs2): (Position, Vec<Move>) = serde_json::from_str(&json).unwrap();
+        assert_eq!((pos2, mvs2), (pos.clone(), mvs));
+
+        let json = serde_json::to_string(pos.board()).unwrap();
+        assert_eq!(
+            json,
+            r#""lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL""#
+        );
+        assert_eq!(&serde_json::from_str::<Board>(&json).unwrap(), pos.board());
+
+        let sq = Square::new(6, 6);
+        assert_eq!(serde_json::to_string(&sq).unwrap(), r#""7g""#);
+        assert_eq!(serde_json::from_str::<Square>(r#""7g""#).unwrap(), sq);
+        assert!(serde_json::from_str::<Square>(r#""7j""#).is_err());
+        assert!(serde_json::from_str::<Move>("3").is_err());
+
+        let json = serde_json::to_string(&(Side::Gote, PieceType::Horse)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<(Side, PieceType)>(&json).unwrap(),
+            (Side::Gote, PieceType::Horse)
+        );
+        let json = serde_json::to_string(pos.hand(Side::Sente)).unwrap();
+        assert_eq!(
+            &serde_json::from_str::<Hand>(&json).unwrap(),
+            pos.hand(Side::Sente)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pins() -> Result<()> {
+        use crate::bitboard::Bitboard;
+
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+        let bb = |sqs: &[&str]| sqs.iter().map(|s| sq(s)).collect::<Bitboard>();
+
+        // 5筋: 先手玉 5i、先手金 5h、後手飛 5a。
+        // 斜め: 先手銀 4h、後手角 1e。
+        // 2筋: 先手香 2i、先手歩 2e、後手玉 2a。
+        let (pos, _) = decode("sfen 4r2k1/9/9/9/7Pb/9/9/4GS3/4K2L1 b - 1")?;
+        assert_eq!(pos.pinned_pieces(Side::Sente), bb(&["4h", "5h"]));
+        assert_eq!(pos.discovered_check_candidates(Side::Sente), bb(&["2e"]));
+        assert!(pos.pinned_pieces(Side::Gote).is_empty());
+
+        // 香は後ろ向きには走れない。
+        let (pos, _) = decode("sfen 4l4/9/9/9/9/9/9/4G4/4K4 b - 1")?;
+        assert_eq!(pos.pinned_pieces(Side::Sente), bb(&["5h"]));
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/4G4/4K3l b - 1")?;
+        assert!(pos.pinned_pieces(Side::Sente).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_evasion_moves() -> Result<()> {
+        fn sorted(mut mvs: Vec<Move>) -> Vec<String> {
+            let mut res: Vec<_> = mvs.drain(..).map(|mv| mv.to_string()).collect();
+            res.sort();
+            res
+        }
+
+        let sfens = [
+            // 飛車による王手: 合駒、移動合、玉の移動
+            "sfen 4r3k/9/9/9/9/9/9/3S5/4K4 b GP 1",
+            // 桂による王手と、その捕獲
+            "sfen 8k/9/9/9/9/9/3n5/4G4/4K4 b - 1",
+            // 両王手
+            "sfen 4r3k/9/9/9/9/9/6b2/3G5/4K4 b G 1",
+            // 実戦的な王手局面
+            "sfen 1ns1k1s2/lgg1L4/p3pr2b/2pp2p2/Np1NP1Pp1/PPP5p/1K1P1P1P1/LG1SG3+p/B4RSN1 w lp 98",
+            "sfen l2s+b2np/1rk1sgl2/1p4PP1/p3P3P/2ppGN1+B1/3L5/PP2pP2N/L1G1KS+pR1/1NS5G b 4P 101",
+            // 王手でない局面
+            "startpos",
+        ];
+        for sfen in sfens.iter() {
+            let (pos, _) = decode(sfen)?;
+            assert_eq!(
+                sorted(pos.evasion_moves()),
+                sorted(pos.legal_moves()),
+                "{}",
+                sfen
+            );
+        }
+
+        let (pos, _) = decode(sfens[2])?;
+        assert!(pos
+            .evasion_moves()
+            .iter()
+            .all(|mv| matches!(mv, Move::Nondrop(nondrop) if nondrop.src() == Square::new(4, 8))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repetition() -> Result<()> {
+        // 飛車の往復による千日手
+        let cycle = " 2h3h 8b7b 3h2h 7b8b";
+        let sfen = format!("startpos moves{}", cycle.repeat(3));
+        let (pos, mvs) = decode(&sfen)?;
+        assert_eq!(
+            detect_repetition(&pos, &mvs)?,
+            Some(Repetition {
+                ply: 12,
+                first_ply: 0,
+                kind: RepetitionKind::Draw,
+            })
+        );
+        assert_eq!(detect_repetition(&pos, &mvs[..11])?, None);
+
+        // 先手の連続王手の千日手
+        let cycle = " 1b1a 5a5b 1a1b 5b5a";
+        let sfen = format!("sfen 4k4/8R/9/9/9/9/9/9/4K4 b - 1 moves{}", cycle.repeat(3));
+        let (pos, mvs) = decode(&sfen)?;
+        let rep = detect_repetition(&pos, &mvs)?.unwrap();
+        assert_eq!(rep.kind, RepetitionKind::PerpetualCheck(Side::Sente));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handicap() -> Result<()> {
+        assert_eq!(Position::handicap(Handicap::Even), decode("startpos")?.0);
+
+        let pos = Position::handicap(Handicap::TwoPieces);
+        assert_eq!(pos.side(), Side::Gote);
+        assert_eq!(pos.board().at(7, 1), BoardCell::Empty);
+        assert_eq!(pos.board().at(1, 1), BoardCell::Empty);
+
+        for &h in Handicap::ALL.iter() {
+            assert_eq!(Handicap::from_name(h.name()), Some(h));
+            assert_eq!(Position::handicap(h).validate(), Ok(()));
+        }
+        assert_eq!(Handicap::from_name("飛角落ち"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_moves_staged() -> Result<()> {
+        let (pos, _) = decode("sfen 4k4/9/9/9/4p4/9/1P7/4R4/4K4 b GP 1")?;
+
+        let staged: Vec<_> = pos.moves_staged().collect();
+        let mut legal = pos.legal_moves();
+        let mut sorted = staged.clone();
+        legal.sort_by_key(|mv| mv.to_string());
+        sorted.sort_by_key(|mv| mv.to_string());
+        assert_eq!(sorted, legal);
+
+        // 最初は駒を取る手か成る手
+        assert_eq!(staged[0], "5h5e".parse()?);
+        assert!(matches!(staged.last(), Some(Move::Drop(_))));
+
+        let (pos, _) = decode("startpos")?;
+        assert_eq!(pos.moves_staged().count(), 30);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_drops() -> Result<()> {
+        use crate::bitboard::Bitboard;
+
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+
+        // 1筋に歩があり、1b への歩打ちは打ち歩詰め。
+        let (pos, _) = decode("sfen 8k/9/6NG1/8P/9/9/9/9/K8 b PN 1")?;
+        let targets = pos.drop_targets(PieceType::Pawn);
+        assert!((targets & Bitboard::file(0)).is_empty());
+        assert!((targets & Bitboard::rank(0)).is_empty());
+
+        let (pos, _) = decode("sfen 8k/9/6NG1/9/9/9/9/9/K8 b P 1")?;
+        assert!(pos.drop_targets(PieceType::Pawn).contains(sq("1b")));
+        assert!(!pos
+            .drop_moves(PieceType::Pawn)
+            .contains(&Move::drop(PieceType::Pawn, sq("1b"))));
+        assert_eq!(
+            pos.drop_moves(PieceType::Pawn).len(),
+            pos.drop_targets(PieceType::Pawn).len() - 1
+        );
+
+        // 持っていない駒、行き所のない桂
+        assert!(pos.drop_targets(PieceType::Rook).is_empty());
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 b N 1")?;
+        assert_eq!(pos.drop_targets(PieceType::Knight).len(), 81 - 18 - 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_board_iter() -> Result<()> {
+        let (pos, _) = decode("startpos")?;
+        let board = pos.board();
+
+        assert_eq!(board.iter().count(), 81);
+        assert!(board
+            .iter()
+            .all(|(sq, cell)| board.at(sq.x(), sq.y()) == cell));
+        assert_eq!(board.pieces(Side::Sente).count(), 20);
+        assert_eq!(
+            board
+                .pieces_of(Side::Gote, PieceType::Lance)
+                .collect::<Vec<_>>(),
+            vec![Square::new(0, 0), Square::new(8, 0)]
+        );
+        assert_eq!(board.pieces_of(Side::Sente, PieceType::Pawn).count(), 9);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "testdata")]
+    #[test]
+    fn test_testdata() {
+        use crate::testdata::Expected;
+
+        for case in testdata() {
+            let actual = decode(case.input).map(|(pos, mvs)| encode(&pos, &mvs));
+            match case.expected {
+                Expected::Sfen(sfen) => {
+                    assert_eq!(actual.ok().as_deref(), Some(sfen), "{}", case.name)
+                }
+                Expected::Error => assert!(actual.is_err(), "{}", case.name),
+            }
+        }
+    }
+
+    #[test]
+    fn test_board_edit() -> Result<()> {
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+
+        let mut board = Board::empty();
+        assert_eq!(
+            board
+                .iter()
+                .filter(|&(_, cell)| cell != BoardCell::Empty)
+                .count(),
+            0
+        );
+
+        board.set(sq("5i"), BoardCell::Piece(Side::Sente, PieceType::King));
+        board.set(sq("5a"), BoardCell::Piece(Side::Gote, PieceType::King));
+        board.set(sq("5e"), BoardCell::Piece(Side::Sente, PieceType::Gold));
+        board.swap(sq("5e"), sq("4e"));
+        board.clear(sq("5a"));
+        board.set(sq("5b"), BoardCell::Piece(Side::Gote, PieceType::King));
+
+        let pos = Position::new(Side::Sente, board, Hand::new(|_| 0), Hand::new(|_| 0), 1);
+        assert_eq!(pos.to_string(), "sfen 9/4k4/9/9/5G3/9/9/9/4K4 b - 1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_piece_counts() -> Result<()> {
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/4+P4/4K2+p1 b 2P 1")?;
+        let board = pos.board();
+        assert_eq!(board.king(Side::Sente), Some("5i".parse()?));
+        assert_eq!(board.count(Side::Sente, PieceType::ProPawn), 1);
+        assert_eq!(board.count(Side::Sente, PieceType::Pawn), 0);
+
+        let counts = pos.piece_counts();
+        assert_eq!(counts.board(Side::Gote, PieceType::ProPawn), 1);
+        assert_eq!(counts.hand(Side::Sente, PieceType::Pawn), 2);
+        assert_eq!(counts.hand(Side::Sente, PieceType::King), 0);
+        assert_eq!(counts.total(Side::Sente, PieceType::Pawn), 3);
+        assert_eq!(counts.total(Side::Sente, PieceType::ProPawn), 3);
+        assert_eq!(counts.len(Side::Sente), 4);
+        assert_eq!(counts.len(Side::Gote), 2);
+
+        let (pos, _) = decode("startpos")?;
+        assert_eq!(pos.piece_counts().len(Side::Gote), 20);
+
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/P8/4K4 b 255P 1")?;
+        assert_eq!(pos.piece_counts().total(Side::Sente, PieceType::Pawn), 256);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_coord_style() -> Result<()> {
+        let sq: Square = "7f".parse()?;
+        assert_eq!(sq.coord(CoordStyle::FullWidth), "７六");
+        assert_eq!(sq.coord(CoordStyle::Ascii), "76");
+        assert_eq!(sq.name(Lang::Japanese), sq.coord(CoordStyle::default()));
+        assert_eq!(CoordStyle::Ascii.rank(8), '9');
+        assert_eq!(CoordStyle::FullWidth.file(0), '１');
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitboard() -> Result<()> {
+        use crate::bitboard::Bitboard;
+
+        let (pos, _) = decode("startpos")?;
+        let bbs = pos.board().to_bitboards();
+
+        assert_eq!(bbs.occupied().len(), 40);
+        assert_eq!(bbs.side(Side::Sente).len(), 20);
+        let pawns = bbs.piece(Side::Sente, PieceType::Pawn);
+        assert_eq!(pawns, Bitboard::rank(6));
+        assert_eq!(
+            pawns.iter().collect::<Vec<_>>(),
+            pos.board()
+                .pieces_of(Side::Sente, PieceType::Pawn)
+                .collect::<Vec<_>>()
+        );
+        assert!((pawns & Bitboard::file(4)).contains("5g".parse()?));
+        assert_eq!((!bbs.occupied()).len(), 41);
+        assert_eq!(!Bitboard::EMPTY, Bitboard::FULL);
+        assert_eq!(Bitboard::from_bits(u128::MAX), Bitboard::FULL);
+
+        let mut bb = Bitboard::EMPTY;
+        bb.insert("1a".parse()?);
+        bb.insert("9i".parse()?);
+        bb.remove("1a".parse()?);
+        assert_eq!(bb.iter().collect::<Vec<_>>(), vec!["9i".parse()?]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_differential() {
+        use crate::differential::{compare, normalize};
+
+        let inputs = [
+            "startpos",
+            "startpos moves 7g7f",
+            "sfen 4k4/9/9/9/9/9/9/9/4K4 b 2P 1",
+            "bogus",
+        ];
+
+        // 自身との比較では食い違いはない。
+        assert!(compare(&normalize, inputs.iter().copied()).is_empty());
+
+        // 持駒の枚数 "2P" を解釈できない実装との比較。
+        let reference = |input: &str| {
+            if input.contains("2P") {
+                Err("unsupported".to_owned())
+            } else {
+                normalize(input)
+            }
+        };
+        let divs = compare(&reference, inputs.iter().copied());
+        assert_eq!(divs.len(), 1);
+        assert_eq!(divs[0].input, inputs[2]);
+        assert!(divs[0].ours.is_ok() && divs[0].theirs.is_err());
+    }
+
+    #[test]
+    fn test_editor() -> Result<()> {
+        use crate::editor::Editor;
+
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1")?;
+        let mut editor = Editor::new(pos);
+        assert!(!editor.is_dirty());
+        assert!(!editor.undo());
+
+        editor.place("5h".parse()?, Side::Sente, PieceType::Gold);
+        editor.set_hand(Side::Gote, PieceType::Pawn, 3);
+        editor.flip_side();
+        assert_eq!(editor.to_sfen(), "sfen 4k4/9/9/9/9/9/9/4G4/4K4 w 3p 1");
+        assert!(editor.is_dirty());
+
+        editor.mark_clean();
+        editor.remove("5a".parse()?);
+        assert_eq!(editor.to_sfen(), "sfen 9/9/9/9/9/9/9/4G4/4K4 w 3p 1");
+        assert!(editor.undo());
+        assert!(!editor.is_dirty());
+        assert!(editor.undo());
+        assert_eq!(editor.to_sfen(), "sfen 4k4/9/9/9/9/9/9/4G4/4K4 b 3p 1");
+        assert!(editor.redo());
+        assert!(editor.redo());
+        assert!(!editor.redo());
+        assert!(editor.is_dirty());
+
+        // 取り消し後に新しい編集をすると、やり直し履歴と保存済み状態は失われる。
+        editor.undo();
+        editor.undo();
+        editor.flip_side();
+        assert!(!editor.can_redo());
+        assert!(editor.is_dirty());
+        editor.undo();
+        editor.redo();
+        assert!(editor.is_dirty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_perft() -> Result<()> {
+        let (pos, _) = decode("startpos")?;
+        assert_eq!(perft(&pos, 0), 1);
+        assert_eq!(perft(&pos, 1), 30);
+        assert_eq!(perft(&pos, 2), 900);
+        assert_eq!(perft(&pos, 3), 25470);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_moves_from_to() -> Result<()> {
+        use std::collections::HashSet;
+
+        let (mut pos, mvs) = decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b")?;
+        for mv in mvs {
+            pos.do_move(mv)?;
+        }
+        let all: HashSet<_> = pos.legal_moves().into_iter().collect();
+        for sq in movegen::all_squares() {
+            let from = pos.moves_from(sq);
+            assert!(from.iter().all(|mv| all.contains(mv)));
+            assert_eq!(
+                from.len(),
+                all.iter()
+                    .filter(|mv| matches!(mv, Move::Nondrop(nd) if nd.src() == sq))
+                    .count()
+            );
+            let to = pos.moves_to(sq);
+            assert!(to.iter().all(|mv| all.contains(mv)));
+            let dst = |mv: &&Move| match mv {
+                Move::Nondrop(nd) => nd.dst(),
+                Move::Drop(d) => d.dst(),
+            };
+            assert_eq!(to.len(), all.iter().filter(|mv| dst(mv) == sq).count());
+        }
+
+        assert_eq!(pos.moves_from("2h".parse()?).len(), 8);
+        assert!(pos.moves_to("5e".parse()?).contains(&"B*5e".parse()?));
+        assert!(pos.moves_from("5a".parse()?).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kif_decode() -> Result<()> {
+        let game = kif::decode(
+            "\
+# KIF形式棋譜ファイル
+開始日時：2020/01/01
+手合割：平手　　
+先手：A
+後手：B
+手数----指手---------消費時間--
+   1 ７六歩(77)   ( 0:01/00:00:01)
+   2 ３四歩(33)   ( 0:02/00:00:02)
+*コメント
+   3 ２二角成(88) ( 1:03/00:01:04)
+   4 同　銀(31)   ( 0:04/00:00:06)
+   5 ４五角打     ( 0:05/00:01:09)
+   6 投了         ( 0:06/00:00:12)
+まで5手で先手の勝ち
+",
+        )?;
+
+        assert_eq!(
+            game.headers,
+            [("開始日時", "2020/01/01"), ("先手", "A"), ("後手", "B")]
+                .iter()
+                .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            encode(&game.position, &game.moves),
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d 8h2b+ 3a2b B*4e"
+        );
+        assert_eq!(
+            game.times,
+            [1, 2, 63, 4, 5]
+                .iter()
+                .map(|&s| Some(std::time::Duration::from_secs(s)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(game.terminal.as_deref(), Some("投了"));
+
+        let game = kif::decode("手合割：香落ち\n1 ９二香(91)\n")?;
+        assert_eq!(game.position.side(), Side::Gote);
+        assert!(kif::decode("1 ７六歩(78)\n").is_err());
+        assert!(kif::decode("1 同　歩(77)\n").is_err());
+
+        // 消費時間が溢れる場合は時間なしとして扱う。
+        let game = kif::decode("1 ７六歩(77) (1000000000000000000:00/00:00:00)\n")?;
+        assert_eq!(game.times, [None]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_move() -> Result<()> {
+        use crate::relative::{Motion, RelativeMove, RelativePos};
+
+        let pos: Position = "sfen 4k4/9/9/9/9/9/9/4G4/3G1G3 b G 1".parse()?;
+        let check = |pos: &Position, mv: &str, motion, relative| -> Result<()> {
+            let mv: Move = mv.parse()?;
+            let rel = RelativeMove::from_move(pos, mv)?;
+            assert_eq!((rel.motion, rel.relative), (motion, relative), "{}", mv);
+            assert_eq!(rel.to_move(pos)?, mv);
+            Ok(())
+        };
+        check(&pos, "4i5i", None, Some(RelativePos::Right))?;
+        check(&pos, "6i5i", None, Some(RelativePos::Left))?;
+        check(&pos, "5h5i", Some(Motion::Down), None)?;
+        check(&pos, "4i4h", Some(Motion::Up), None)?;
+        check(&pos, "4i3h", None, None)?;
+        check(&pos, "5h5g", None, None)?;
+        check(&pos, "G*5g", None, None)?;
+        assert!(RelativeMove::from_move(&pos, "6i5h".parse()?).is_err());
+
+        let pos: Position = "sfen 4k4/9/9/9/9/9/9/9/3GGG3 b - 1".parse()?;
+        check(&pos, "5i5h", None, Some(RelativePos::Straight))?;
+        check(&pos, "6i5h", None, Some(RelativePos::Left))?;
+
+        let pos: Position = "sfen 4k4/9/9/9/9/9/3S5/9/3S1S3 b - 1".parse()?;
+        check(&pos, "6i5h", Some(Motion::Up), Some(RelativePos::Left))?;
+        check(&pos, "6g5h", Some(Motion::Down), None)?;
+        check(&pos, "4i5h", None, Some(RelativePos::Right))?;
+
+        // 移動元の配置が異なる局面にも適用できる。
+        let rel = RelativeMove::from_move(&pos, "6i5h".parse()?)?;
+        let pos: Position = "sfen 4k4/9/9/9/9/9/5S3/9/3S1S3 b - 1".parse()?;
+        assert_eq!(rel.to_move(&pos)?, "6i5h".parse()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kif_encode() -> Result<()> {
+        let (pos, mvs) = decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e 2b3c 4e3d 3c3d")?;
+        let mut game = kif::Game::new(pos, mvs);
+        game.headers.push(("先手".to_owned(), "A".to_owned()));
+        game.times[0] = Some(std::time::Duration::from_secs(1));
+        game.times[2] = Some(std::time::Duration::from_secs(62));
+        game.terminal = Some("投了".to_owned());
+
+        let kif = kif::encode(&game)?;
+        assert_eq!(
+            kif,
+            "\
+先手：A
+手合割：平手
+手数----指手---------消費時間--
+   1 ７六歩(77)     ( 0:01/00:00:01)
+   2 ３四歩(33)
+   3 ２二角成(88)   ( 1:02/00:01:03)
+   4 同　銀(31)
+   5 ４五角打
+   6 ３三銀(22)
+   7 ３四角(45)
+   8 同　銀(33)
+   9 投了
+"
+        );
+        assert_eq!(kif::decode(&kif)?, game);
+
+        let kif = kif::encode_with_style(&game, CoordStyle::Ascii)?;
+        assert!(kif.contains("   1 76歩(77)"));
+        assert_eq!(kif::decode(&kif)?, game);
+
+        let (pos, mvs) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 1 moves G*5b")?;
+        let game = kif::Game::new(pos, mvs);
+        let kif = kif::encode_with_style(&game, CoordStyle::Ascii)?;
+        assert!(kif.contains("先手の持駒：金"));
+        assert!(kif.contains("   1 52金打"));
+        assert_eq!(kif::decode(&kif)?, game);
+
+        {
+            use crate::notation::{DecodeNotation, EncodeNotation};
+            let kif = kif::Kif::encode_notation(&game.position, &game.moves)?;
+            assert_eq!(kif, kif::encode(&game)?);
+            assert_eq!(
+                kif::Kif::decode_notation(&kif)?,
+                (game.position, game.moves)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validator_service() {
+        use crate::validator::{RateLimiter, Service, Status};
+
+        let service = Service {
+            max_batch: 4,
+            max_len: 100,
+            threads: std::num::NonZeroUsize::new(2).unwrap(),
+            ..Service::new()
+        };
+        let inputs = [
+            "startpos moves 7g7f",
+            "startpos moves 7g7e",
+            "sfen 4k4/9/9/9/9/9/9/9/9 b - 1",
+            "sfen foo",
+            "startpos",
+        ];
+        let reports = service.validate(&inputs);
+        let statuses: Vec<_> = reports.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                Status::Ok,
+                Status::Invalid,
+                Status::Invalid,
+                Status::Malformed,
+                Status::Rejected
+            ]
+        );
+        assert!(reports.iter().enumerate().all(|(i, r)| r.index == i));
+        assert_eq!(
+            reports[0].sfen.as_deref(),
+            Some("sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f")
+        );
+
+        let service = Service {
+            replay: false,
+            ..service
+        };
+        assert_eq!(service.validate(&inputs[1..2])[0].status, Status::Ok);
+
+        // 流量制限: 3件分のトークンを使い切ると、補充されるまで拒否する。
+        let rate_limit = RateLimiter::new(3, 0.0);
+        let service = Service {
+            rate_limit: Some(rate_limit.clone()),
+            ..service
+        };
+        let reports = service.validate(&inputs);
+        let statuses: Vec<_> = reports.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                Status::Ok,
+                Status::Ok,
+                Status::Invalid,
+                Status::Rejected,
+                Status::Rejected
+            ]
+        );
+        assert_eq!(reports[3].errors, ["rate limit exceeded"]);
+        assert_eq!(reports[4].errors, ["batch size limit exceeded"]);
+        assert_eq!(service.validate(&inputs[..1])[0].status, Status::Rejected);
+        assert_eq!(rate_limit.acquire(1), 0);
+
+        let rate_limit = RateLimiter::new(2, 10.0);
+        let now = std::time::Instant::now();
+        assert_eq!(rate_limit.acquire_at(5, now), 2);
+        assert_eq!(rate_limit.acquire_at(5, now), 0);
+        let later = now + std::time::Duration::from_millis(150);
+        assert_eq!(rate_limit.acquire_at(5, later), 1);
+        // 補充は capacity で頭打ちになる。
+        let later = later + std::time::Duration::from_secs(60);
+        assert_eq!(rate_limit.acquire_at(5, later), 2);
+    }
+
+    #[test]
+    fn test_ki2() -> Result<()> {
+        let ki2 = "\
+先手：A
+手合割：平手
+▲７六歩    △３四歩    ▲２二角成  △同　銀    ▲５五角    △６二銀
+▲４八金    △３三銀    ▲５八金寄  △４四銀    ▲同　角    △同　歩
+▲４八銀打
+まで13手で中断
+";
+        let game = ki2::decode(ki2)?;
+        assert_eq!(game.headers, [("先手".to_owned(), "A".to_owned())]);
+        assert_eq!(
+            encode(&game.position, &game.moves),
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves \
+             7g7f 3c3d 8h2b+ 3a2b B*5e 7a6b 4i4h 2b3c 4h5h 3c4d 5e4d 4c4d S*4h"
+        );
+        assert_eq!(game.terminal.as_deref(), Some("中断"));
+        assert_eq!(ki2::encode(&game)?, ki2);
+
+        // 修飾がない手は紛れがあればエラー。
+        assert!(ki2::decode("▲７六歩\n△５二金\n").is_err());
+
+        {
+            use crate::notation::{DecodeNotation, EncodeNotation};
+            let (pos, mvs) = ki2::Ki2::decode_notation(ki2)?;
+            assert_eq!((&pos, &mvs), (&game.position, &game.moves));
+            let ki2 = ki2::Ki2::encode_notation(&pos, &mvs)?;
+            assert!(ki2.starts_with("手合割：平手\n▲７六歩"));
+            assert!(!ki2.contains("まで"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_csa() -> Result<()> {
+        let csa = "\
+V2.2
+N+A
+N-B
+$EVENT:test
+PI
++
++7776FU
+T1
+-3334FU
+T2
++8822UM,T3
+-3122GI
++0045KA
+%TORYO
+";
+        let game = csa::decode(csa)?;
+        assert_eq!(
+            encode(&game.position, &game.moves),
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves \
+             7g7f 3c3d 8h2b+ 3a2b B*4e"
+        );
+        assert_eq!(game.headers[2], ("$EVENT".to_owned(), "test".to_owned()));
+        assert_eq!(game.times[2], Some(std::time::Duration::from_secs(3)));
+        assert_eq!(game.times[3], None);
+        assert_eq!(game.terminal.as_deref(), Some("投了"));
+
+        let encoded = csa::encode(&game)?;
+        assert!(encoded.contains("\n+8822UM\nT3\n"));
+        assert_eq!(csa::decode(&encoded)?, game);
+
+        // KIF 形式からの変換
+        let game = kif::decode(kif::encode(&game)?)?;
+        assert_eq!(csa::decode(csa::encode(&game)?)?.moves, game.moves);
+
+        {
+            use crate::notation::{DecodeNotation, EncodeNotation};
+            let (pos, mvs) = csa::Csa::decode_notation(csa)?;
+            assert_eq!((&pos, &mvs), (&game.position, &game.moves));
+            let csa = csa::Csa::encode_notation(&pos, &mvs)?;
+            assert!(csa.starts_with("V2.2\nPI\n+\n+7776FU\n"));
+            assert!(!csa.contains('%'));
+        }
+
+        // 駒落ちと 00AL
+        let game = csa::decode("PI82HI22KA\n-\n")?;
+        assert_eq!(game.position, decode(Handicap::TwoPieces.sfen())?.0);
+        let game = csa::decode(
+            "\
+P1 *  *  *  *  *  *  *  * -OU
+P2 *  *  *  *  *  *  *  *  *
+P3 *  *  *  *  *  *  *  *  *
+P4 *  *  *  *  *  *  *  *  *
+P5 *  *  *  *  *  *  *  *  *
+P6 *  *  *  *  *  *  *  *  *
+P7 *  *  *  *  *  *  *  *  *
+P8 *  *  *  *  *  *  *  *  *
+P9 *  *  *  *  *  *  *  *  *
+P+28KI
+P-00AL
++
+",
+        )?;
+        assert_eq!(
+            game.position.to_string(),
+            "sfen 8k/9/9/9/9/9/9/7G1/9 b 2r2b3g4s4n4l18p 1"
+        );
+        assert_eq!(csa::decode(csa::encode(&game)?)?, game);
+
+        // 持駒の枚数が u8 に収まらない
+        let err = csa::decode(format!("P+{}\n+\n", "00FU".repeat(300))).unwrap_err();
+        assert_eq!(err.to_string(), "sfen decode error: csa: hands overflow");
+        let game = csa::decode(format!(
+            "P+{}\nP-{}\nP+00AL\n+\n",
+            "00FU".repeat(200),
+            "00FU".repeat(200)
+        ))?;
+        assert_eq!(game.position.hand(Side::Sente).count(PieceType::Pawn), 200);
+        assert_eq!(game.position.hand(Side::Sente).count(PieceType::Rook), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_code() {
+        let code = |sfen: &str| decode(sfen).unwrap_err().code();
+
+        assert_eq!(code("sfen"), ErrorCode::PositionIncomplete);
+        assert_eq!(code("foo"), ErrorCode::PositionInvalidMagic);
+        assert_eq!(
+            code("sfen 9/9/9/9/9/9/9/9/9/9 b - 1"),
+            ErrorCode::BoardTooManyRows
+        );
+        assert_eq!(
+            code("sfen 55/9/9/9/9/9/9/9/9 b - 1"),
+            ErrorCode::BoardRowOverflow
+        );
+        assert_eq!(
+            code("sfen +G8/9/9/9/9/9/9/9/9 b - 1"),
+            ErrorCode::BoardRowNotPromotable
+        );
+        assert_eq!(code("sfen 9/9/9/9/9/9/9/9/9 x - 1"), ErrorCode::SideInvalid);
+        assert_eq!(
+            code("sfen 9/9/9/9/9/9/9/9/9 b K 1"),
+            ErrorCode::HandsNotHandPiece
+        );
+        assert_eq!(
+            code("sfen 9/9/9/9/9/9/9/9/9 b 200P100P 1"),
+            ErrorCode::HandsOverflow
+        );
+        assert_eq!(code("sfen 9/9/9/9/9/9/9/9/9 b - x"), ErrorCode::PlyInvalid);
+        assert_eq!(code("startpos foo"), ErrorCode::MovesKeywordExpected);
+        assert_eq!(code("startpos moves 7g7f+x"), ErrorCode::MoveInvalidString);
+        assert_eq!(code("startpos moves 0g7f"), ErrorCode::SquareInvalidX);
+        assert_eq!(code("startpos moves 7g7z"), ErrorCode::SquareInvalidY);
+        assert_eq!(
+            "startpos moves 7g7f"
+                .parse::<Position>()
+                .unwrap_err()
+                .code(),
+            ErrorCode::PositionUnexpectedMoves
+        );
+        assert_eq!(kif::decode("1 同　歩").unwrap_err().code(), ErrorCode::Kif);
+        assert_eq!(
+            url::parse_lishogi_url("https://example.com/")
+                .unwrap_err()
+                .code(),
+            ErrorCode::Url
+        );
+        let mut buf = vec![1, 0, 1, 0xFF, 0xFF];
+        let crc = crate::crc32::crc32(&buf);
+        buf.extend_from_slice(&crc.to_le_bytes());
+        let err = compact::decode_game(compact::base32_encode(&buf)).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::MoveInvalidPacked);
+        assert_eq!(
+            err.to_string(),
+            "sfen decode error: move: invalid packed move: 0xffff"
+        );
+
+        assert_eq!(ErrorCode::BoardRowOverflow.as_str(), "E_BOARD_ROW_OVERFLOW");
+        assert_eq!(ErrorCode::BoardRowOverflow.as_u16(), 203);
+        assert_eq!(ErrorCode::Decode.to_string(), "E_DECODE");
+    }
+
+    #[test]
+    fn test_bod() -> Result<()> {
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 w RB2Gs18p 10")?;
+        let bod = bod::encode(&pos);
+        assert!(bod.contains("先手の持駒：飛　角　金二　\n"));
+        assert!(bod.contains("後手の持駒：銀　歩十八　\n"));
+        assert!(bod.contains("手数＝9\n後手番\n"));
+        assert_eq!(bod::decode(&bod)?, pos);
+        assert_eq!(
+            bod::decode(bod::encode_with_style(&pos, CoordStyle::Ascii))?,
+            pos
+        );
+
+        let bod = "\
+後手の持駒：なし
+  ９ ８ ７ ６ ５ ４ ３ ２ １
++---------------------------+
+|v香v桂v銀v金v玉v金v銀v桂v香|一
+| ・v飛 ・ ・ ・ ・ ・v角 ・|二
+|v歩v歩v歩v歩v歩v歩v歩v歩v歩|三
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|四
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|五
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|六
+| 歩 歩 歩 歩 歩 歩 歩 歩 歩|七
+| ・ 角 ・ ・ ・ ・ ・ 飛 ・|八
+| 香 桂 銀 金 玉 金 銀 桂 香|九
++---------------------------+
+先手の持駒：なし
+";
+        assert_eq!(bod::decode(bod)?, Position::handicap(Handicap::Even));
+        assert!(bod::decode("先手番").is_err());
+        assert_eq!(bod::decode("先手番").unwrap_err().code(), ErrorCode::Bod);
+        assert_eq!(
+            bod::decode("手数＝2147483647").unwrap_err().code(),
+            ErrorCode::Bod
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_usi() -> Result<()> {
+        use crate::usi::{self, BestMove, Bound, Info, Message, Score};
+        use std::time::Duration;
+
+        let mv = |s: &str| s.parse::<Move>().unwrap();
+
+        assert_eq!(
+            usi::decode_bestmove("bestmove 7g7f ponder 3c3d")?,
+            BestMove::Move(mv("7g7f"), Some(mv("3c3d")))
+        );
+        assert_eq!(
+            usi::decode_bestmove("bestmove P*5e")?,
+            BestMove::Move(mv("P*5e"), None)
+        );
+        assert_eq!(usi::decode_bestmove("bestmove resign")?, BestMove::Resign);
+        assert!(usi::decode_bestmove("bestmove 7g7f ponder").is_err());
+
+        assert_eq!(
+            usi::decode_info(
+                "info depth 20 seldepth 28 score cp -85 upperbound nodes 123456 nps 1000000 \
+                 time 1500 hashfull 300 multipv 2 pv 7g7f 3c3d 8h2b+"
+            )?,
+            Info {
+                depth: Some(20),
+                seldepth: Some(28),
+                time: Some(Duration::from_millis(1500)),
+                nodes: Some(123456),
+                nps: Some(1000000),
+                hashfull: Some(300),
+                multipv: Some(2),
+                score: Some(Score::Cp(-85)),
+                bound: Some(Bound::Upper),
+                currmove: None,
+                pv: vec![mv("7g7f"), mv("3c3d"), mv("8h2b+")],
+                string: None,
+            }
+        );
+        let info = usi::decode_info("info score mate - currmove 5e5d string 詰み 発見")?;
+        assert_eq!(info.score, Some(Score::MateUnknown(false)));
+        assert_eq!(info.currmove, Some(mv("5e5d")));
+        assert_eq!(info.string.as_deref(), Some("詰み 発見"));
+        assert_eq!(
+            usi::decode_info("info score mate 7 pv")?.score,
+            Some(Score::Mate(7))
+        );
+        assert_eq!(
+            usi::decode_info("info depth x").unwrap_err().code(),
+            ErrorCode::Usi
+        );
+
+        assert_eq!(
+            usi::decode_message("readyok\n")?,
+            Message::Other("readyok".to_owned())
+        );
+        assert!(matches!(
+            usi::decode_message("info depth 1")?,
+            Message::Info(Info { depth: Some(1), .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed() -> Result<()> {
+        use crate::packed::{self, PackedSfenValue};
+
+        let (pos, mvs) = decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e")?;
+        let mut cur = pos.clone();
+        assert_eq!(packed::decode(&packed::encode(&cur)?)?, cur);
+        for &mv in &mvs {
+            cur.apply_move_in_place(mv)?;
+            let mut expected = cur.clone();
+            expected.ply = 1;
+            assert_eq!(packed::decode(&packed::encode(&cur)?)?, expected);
+        }
+
+        // 平手の開始局面の PackedSfen。やねうら王の sfen_packer.cpp の形式に従い手で組み立てたもの
+        // (手番 0、玉のマス 44 (5i) と 36 (5a)、盤上の駒のハフマン符号、持駒なし)。
+        #[rustfmt::skip]
+        let startpos_packed = [
+            0x58, 0xA4, 0x51, 0x22, 0x0C, 0xEB, 0x67, 0x22,
+            0x7E, 0x96, 0x53, 0x22, 0x1C, 0xAF, 0x44, 0x78,
+            0x24, 0xC2, 0x2B, 0x11, 0x9E, 0x53, 0x22, 0x1C,
+            0xEB, 0x6F, 0x22, 0x3E, 0x96, 0x51, 0x22, 0x0C,
+        ];
+        assert_eq!(packed::encode(&pos)?, startpos_packed);
+        assert_eq!(packed::decode(&startpos_packed)?, pos);
+
+        assert_eq!(packed::encode_move16("7g7f".parse()?), 0x1E3B);
+        for mv in ["7g7f", "8h2b+", "B*4e", "G*5e"] {
+            let mv: Move = mv.parse()?;
+            assert_eq!(packed::decode_move16(packed::encode_move16(mv))?, Some(mv));
+        }
+        assert_eq!(packed::decode_move16(0)?, None);
+
+        let value = PackedSfenValue {
+            position: cur,
+            score: -120,
+            mv: Some("3c3d".parse()?),
+            game_ply: 6,
+            game_result: -1,
+        };
+        assert_eq!(PackedSfenValue::from_bytes(&value.to_bytes()?)?, value);
+
+        let (pos, _) = decode("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 1")?;
+        assert!(packed::encode(&pos).is_err());
+        assert_eq!(
+            packed::decode(&[0xFF; 32]).unwrap_err().code(),
+            ErrorCode::Packed
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary() -> Result<()> {
+        use crate::binary::{self, GameReader, GameWriter};
+
+        let games = [
+            decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e")?,
+            decode("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 7 moves G*5b")?,
+            decode("sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 42")?,
+        ];
+
+        let mut wtr = GameWriter::new(Vec::new()).unwrap();
+        for (pos, mvs) in &games {
+            let buf = binary::encode_game(pos, mvs);
+            assert_eq!(binary::decode_game(&buf)?, (pos.clone(), mvs.clone()));
+            wtr.write_game(pos, mvs).unwrap();
+        }
+        // PackedSfen で表せる局面は 1 + 1 + 32 + 1 + 1 + 2 * 指し手数 byte になる。
+        assert_eq!(binary::encode_game(&games[0].0, &games[0].1).len(), 46);
+
+        let buf = wtr.into_inner();
+        let rdr = GameReader::new(buf.as_slice()).unwrap();
+        assert_eq!(
+            rdr.collect::<std::io::Result<Vec<_>>>().unwrap(),
+            games.to_vec()
+        );
+
+        assert!(GameReader::new(&buf[1..]).is_err());
+        let mut rdr = GameReader::new(&buf[..buf.len() - 1]).unwrap();
+        assert!(rdr.read_game().unwrap().is_some());
+        assert!(rdr.read_game().unwrap().is_some());
+        assert!(rdr.read_game().is_err());
+        assert_eq!(
+            binary::decode_game(&[2]).unwrap_err().code(),
+            ErrorCode::Binary
+        );
+
+        // 指し手数 2^63 (2 倍するとオーバーフローする)
+        let mut buf = binary::encode_game(&games[2].0, &[]);
+        buf.pop();
+        buf.extend_from_slice(&[0x80; 9]);
+        buf.push(0x01);
+        assert!(binary::decode_game(&buf).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_to_japanese() -> Result<()> {
+        let (mut pos, mvs) =
+            decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*5e 4a3b 5e3c+ 3b3c 7i6h")?;
+        let mut prev = None;
+        let mut res = Vec::new();
+        for mv in mvs {
+            res.push(mv.to_japanese_after(&pos, prev)?);
+            pos.apply_move_in_place(mv)?;
+            prev = Some(mv);
+        }
+        assert_eq!(
+            res,
+            [
+                "▲７六歩",
+                "△３四歩",
+                "▲２二角成",
+                "△同　銀",
+                "▲５五角",
+                "△３二金",
+                "▲３三角成",
+                "△同　金",
+                "▲６八銀",
+            ]
+        );
+
+        let mv: Move = "6a5b".parse()?;
+        assert_eq!(mv.to_japanese(&pos)?, "△５二金");
+        assert!("5i5a".parse::<Move>()?.to_japanese(&pos).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_to_western() -> Result<()> {
+        let (mut pos, mvs) =
+            decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*5e 4a3b 5e3c+ 3b3c 6i5h")?;
+        let mut res = Vec::new();
+        for mv in mvs {
+            if mv == "5e3c+".parse()? {
+                assert_eq!("5e3c".parse::<Move>()?.to_western(&pos)?, "B-3c=");
+            }
+            res.push(mv.to_western(&pos)?);
+            pos.apply_move_in_place(mv)?;
+        }
+        assert_eq!(
+            res,
+            ["P-7f", "P-3d", "Bx2b+", "Sx2b", "B*5e", "G-3b", "B-3c+", "Gx3c", "G6i-5h",]
+        );
+
+        assert!("5i5a".parse::<Move>()?.to_western(&pos).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_position_pretty() -> Result<()> {
+        let (pos, _) = decode("sfen 4k4/9/4+P4/9/9/9/9/9/4K4 w RB2Gs18p 10")?;
+
+        assert_eq!(
+            pos.to_ascii(),
+            "\
+gote hand: s18p
+  9  8  7  6  5  4  3  2  1
++---------------------------+
+| .  .  .  .  k  .  .  .  . | a
+| .  .  .  .  .  .  .  .  . | b
+| .  .  .  . +P  .  .  .  . | c
+| .  .  .  .  .  .  .  .  . | d
+| .  .  .  .  .  .  .  .  . | e
+| .  .  .  .  .  .  .  .  . | f
+| .  .  .  .  .  .  .  .  . | g
+| .  .  .  .  .  .  .  .  . | h
+| .  .  .  .  K  .  .  .  . | i
++---------------------------+
+sente hand: RB2G
+gote to move, ply 10
+"
+        );
+
+        let unicode = pos.to_unicode();
+        assert!(unicode.starts_with("後手の持駒：銀　歩十八　\n  ９ ８ ７ ６ ５ ４ ３ ２ １\n┌"));
+        assert!(unicode.contains("│ ・ ・ ・ ・ と ・ ・ ・ ・│三\n"));
+        assert!(unicode.ends_with("┘\n先手の持駒：飛　角　金二　\n後手番、10手目\n"));
+
+        let unicode = pos.to_unicode_with_style(CoordStyle::Ascii);
+        assert!(unicode.contains("\n   9  8  7  6  5  4  3  2  1\n┌"));
+        assert!(unicode.contains("│ ・ ・ ・ ・ と ・ ・ ・ ・│3\n"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_render_svg() -> Result<()> {
+        use crate::render::{self, SvgOptions};
+
+        let (pos, _) = decode("sfen 4k4/9/4+P4/9/9/9/9/9/4K4 w RB2Gs18p 10")?;
+        let opts = SvgOptions {
+            last_move: Some("5d5c+".parse()?),
+            ..SvgOptions::default()
+        };
+        let svg = render::svg(&pos, &opts);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("#f0a860").count(), 2);
+        assert!(svg.contains("transform=\"rotate(180 200 80)\">玉</text>"));
+        assert!(svg.contains("fill=\"#c00000\">と</text>"));
+        assert!(svg.contains(">後手の持駒：銀　歩十八</text>"));
+        assert!(svg.contains(">先手の持駒：飛　角　金二</text>"));
+
+        let svg = render::svg(
+            &pos,
+            &SvgOptions {
+                coordinates: false,
+                ..SvgOptions::default()
+            },
+        );
+        assert!(!svg.contains("#f0a860"));
+        assert!(!svg.contains(">９</text>"));
+
+        let svg = render::svg(
+            &pos,
+            &SvgOptions {
+                coord_style: CoordStyle::Ascii,
+                ..SvgOptions::default()
+            },
+        );
+        assert!(svg.contains(">9</text>"));
+        assert!(!svg.contains(">９</text>"));
+        assert!(!svg.contains(">一</text>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_variant() -> Result<()> {
+        let (pos, mvs) = decode_variant(Variant::Minishogi, "startpos moves 5e4d 1a2b")?;
+        assert_eq!(
+            pos.board().at(4, 4),
+            BoardCell::Piece(Side::Sente, PieceType::King)
+        );
+        assert_eq!(
+            pos.board().at(0, 0),
+            BoardCell::Piece(Side::Gote, PieceType::King)
+        );
+        assert_eq!(pos.board().at(5, 0), BoardCell::Empty);
+        assert_eq!(
+            encode_variant(Variant::Minishogi, &pos, &mvs)?,
+            "sfen rbsgk/4p/5/P4/KGSBR b - 1 moves 5e4d 1a2b"
+        );
+
+        let sfen = "sfen 2k2/5/1+R3/5/K4 w Pp 9";
+        let (pos, _) = decode_variant(Variant::Minishogi, sfen)?;
+        assert_eq!(encode_variant(Variant::Minishogi, &pos, &[])?, sfen);
+
+        assert!(decode_variant(Variant::Minishogi, "sfen 6/5/5/5/5 b - 1").is_err());
+        assert!(decode_variant(Variant::Minishogi, "sfen 5/5/5/5/5/5 b - 1").is_err());
+        assert_eq!(
+            decode_variant(Variant::Minishogi, "startpos moves 5e6d")
+                .unwrap_err()
+                .code(),
+            ErrorCode::MoveOutOfBoard
+        );
+        assert!(encode_variant(Variant::Minishogi, &decode("startpos")?.0, &[]).is_err());
+
+        // 盤の大きさに満たない盤面はエラーになる。
+        let minishogi = "sfen rbsgk/4p/5/P4/KGSBR b - 1";
+        assert_eq!(
+            decode_variant(Variant::Standard, minishogi)
+                .unwrap_err()
+                .code(),
+            ErrorCode::BoardRowUnderflow
+        );
+        assert_eq!(
+            decode("sfen 9/9/9/9/9 b - 1").unwrap_err().code(),
+            ErrorCode::BoardTooFewRows
+        );
+        assert_eq!(
+            decode_variant(Variant::Minishogi, "sfen 5/5/5/5/4 b - 1")
+                .unwrap_err()
+                .code(),
+            ErrorCode::BoardRowUnderflow
+        );
+
+        assert_eq!(
+            decode_variant(Variant::Standard, "startpos moves 7g7f")?,
+            decode("startpos moves 7g7f")?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tsume() -> Result<()> {
+        let sfen = "sfen 4k4/9/4P4/9/9/9/9/9/9 b 2r 1";
+        let (pos, _) = tsume::decode(sfen, Side::Sente)?;
+        assert_eq!(pos.hand(Side::Gote).count(PieceType::Rook), 2);
+        assert_eq!(pos.hand(Side::Sente).count(PieceType::Rook), 0);
+        assert_eq!(pos.hand(Side::Sente).count(PieceType::Pawn), 17);
+        assert_eq!(pos.hand(Side::Sente).count(PieceType::Gold), 4);
+        assert!(pos.remaining_pieces().is_empty());
+        assert_eq!(tsume::encode(&pos, &[], Side::Sente)?, sfen);
+
+        let (mut pos, _) = decode("sfen 4k4/9/4+P4/9/9/9/9/9/9 b G 1")?;
+        assert!(tsume::encode(&pos, &[], Side::Gote).is_err());
+        pos.fill_hand_with_remaining(Side::Gote);
+        assert_eq!(pos.hand(Side::Sente).count(PieceType::Gold), 1);
+        assert_eq!(pos.hand(Side::Gote).count(PieceType::Gold), 3);
+        assert_eq!(pos.hand(Side::Gote).count(PieceType::Pawn), 17);
+        assert_eq!(
+            tsume::encode(&pos, &[], Side::Gote)?,
+            "sfen 4k4/9/4+P4/9/9/9/9/9/9 b G 1"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_declaration() -> Result<()> {
+        let (pos, _) = decode("sfen RB7/4K4/PPPPPPPPP/9/9/9/9/9/4k4 b RGSNL 1")?;
+        assert_eq!(pos.declaration_points(Side::Sente), 28);
+        assert_eq!(pos.declaration_points(Side::Gote), 0);
+        assert!(pos.can_declare_win(Side::Sente));
+        assert!(!pos.can_declare_win(Side::Gote));
+
+        // 点数不足
+        let (pos, _) = decode("sfen RB7/4K4/PPPPPPPPP/9/9/9/9/9/4k4 b RGSN 1")?;
+        assert_eq!(pos.declaration_points(Side::Sente), 27);
+        assert!(!pos.can_declare_win(Side::Sente));
+
+        // 手番でない、敵陣の駒が 10 枚未満、王手
+        let (pos, _) = decode("sfen RB7/4K4/PPPPPPPPP/9/9/9/9/9/4k4 w RGSNL 1")?;
+        assert!(!pos.can_declare_win(Side::Sente));
+        let (pos, _) = decode("sfen RB7/4K4/PPPPPPP2/9/9/9/9/9/4k4 b RGSNL2P 1")?;
+        assert_eq!(pos.declaration_points(Side::Sente), 28);
+        assert!(!pos.can_declare_win(Side::Sente));
+        let (pos, _) = decode("sfen RB2r4/4K4/PPPPPPPPP/9/9/9/9/9/4k4 b BGSNL 1")?;
+        assert_eq!(pos.declaration_points(Side::Sente), 28);
+        assert!(!pos.can_declare_win(Side::Sente));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_material() -> Result<()> {
+        use crate::material::{self, PieceValues};
+
+        let values = PieceValues::default();
+        let (pos, _) = decode("startpos")?;
+        assert_eq!(material::balance(&pos, &values), 0);
+        assert_eq!(
+            material::material(&pos, Side::Sente, &values),
+            9 * 90 + 2 * (315 + 405 + 495 + 540) + 855 + 990
+        );
+
+        // 玉以外は先手の馬と持駒の歩のみ
+        let (pos, _) = decode("sfen 4k4/9/9/9/4+B4/9/9/9/4K4 b P 1")?;
+        assert_eq!(material::balance(&pos, &values), 945 + 90);
+
+        let mut values = PieceValues::new(|pt| if pt == PieceType::King { 0 } else { 1 });
+        values.set(PieceType::Horse, 10);
+        assert_eq!(values.pawn(), 1);
+        assert_eq!(material::balance(&pos, &values), 11);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform() -> Result<()> {
+        let (pos, mvs) = decode("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e")?;
+        let mut cur = pos.clone();
+        for mv in mvs {
+            cur.apply_move_in_place(mv)?;
+        }
+
+        let mirrored = cur.mirror_horizontal();
+        assert_eq!(
+            mirrored.to_string(),
+            "sfen ln1gkgsnl/1s5r1/pp1pppppp/2p6/3B5/6P2/PPPPPP1PP/1R7/LNSGKGSNL w b 6"
+        );
+        assert_eq!(mirrored.mirror_horizontal(), cur);
+        assert_eq!(cur.rotate180().rotate180(), cur);
+        assert_eq!(
+            pos.rotate180().to_string(),
+            pos.to_string().replace(" b ", " w ")
+        );
+
+        for mv in cur.legal_moves() {
+            assert!(mirrored.is_legal(mv.mirror_horizontal()));
+            assert!(cur.rotate180().is_legal(mv.rotate180()));
+        }
+        assert_eq!(
+            "7g7f".parse::<Move>()?.mirror_horizontal(),
+            "3g3f".parse::<Move>()?
+        );
+        assert_eq!("B*4e".parse::<Move>()?.rotate180(), "B*6e".parse::<Move>()?);
+        assert_eq!(
+            "8h2b+".parse::<Move>()?.rotate180(),
+            "2b8h+".parse::<Move>()?
+        );
+
+        Ok(())
+    }
 }
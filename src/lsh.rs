@@ -0,0 +1,223 @@
+//! 類似局面の近似最近傍探索のための LSH (locality-sensitive hashing) インデックス。
+//!
+//! `Position::similarity()` と同じ特徴 (マスごとの駒、陣営・駒種ごとの持駒枚数) の
+//! 集合に対して MinHash を計算し、バンド分割したハッシュ値でバケットを引く。
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use crate::mix::mix;
+use crate::*;
+
+const MAGIC: &[u8; 8] = b"SFENLSH\0";
+const VERSION: u32 = 1;
+
+/// シグネチャ長 (n_band * n_row) の上限。
+const MAX_SIG_LEN: usize = 1 << 16;
+
+#[derive(Clone, Debug)]
+pub struct LshIndex {
+    n_band: usize,
+    n_row: usize,
+    seed: u64,
+    /// 挿入された局面の MinHash シグネチャ (長さ n_band * n_row) を連結したもの。
+    sigs: Vec<u32>,
+    /// バンドごとの (バンドハッシュ → 局面 ID リスト)。
+    buckets: Vec<HashMap<u64, Vec<usize>>>,
+}
+
+impl LshIndex {
+    /// バンド数 `n_band`、バンドあたりの行数 `n_row` のインデックスを作る。
+    ///
+    /// バンド数を増やすと再現率が、行数を増やすと適合率が上がる。
+    /// `n_band` または `n_row` が 0 の場合、および `n_band * n_row` が 65536 を超える場合、panic する。
+    pub fn new(n_band: usize, n_row: usize, seed: u64) -> Self {
+        assert!(n_band > 0 && n_row > 0, "n_band and n_row must be positive");
+        assert!(
+            sig_len(n_band, n_row).is_some(),
+            "n_band * n_row must not exceed {}",
+            MAX_SIG_LEN
+        );
+
+        Self {
+            n_band,
+            n_row,
+            seed,
+            sigs: Vec::new(),
+            buckets: vec![HashMap::new(); n_band],
+        }
+    }
+
+    /// 格納されている局面数を返す。
+    pub fn len(&self) -> usize {
+        self.sigs.len() / self.sig_len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sigs.is_empty()
+    }
+
+    /// 局面を挿入し、その ID を返す。ID は 0 から順に振られる。
+    pub fn insert(&mut self, pos: &Position) -> usize {
+        let id = self.len();
+        let sig = self.signature(pos);
+        self.add_to_buckets(id, &sig);
+        self.sigs.extend_from_slice(&sig);
+        id
+    }
+
+    /// `pos` と少なくとも1つのバンドを共有する局面の ID を、
+    /// 推定類似度の降順に (ID, 推定類似度) の形で返す。
+    pub fn probe(&self, pos: &Position) -> Vec<(usize, f32)> {
+        let sig = self.signature(pos);
+
+        let mut ids: Vec<_> = sig
+            .chunks(self.n_row)
+            .zip(&self.buckets)
+            .filter_map(|(band, buckets)| buckets.get(&band_hash(band)))
+            .flatten()
+            .copied()
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let mut res: Vec<_> = ids
+            .into_iter()
+            .map(|id| {
+                let sig_id = &self.sigs[id * self.sig_len()..(id + 1) * self.sig_len()];
+                let n_eq = sig.iter().zip(sig_id).filter(|(a, b)| a == b).count();
+                (id, n_eq as f32 / self.sig_len() as f32)
+            })
+            .collect();
+        res.sort_by(|lhs, rhs| rhs.1.partial_cmp(&lhs.1).expect("internal error"));
+
+        res
+    }
+
+    /// インデックスをバイナリ形式で書き出す。
+    pub fn write_to(&self, mut wtr: impl Write) -> io::Result<()> {
+        let to_u32 = |n: usize| {
+            u32::try_from(n).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "lsh: too large parameter")
+            })
+        };
+
+        wtr.write_all(MAGIC)?;
+        wtr.write_all(&VERSION.to_le_bytes())?;
+        wtr.write_all(&to_u32(self.n_band)?.to_le_bytes())?;
+        wtr.write_all(&to_u32(self.n_row)?.to_le_bytes())?;
+        wtr.write_all(&self.seed.to_le_bytes())?;
+        wtr.write_all(&(self.len() as u64).to_le_bytes())?;
+        for &h in &self.sigs {
+            wtr.write_all(&h.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// `write_to()` で書き出したインデックスを読み込む。
+    pub fn read_from(mut rdr: impl Read) -> io::Result<Self> {
+        fn invalid(msg: &str) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, msg)
+        }
+        fn read_u32(rdr: &mut impl Read) -> io::Result<u32> {
+            let mut buf = [0; 4];
+            rdr.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+        fn read_u64(rdr: &mut impl Read) -> io::Result<u64> {
+            let mut buf = [0; 8];
+            rdr.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        }
+
+        let mut magic = [0; 8];
+        rdr.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("lsh: invalid magic"));
+        }
+        if read_u32(&mut rdr)? != VERSION {
+            return Err(invalid("lsh: unsupported version"));
+        }
+
+        let n_band = read_u32(&mut rdr)? as usize;
+        let n_row = read_u32(&mut rdr)? as usize;
+        if n_band == 0 || n_row == 0 || sig_len(n_band, n_row).is_none() {
+            return Err(invalid("lsh: invalid parameters"));
+        }
+        let seed = read_u64(&mut rdr)?;
+        let len =
+            usize::try_from(read_u64(&mut rdr)?).map_err(|_| invalid("lsh: too large length"))?;
+
+        let mut index = Self::new(n_band, n_row, seed);
+        let mut sig = vec![0; index.sig_len()];
+        for id in 0..len {
+            for h in sig.iter_mut() {
+                *h = read_u32(&mut rdr)?;
+            }
+            index.add_to_buckets(id, &sig);
+            index.sigs.extend_from_slice(&sig);
+        }
+
+        Ok(index)
+    }
+
+    fn sig_len(&self) -> usize {
+        self.n_band * self.n_row
+    }
+
+    fn signature(&self, pos: &Position) -> Vec<u32> {
+        let features = features(pos);
+        (0..self.sig_len() as u64)
+            .map(|i| {
+                let seed = mix(self.seed ^ mix(i));
+                features
+                    .iter()
+                    .map(|&f| mix(f ^ seed) as u32)
+                    .min()
+                    .unwrap_or(u32::MAX)
+            })
+            .collect()
+    }
+
+    fn add_to_buckets(&mut self, id: usize, sig: &[u32]) {
+        for (band, buckets) in sig.chunks(self.n_row).zip(&mut self.buckets) {
+            buckets.entry(band_hash(band)).or_default().push(id);
+        }
+    }
+}
+
+/// シグネチャ長 `n_band * n_row` を返す。上限を超える場合は `None` を返す。
+fn sig_len(n_band: usize, n_row: usize) -> Option<usize> {
+    n_band.checked_mul(n_row).filter(|&len| len <= MAX_SIG_LEN)
+}
+
+/// 局面の特徴集合を返す。
+///
+/// 盤上の駒は (マス, 陣営, 駒種)、持駒は (陣営, 駒種, k) (k = 1..=枚数) を1要素とする。
+fn features(pos: &Position) -> Vec<u64> {
+    let mut res = Vec::with_capacity(64);
+
+    for y in 0..9 {
+        for x in 0..9 {
+            if let BoardCell::Piece(side, pt) = pos.board().at(x, y) {
+                let sq = u64::from(9 * y + x);
+                res.push((sq << 16) | ((side as u64) << 8) | pt as u64);
+            }
+        }
+    }
+
+    for &side in [Side::Sente, Side::Gote].iter() {
+        for (pt, n) in pos.hand(side).enumerate() {
+            for k in 1..=u64::from(n) {
+                res.push((1 << 32) | (k << 16) | ((side as u64) << 8) | pt as u64);
+            }
+        }
+    }
+
+    res
+}
+
+fn band_hash(band: &[u32]) -> u64 {
+    band.iter().fold(0, |acc, &h| mix(acc ^ u64::from(h)))
+}
@@ -0,0 +1,81 @@
+//! 駒割り (駒の価値の合計) の計算。
+
+use crate::*;
+
+/// 駒種ごとの価値の表。
+///
+/// 持駒の価値は盤上の同じ駒種 (成る前の駒) の価値とする。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PieceValues([i32; 14]);
+
+impl Default for PieceValues {
+    /// 歩を 90 とする一般的な価値 (玉は 0)。
+    fn default() -> Self {
+        Self::new(|pt| match pt {
+            PieceType::Pawn => 90,
+            PieceType::Lance => 315,
+            PieceType::Knight => 405,
+            PieceType::Silver => 495,
+            PieceType::Gold => 540,
+            PieceType::Bishop => 855,
+            PieceType::Rook => 990,
+            PieceType::King => 0,
+            PieceType::ProPawn => 540,
+            PieceType::ProLance => 540,
+            PieceType::ProKnight => 540,
+            PieceType::ProSilver => 540,
+            PieceType::Horse => 945,
+            PieceType::Dragon => 1395,
+        })
+    }
+}
+
+impl PieceValues {
+    /// f(pt: PieceType) -> i32 を用いて初期化した表を返す。
+    pub fn new<F>(mut f: F) -> Self
+    where
+        F: FnMut(PieceType) -> i32,
+    {
+        let mut values = [0; 14];
+        for &pt in PieceType::ALL.iter() {
+            values[pt as usize] = f(pt);
+        }
+        Self(values)
+    }
+
+    pub fn get(&self, pt: PieceType) -> i32 {
+        self.0[pt as usize]
+    }
+
+    pub fn set(&mut self, pt: PieceType, value: i32) {
+        self.0[pt as usize] = value;
+    }
+
+    /// 歩の価値。駒割りを「歩何枚分」で扱うときの単位。
+    pub fn pawn(&self) -> i32 {
+        self.get(PieceType::Pawn)
+    }
+}
+
+/// `side` 側の盤上の駒と持駒の価値の合計を返す。
+pub fn material(pos: &Position, side: Side, values: &PieceValues) -> i32 {
+    let on_board: i32 = pos
+        .board()
+        .iter()
+        .map(|(_, cell)| match cell {
+            BoardCell::Piece(s, pt) if s == side => values.get(pt),
+            _ => 0,
+        })
+        .sum();
+    let in_hand: i32 = pos
+        .hand(side)
+        .enumerate()
+        .map(|(pt, n)| values.get(pt) * i32::from(n))
+        .sum();
+    on_board + in_hand
+}
+
+/// 先手から見た駒割り (先手の `material()` - 後手の `material()`) を返す。
+pub fn balance(pos: &Position, values: &PieceValues) -> i32 {
+    material(pos, Side::Sente, values) - material(pos, Side::Gote, values)
+}
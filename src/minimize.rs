@@ -0,0 +1,52 @@
+//! 指し手リストの縮小 (delta debugging)。
+
+use crate::*;
+
+/// 述語 `pred` を満たしたまま指し手リストを縮小する (delta debugging)。
+///
+/// まず `pred` を満たす最短の接頭辞を求め、次にそこから指し手を取り除けるだけ取り除く
+/// (ddmin アルゴリズム)。結果は 1-minimal (どの1手を取り除いても `pred` を満たさない)
+/// である。
+///
+/// 縮小途中の指し手リストは適用不能なこともあるので、`pred` 側で適切に扱う必要がある。
+/// 元の指し手リストが `pred` を満たさない場合、元の指し手リストをそのまま返す。
+pub fn minimize<F>(pos: &Position, mvs: &[Move], mut pred: F) -> Vec<Move>
+where
+    F: FnMut(&Position, &[Move]) -> bool,
+{
+    let len_prefix = match (0..=mvs.len()).find(|&len| pred(pos, &mvs[..len])) {
+        Some(len) => len,
+        None => return mvs.to_vec(),
+    };
+    let mut cur = mvs[..len_prefix].to_vec();
+
+    let mut n_chunk = 2;
+    while cur.len() >= 2 {
+        let chunk_len = (cur.len() + n_chunk - 1) / n_chunk;
+
+        let reduced = (0..cur.len()).step_by(chunk_len).find_map(|start| {
+            let end = (start + chunk_len).min(cur.len());
+            let complement: Vec<_> = cur[..start].iter().chain(&cur[end..]).copied().collect();
+            if pred(pos, &complement) {
+                Some(complement)
+            } else {
+                None
+            }
+        });
+
+        match reduced {
+            Some(complement) => {
+                cur = complement;
+                n_chunk = (n_chunk - 1).max(2);
+            }
+            None => {
+                if n_chunk >= cur.len() {
+                    break;
+                }
+                n_chunk = (2 * n_chunk).min(cur.len());
+            }
+        }
+    }
+
+    cur
+}
@@ -0,0 +1,418 @@
+//! 指し手生成。
+
+use crate::attack::{between, offset, orient};
+use crate::bitboard::Bitboard;
+use crate::region::Region;
+use crate::*;
+
+impl Position {
+    /// 合法手を全て返す。
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut mvs = self.pseudo_legal_moves();
+        mvs.retain(|&mv| self.is_legal(mv));
+        mvs
+    }
+
+    /// 合法手を、駒を取る手と成る手、それ以外の駒を動かす手、駒を打つ手の順に遅延的に返す。
+    ///
+    /// 各段階の手はその段階に達したときに生成され、合法性は1手ずつ返す直前に判定される。
+    pub fn moves_staged(&self) -> StagedMoves<'_> {
+        StagedMoves {
+            pos: self,
+            stage: Stage::Start,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 擬似合法手を全て返す。
+    ///
+    /// 擬似合法手とは、駒の動きとして正しく、自駒を取らず、行き所のない駒を生じず、
+    /// 二歩にならない手のこと。自玉を王手にさらす手と打ち歩詰めは含まれうる。
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut mvs = Vec::with_capacity(128);
+        self.gen_nondrops(&mut mvs);
+        self.gen_drops(&mut mvs);
+        mvs
+    }
+
+    /// 擬似合法手 `mv` が合法かどうか (自玉を王手にさらさず、打ち歩詰めでないか) を返す。
+    ///
+    /// `mv` は擬似合法手でなければならない。擬似合法手でない手に対する結果は未規定。
+    /// 任意の手の合法性を調べるには `is_pseudo_legal()` と組み合わせる。
+    pub fn is_legal(&self, mv: Move) -> bool {
+        let side = self.side();
+
+        let mut next = self.clone();
+        if next.apply_move_in_place(mv).is_err() {
+            return false;
+        }
+
+        if let Some(king) = next.board().king(side) {
+            if next.board().is_attacked(king, side.flip()) {
+                return false;
+            }
+        }
+
+        if let Move::Drop(drop) = mv {
+            if drop.pt() == PieceType::Pawn && next.is_drop_pawn_mate(drop.dst()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// 王手がかかっている局面での合法手 (王手回避手) を全て返す。
+    ///
+    /// 玉の移動、王手をかけている駒の捕獲、合駒 (移動合と打ち合) のみを生成するので、
+    /// `legal_moves()` より速い。王手がかかっていない場合は `legal_moves()` と同じ。
+    pub fn evasion_moves(&self) -> Vec<Move> {
+        let checkers = self.checkers();
+        if checkers.is_empty() {
+            return self.legal_moves();
+        }
+
+        let side = self.side();
+        let board = self.board();
+        let king = board.king(side).expect("internal error");
+        let mut mvs = Vec::with_capacity(32);
+
+        for dst in board.attacks_from(king, side, PieceType::King) {
+            if !matches!(board.cell(dst), BoardCell::Piece(s, _) if s == side) {
+                mvs.push(Move::nondrop(king, dst, false));
+            }
+        }
+
+        // 両王手なら玉を動かすしかない。
+        if let [checker] = checkers[..] {
+            let blocks = between(king, checker);
+
+            for src in all_squares() {
+                let pt = match board.cell(src) {
+                    BoardCell::Piece(s, pt) if s == side && pt != PieceType::King => pt,
+                    _ => continue,
+                };
+                for dst in board.attacks_from(src, side, pt) {
+                    if dst == checker || blocks.contains(&dst) {
+                        push_nondrops(&mut mvs, side, pt, src, dst);
+                    }
+                }
+            }
+
+            for (pt, n) in self.hand(side).enumerate() {
+                if n == 0 {
+                    continue;
+                }
+                for &dst in &blocks {
+                    if is_dead_square(pt, dst, side) {
+                        continue;
+                    }
+                    if pt == PieceType::Pawn && self.has_pawn_on_file(side, dst.x()) {
+                        continue;
+                    }
+                    mvs.push(Move::drop(pt, dst));
+                }
+            }
+        }
+
+        mvs.retain(|&mv| self.is_legal(mv));
+        mvs
+    }
+
+    /// 手番側が持駒 `pt` を打てるマスの集合を返す。
+    ///
+    /// 空きマスであること、行き所のない駒にならないこと、二歩にならないことのみを考慮する。
+    /// 自玉を王手にさらす打ち方や打ち歩詰めになるマスも含まれうる。
+    /// `pt` を持っていない場合は空。
+    pub fn drop_targets(&self, pt: PieceType) -> Bitboard {
+        let side = self.side();
+        if !pt.is_hand() || self.hand(side).count(pt) == 0 {
+            return Bitboard::EMPTY;
+        }
+
+        all_squares()
+            .filter(|&dst| {
+                self.board().cell(dst) == BoardCell::Empty
+                    && !is_dead_square(pt, dst, side)
+                    && !(pt == PieceType::Pawn && self.has_pawn_on_file(side, dst.x()))
+            })
+            .collect()
+    }
+
+    /// 手番側が持駒 `pt` を打つ合法手を全て返す。打ち歩詰めや王手放置になる手は含まない。
+    pub fn drop_moves(&self, pt: PieceType) -> Vec<Move> {
+        self.drop_targets(pt)
+            .iter()
+            .map(|dst| Move::drop(pt, dst))
+            .filter(|&mv| self.is_legal(mv))
+            .collect()
+    }
+
+    /// マス `src` にある手番側の駒を動かす合法手を全て返す。
+    ///
+    /// `src` に手番側の駒がなければ空。
+    pub fn moves_from(&self, src: Square) -> Vec<Move> {
+        let side = self.side();
+        let board = self.board();
+        let pt = match board.cell(src) {
+            BoardCell::Piece(s, pt) if s == side => pt,
+            _ => return Vec::new(),
+        };
+
+        let mut mvs = Vec::with_capacity(32);
+        for dst in board.attacks_from(src, side, pt) {
+            if matches!(board.cell(dst), BoardCell::Piece(s, _) if s == side) {
+                continue;
+            }
+            push_nondrops(&mut mvs, side, pt, src, dst);
+        }
+
+        mvs.retain(|&mv| self.is_legal(mv));
+        mvs
+    }
+
+    /// マス `dst` に移動する、または駒を打つ合法手を全て返す。
+    ///
+    /// `dst` に手番側の駒があれば空。
+    pub fn moves_to(&self, dst: Square) -> Vec<Move> {
+        let side = self.side();
+        let board = self.board();
+        if matches!(board.cell(dst), BoardCell::Piece(s, _) if s == side) {
+            return Vec::new();
+        }
+
+        let mut mvs = Vec::with_capacity(16);
+        for src in board.attackers_to(dst, side) {
+            if let BoardCell::Piece(_, pt) = board.cell(src) {
+                push_nondrops(&mut mvs, side, pt, src, dst);
+            }
+        }
+
+        if board.cell(dst) == BoardCell::Empty {
+            for (pt, n) in self.hand(side).enumerate() {
+                if n == 0 || is_dead_square(pt, dst, side) {
+                    continue;
+                }
+                if pt == PieceType::Pawn && self.has_pawn_on_file(side, dst.x()) {
+                    continue;
+                }
+                mvs.push(Move::drop(pt, dst));
+            }
+        }
+
+        mvs.retain(|&mv| self.is_legal(mv));
+        mvs
+    }
+
+    /// 手番側の玉に王手がかかっているかどうかを返す。手番側の玉がない場合は false。
+    pub fn is_check(&self) -> bool {
+        !self.checkers().is_empty()
+    }
+
+    /// 手番側の玉に王手をかけている駒のマスを返す。
+    pub fn checkers(&self) -> Vec<Square> {
+        let side = self.side();
+        match self.board().king(side) {
+            Some(king) => self.board().attackers_to(king, side.flip()),
+            None => Vec::new(),
+        }
+    }
+
+    /// 手番側が詰んでいるかどうかを返す。
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_moves().is_empty()
+    }
+
+    /// 任意の手 `mv` が擬似合法手かどうかを返す。
+    pub fn is_pseudo_legal(&self, mv: Move) -> bool {
+        let side = self.side();
+
+        match mv {
+            Move::Nondrop(nondrop) => {
+                let (src, dst) = (nondrop.src(), nondrop.dst());
+                let pt = match self.board().cell(src) {
+                    BoardCell::Piece(s, pt) if s == side => pt,
+                    _ => return false,
+                };
+                if !self.board().attacks_from(src, side, pt).contains(&dst) {
+                    return false;
+                }
+                if matches!(self.board().cell(dst), BoardCell::Piece(s, _) if s == side) {
+                    return false;
+                }
+                if nondrop.is_promotion() {
+                    can_promote(pt, src, dst, side)
+                } else {
+                    !is_dead_square(pt, dst, side)
+                }
+            }
+            Move::Drop(drop) => {
+                let (pt, dst) = (drop.pt(), drop.dst());
+                pt.is_hand()
+                    && self.hand(side).count(pt) > 0
+                    && self.board().cell(dst) == BoardCell::Empty
+                    && !is_dead_square(pt, dst, side)
+                    && !(pt == PieceType::Pawn && self.has_pawn_on_file(side, dst.x()))
+            }
+        }
+    }
+
+    fn gen_nondrops(&self, mvs: &mut Vec<Move>) {
+        let side = self.side();
+        let board = self.board();
+
+        for src in all_squares() {
+            let pt = match board.cell(src) {
+                BoardCell::Piece(s, pt) if s == side => pt,
+                _ => continue,
+            };
+            for dst in board.attacks_from(src, side, pt) {
+                if matches!(board.cell(dst), BoardCell::Piece(s, _) if s == side) {
+                    continue;
+                }
+                push_nondrops(mvs, side, pt, src, dst);
+            }
+        }
+    }
+
+    /// 駒を動かす擬似合法手のうち、`pred(駒を取るか, 成るか)` を満たすものを追加する。
+    fn gen_nondrops_filtered(&self, mvs: &mut Vec<Move>, pred: impl Fn(bool, bool) -> bool) {
+        let side = self.side();
+        let mut buf = Vec::with_capacity(2);
+        for src in all_squares() {
+            let pt = match self.board().cell(src) {
+                BoardCell::Piece(s, pt) if s == side => pt,
+                _ => continue,
+            };
+            for dst in self.board().attacks_from(src, side, pt) {
+                let is_capture = match self.board().cell(dst) {
+                    BoardCell::Piece(s, _) if s == side => continue,
+                    BoardCell::Piece(..) => true,
+                    BoardCell::Empty => false,
+                };
+                buf.clear();
+                push_nondrops(&mut buf, side, pt, src, dst);
+                mvs.extend(buf.iter().copied().filter(|mv| match mv {
+                    Move::Nondrop(nondrop) => pred(is_capture, nondrop.is_promotion()),
+                    Move::Drop(_) => false,
+                }));
+            }
+        }
+    }
+
+    fn gen_drops(&self, mvs: &mut Vec<Move>) {
+        for &pt in Hand::PTS.iter() {
+            mvs.extend(self.drop_targets(pt).iter().map(|dst| Move::drop(pt, dst)));
+        }
+    }
+
+    pub(crate) fn has_pawn_on_file(&self, side: Side, x: u8) -> bool {
+        (0..9).any(|y| self.board().at(x, y) == BoardCell::Piece(side, PieceType::Pawn))
+    }
+
+    /// 直前の手が `sq` への歩打ちであるとき、それが打ち歩詰めかどうかを返す。
+    fn is_drop_pawn_mate(&self, sq: Square) -> bool {
+        // 手番側 (打たれた側) の玉の頭に打った歩でなければ詰みではない。
+        let side = self.side();
+        let dropper = side.flip();
+        let king = match self.board().king(side) {
+            Some(king) => king,
+            None => return false,
+        };
+        if offset(sq, orient((0, -1), dropper)) != Some(king) {
+            return false;
+        }
+
+        self.pseudo_legal_moves()
+            .into_iter()
+            .all(|mv| !self.is_legal(mv))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Stage {
+    Start,
+    CapturesAndPromotions,
+    Quiets,
+    Drops,
+}
+
+/// `Position::moves_staged()` が返すイテレータ。
+#[derive(Debug)]
+pub struct StagedMoves<'a> {
+    pos: &'a Position,
+    stage: Stage,
+    /// 現段階の未返却の擬似合法手 (逆順)。
+    buf: Vec<Move>,
+}
+
+impl Iterator for StagedMoves<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        loop {
+            while let Some(mv) = self.buf.pop() {
+                if self.pos.is_legal(mv) {
+                    return Some(mv);
+                }
+            }
+
+            self.stage = match self.stage {
+                Stage::Start => {
+                    self.pos
+                        .gen_nondrops_filtered(&mut self.buf, |capture, promo| capture || promo);
+                    Stage::CapturesAndPromotions
+                }
+                Stage::CapturesAndPromotions => {
+                    self.pos
+                        .gen_nondrops_filtered(&mut self.buf, |capture, promo| !capture && !promo);
+                    Stage::Quiets
+                }
+                Stage::Quiets => {
+                    self.pos.gen_drops(&mut self.buf);
+                    Stage::Drops
+                }
+                Stage::Drops => return None,
+            };
+            self.buf.reverse();
+        }
+    }
+}
+
+/// `side` 側の駒 `pt` の `src` から `dst` への移動を、成り・不成それぞれ可能なら追加する。
+fn push_nondrops(mvs: &mut Vec<Move>, side: Side, pt: PieceType, src: Square, dst: Square) {
+    if can_promote(pt, src, dst, side) {
+        mvs.push(Move::nondrop(src, dst, true));
+    }
+    if !is_dead_square(pt, dst, side) {
+        mvs.push(Move::nondrop(src, dst, false));
+    }
+}
+
+/// 盤上の全マスを返す。
+pub(crate) fn all_squares() -> impl Iterator<Item = Square> {
+    (0..9).flat_map(|y| (0..9).map(move |x| Square::new(x, y)))
+}
+
+/// マス `sq` が `side` 側にとっての敵陣 (成れる領域) かどうかを返す。
+pub(crate) fn is_promotion_zone(sq: Square, side: Side) -> bool {
+    Region::promotion_zone(side).contains(sq)
+}
+
+/// `side` 側の駒 `pt` が `src` から `dst` への移動で成れるかどうかを返す。
+pub(crate) fn can_promote(pt: PieceType, src: Square, dst: Square, side: Side) -> bool {
+    pt.to_promoted().is_some() && (is_promotion_zone(src, side) || is_promotion_zone(dst, side))
+}
+
+/// `side` 側の駒 `pt` がマス `sq` に置かれたとき行き所がないかどうかを返す。
+pub(crate) fn is_dead_square(pt: PieceType, sq: Square, side: Side) -> bool {
+    let rank = match side {
+        Side::Sente => sq.y(),
+        Side::Gote => 8 - sq.y(),
+    };
+    match pt {
+        PieceType::Pawn | PieceType::Lance => rank == 0,
+        PieceType::Knight => rank <= 1,
+        _ => false,
+    }
+}
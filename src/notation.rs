@@ -0,0 +1,45 @@
+//! 棋譜形式の共通インターフェース。
+//!
+//! 各形式は `EncodeNotation`/`DecodeNotation` を実装する。局面と指し手列の組
+//! `(Position, Vec<Move>)` を形式間の共通の表現とする。
+
+use crate::*;
+
+/// 局面と指し手列を文字列に変換する形式。
+///
+/// 指し手列を局面上で再生する形式では、不正な手があればエラーを返す。
+pub trait EncodeNotation {
+    fn encode_notation(pos: &Position, mvs: &[Move]) -> Result<String>;
+}
+
+/// 文字列を局面と指し手列に変換する形式。
+pub trait DecodeNotation {
+    fn decode_notation(s: &str) -> Result<(Position, Vec<Move>)>;
+}
+
+/// 読み書き両方に対応した形式。
+pub trait Notation: EncodeNotation + DecodeNotation {}
+
+impl<N: EncodeNotation + DecodeNotation> Notation for N {}
+
+/// SFEN 形式。`encode()`/`decode()` と同じ。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Sfen;
+
+impl EncodeNotation for Sfen {
+    fn encode_notation(pos: &Position, mvs: &[Move]) -> Result<String> {
+        Ok(encode(pos, mvs))
+    }
+}
+
+impl DecodeNotation for Sfen {
+    fn decode_notation(s: &str) -> Result<(Position, Vec<Move>)> {
+        decode(s)
+    }
+}
+
+/// 形式 `F` の文字列を形式 `T` に変換する。
+pub fn convert<F: DecodeNotation, T: EncodeNotation>(s: &str) -> Result<String> {
+    let (pos, mvs) = F::decode_notation(s)?;
+    T::encode_notation(&pos, &mvs)
+}
@@ -0,0 +1,354 @@
+//! やねうら王の PackedSfen (局面を 256 bit にハフマン符号化したもの) と
+//! PackedSfenValue (教師局面の 40 byte のレコード)。
+//!
+//! PackedSfen は両玉を含む全 40 枚の駒が盤上または持駒にある局面のみを表せる。
+//! 手数は含まれない。
+
+use crate::*;
+
+/// 盤上の駒のハフマン符号 (code, bits)。code は下位ビットから順に書き出す。
+/// 空きマスは (0, 1)。持駒は code >> 1, bits - 1 を用いる。
+fn huffman(pt: PieceType) -> (u8, u32) {
+    match pt {
+        PieceType::Pawn => (0x01, 2),
+        PieceType::Lance => (0x03, 4),
+        PieceType::Knight => (0x0B, 4),
+        PieceType::Silver => (0x07, 4),
+        PieceType::Bishop => (0x1F, 6),
+        PieceType::Rook => (0x3F, 6),
+        PieceType::Gold => (0x0F, 5),
+        _ => unreachable!("internal error"),
+    }
+}
+
+/// 符号化の対象となる駒種 (持駒になりうる駒種)。
+const HUFFMAN_PTS: [PieceType; 7] = [
+    PieceType::Pawn,
+    PieceType::Lance,
+    PieceType::Knight,
+    PieceType::Silver,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Gold,
+];
+
+/// 局面を PackedSfen にする。
+///
+/// 両玉がちょうど1枚ずつなく、全 40 枚の駒が揃っていない局面はエラーを返す。
+pub fn encode(pos: &Position) -> Result<[u8; 32]> {
+    let mut stream = BitWriter::default();
+
+    stream.write_bit(pos.side() == Side::Gote);
+    for side in [Side::Sente, Side::Gote] {
+        let kings: Vec<_> = pos
+            .board()
+            .iter()
+            .filter(|&(_, cell)| cell == BoardCell::Piece(side, PieceType::King))
+            .map(|(sq, _)| sq)
+            .collect();
+        match kings[..] {
+            [sq] => stream.write_bits(u32::from(sq_to_yo(sq)), 7),
+            _ => {
+                return Err(Error::encode_error(
+                    "packed: each side must have exactly one king",
+                ))
+            }
+        }
+    }
+
+    for yo in 0..81 {
+        let sq = sq_from_yo(yo);
+        match pos.board().cell(sq) {
+            BoardCell::Empty => stream.write_bit(false),
+            BoardCell::Piece(_, PieceType::King) => {}
+            BoardCell::Piece(side, pt) => {
+                let raw = pt.to_unpromoted();
+                let (code, bits) = huffman(raw);
+                stream.write_bits(u32::from(code), bits);
+                if raw != PieceType::Gold {
+                    stream.write_bit(raw != pt);
+                }
+                stream.write_bit(side == Side::Gote);
+            }
+        }
+    }
+
+    for side in [Side::Sente, Side::Gote] {
+        for &pt in HUFFMAN_PTS.iter() {
+            for _ in 0..pos.hand(side).count(pt) {
+                let (code, bits) = huffman(pt);
+                stream.write_bits(u32::from(code >> 1), bits - 1);
+                if pt != PieceType::Gold {
+                    stream.write_bit(false);
+                }
+                stream.write_bit(side == Side::Gote);
+            }
+        }
+    }
+
+    stream.finish().ok_or_else(|| {
+        Error::encode_error("packed: position must have exactly 40 pieces including kings")
+    })
+}
+
+/// PackedSfen をパースする。手数は 1 とする。
+pub fn decode(packed: &[u8; 32]) -> Result<Position> {
+    let err = || Error::decode_error(ErrorCode::Packed, "packed: invalid packed sfen");
+
+    let mut stream = BitReader::new(packed);
+
+    let side = if stream.read_bit().ok_or_else(err)? {
+        Side::Gote
+    } else {
+        Side::Sente
+    };
+
+    let mut board = Board::empty();
+    for side in [Side::Sente, Side::Gote] {
+        let yo = stream.read_bits(7).ok_or_else(err)? as u8;
+        if yo >= 81 {
+            return Err(err());
+        }
+        board.set(sq_from_yo(yo), BoardCell::Piece(side, PieceType::King));
+    }
+
+    for yo in 0..81 {
+        let sq = sq_from_yo(yo);
+        if board.cell(sq) != BoardCell::Empty {
+            continue;
+        }
+        let pt = match stream.read_huffman(false).ok_or_else(err)? {
+            None => continue,
+            Some(pt) => pt,
+        };
+        let promoted = pt != PieceType::Gold && stream.read_bit().ok_or_else(err)?;
+        let pt = if promoted {
+            pt.to_promoted().expect("internal error")
+        } else {
+            pt
+        };
+        let side = if stream.read_bit().ok_or_else(err)? {
+            Side::Gote
+        } else {
+            Side::Sente
+        };
+        board.set(sq, BoardCell::Piece(side, pt));
+    }
+
+    let mut hands = [Hand::empty(), Hand::empty()];
+    while !stream.is_end() {
+        let pt = stream
+            .read_huffman(true)
+            .ok_or_else(err)?
+            .expect("internal error");
+        if pt != PieceType::Gold && stream.read_bit().ok_or_else(err)? {
+            return Err(err());
+        }
+        let side = if stream.read_bit().ok_or_else(err)? {
+            Side::Gote
+        } else {
+            Side::Sente
+        };
+        hands[side as usize].add(pt, 1).ok_or_else(err)?;
+    }
+
+    let [hand_sente, hand_gote] = hands;
+    Ok(Position::new(side, board, hand_sente, hand_gote, 1))
+}
+
+/// やねうら王の教師局面のレコード (PackedSfenValue)。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PackedSfenValue {
+    /// 局面。手数は `game_ply` と同じ。
+    pub position: Position,
+    /// 手番側から見た評価値。
+    pub score: i16,
+    /// 指し手。ファイル上で 0 (指し手なし) なら None。
+    pub mv: Option<Move>,
+    pub game_ply: u16,
+    /// 手番側から見た対局結果。1 なら勝ち、-1 なら負け、0 なら引き分け。
+    pub game_result: i8,
+}
+
+impl PackedSfenValue {
+    /// レコードのバイト数。
+    pub const SIZE: usize = 40;
+
+    /// 40 byte のレコードをパースする。
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Result<Self> {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+
+        let mut packed = [0; 32];
+        packed.copy_from_slice(&bytes[..32]);
+        let mut position = decode(&packed)?;
+
+        let game_ply = u16_at(36);
+        position.ply = i32::from(game_ply);
+
+        Ok(Self {
+            position,
+            score: u16_at(32) as i16,
+            mv: decode_move16(u16_at(34))?,
+            game_ply,
+            game_result: bytes[38] as i8,
+        })
+    }
+
+    /// 40 byte のレコードにする。`position` の手数は無視し、`game_ply` を書き出す。
+    pub fn to_bytes(&self) -> Result<[u8; Self::SIZE]> {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..32].copy_from_slice(&encode(&self.position)?);
+        bytes[32..34].copy_from_slice(&self.score.to_le_bytes());
+        bytes[34..36].copy_from_slice(&self.mv.map_or(0, encode_move16).to_le_bytes());
+        bytes[36..38].copy_from_slice(&self.game_ply.to_le_bytes());
+        bytes[38] = self.game_result as u8;
+        Ok(bytes)
+    }
+}
+
+const MOVE16_DROP: u16 = 1 << 14;
+const MOVE16_PROMOTE: u16 = 1 << 15;
+
+/// 指し手をやねうら王の 16 bit 表現 (Move16) にする。
+pub fn encode_move16(mv: Move) -> u16 {
+    match mv {
+        Move::Nondrop(nd) => {
+            let promote = if nd.is_promotion() { MOVE16_PROMOTE } else { 0 };
+            u16::from(sq_to_yo(nd.dst())) | u16::from(sq_to_yo(nd.src())) << 7 | promote
+        }
+        // やねうら王の駒種は歩が 1。
+        Move::Drop(d) => u16::from(sq_to_yo(d.dst())) | (d.pt() as u16 + 1) << 7 | MOVE16_DROP,
+    }
+}
+
+/// やねうら王の 16 bit 表現 (Move16) の指し手をパースする。0 (指し手なし) なら None を返す。
+pub fn decode_move16(x: u16) -> Result<Option<Move>> {
+    let err = || {
+        Error::decode_error(
+            ErrorCode::Packed,
+            format!("packed: invalid move16: {:#06x}", x),
+        )
+    };
+
+    if x == 0 {
+        return Ok(None);
+    }
+    let dst = (x & 0x7F) as u8;
+    let src = (x >> 7 & 0x7F) as u8;
+    if dst >= 81 {
+        return Err(err());
+    }
+    let dst = sq_from_yo(dst);
+
+    let mv = if x & MOVE16_DROP != 0 {
+        if x & MOVE16_PROMOTE != 0 {
+            return Err(err());
+        }
+        let pt = src
+            .checked_sub(1)
+            .and_then(|i| HUFFMAN_PTS.iter().copied().find(|&pt| pt as u8 == i))
+            .ok_or_else(err)?;
+        Move::drop(pt, dst)
+    } else {
+        if src >= 81 {
+            return Err(err());
+        }
+        Move::nondrop(sq_from_yo(src), dst, x & MOVE16_PROMOTE != 0)
+    };
+    Ok(Some(mv))
+}
+
+/// やねうら王のマス番号 (1一 が 0、1二 が 1、...、9九 が 80) にする。
+fn sq_to_yo(sq: Square) -> u8 {
+    9 * sq.x() + sq.y()
+}
+
+fn sq_from_yo(yo: u8) -> Square {
+    Square::new(yo / 9, yo % 9)
+}
+
+/// 256 bit のビット列の書き込み。各バイトの下位ビットから順に書く。
+#[derive(Debug, Default)]
+struct BitWriter {
+    data: [u8; 32],
+    cursor: usize,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, b: bool) {
+        if self.cursor < 256 && b {
+            self.data[self.cursor / 8] |= 1 << (self.cursor % 8);
+        }
+        self.cursor += 1;
+    }
+
+    fn write_bits(&mut self, x: u32, n: u32) {
+        for i in 0..n {
+            self.write_bit(x >> i & 1 != 0);
+        }
+    }
+
+    /// ちょうど 256 bit 書き込まれていればデータを返す。
+    fn finish(self) -> Option<[u8; 32]> {
+        (self.cursor == 256).then_some(self.data)
+    }
+}
+
+#[derive(Debug)]
+struct BitReader<'a> {
+    data: &'a [u8; 32],
+    cursor: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8; 32]) -> Self {
+        Self { data, cursor: 0 }
+    }
+
+    fn is_end(&self) -> bool {
+        self.cursor == 256
+    }
+
+    fn read_bit(&mut self) -> Option<bool> {
+        if self.is_end() {
+            return None;
+        }
+        let b = self.data[self.cursor / 8] >> (self.cursor % 8) & 1 != 0;
+        self.cursor += 1;
+        Some(b)
+    }
+
+    fn read_bits(&mut self, n: u32) -> Option<u32> {
+        let mut x = 0;
+        for i in 0..n {
+            x |= u32::from(self.read_bit()?) << i;
+        }
+        Some(x)
+    }
+
+    /// ハフマン符号を1つ読む。盤上の駒の符号なら空きマスは None となる。
+    fn read_huffman(&mut self, hand: bool) -> Option<Option<PieceType>> {
+        let (mut code, mut bits) = (0, 0);
+        loop {
+            code |= u8::from(self.read_bit()?) << bits;
+            bits += 1;
+            if !hand && (code, bits) == (0, 1) {
+                return Some(None);
+            }
+            let found = HUFFMAN_PTS.iter().copied().find(|&pt| {
+                let (c, b) = huffman(pt);
+                if hand {
+                    (c >> 1, b - 1) == (code, bits)
+                } else {
+                    (c, b) == (code, bits)
+                }
+            });
+            if let Some(pt) = found {
+                return Some(Some(pt));
+            }
+            if bits >= 6 {
+                return None;
+            }
+        }
+    }
+}
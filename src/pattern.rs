@@ -0,0 +1,189 @@
+//! 盤面の部分的な条件を記述するパターン。
+//!
+//! パターンは空白区切りの条件の並びで、全ての条件を満たす盤面にマッチする。
+//! 各条件は `<対象><量化子><マス集合>` の形をとる:
+//!
+//! * 対象: sfen の駒文字 (`K`, `+r` など。大文字が先手)、`.` (空きマス)、`*` (任意の駒)
+//! * 量化子: `@` (マス集合のいずれかが対象)、`=` (マス集合の全てが対象)
+//! * マス集合: 筋と段の組。筋は `5`, `[6-8]`, `*` (全筋)、段は `i`, `[g-i]`, `*` (全段)。
+//!   単独の `*` は盤面全体を表す。
+//!
+//! 例: `K@[7-8][h-i] G@[6-7][h-i] .=5*` は「先手玉が 7八-8九 の範囲にあり、
+//! 先手金が 6八-7九 の範囲にあり、5筋が全て空いている」盤面にマッチする。
+
+use std::str::FromStr;
+
+use crate::bitboard::Bitboard;
+use crate::region::Region;
+use crate::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Pattern {
+    conds: Vec<Cond>,
+}
+
+impl Pattern {
+    /// パターン文字列をパースする。
+    pub fn parse(s: impl AsRef<str>) -> Result<Self> {
+        let conds = s
+            .as_ref()
+            .split_ascii_whitespace()
+            .map(parse_cond)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { conds })
+    }
+
+    /// 盤面がパターンにマッチするかどうかを返す。
+    pub fn matches(&self, board: &Board) -> bool {
+        self.conds.iter().all(|cond| cond.matches(board))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Target {
+    Empty,
+    Occupied,
+    Piece(Side, PieceType),
+}
+
+impl Target {
+    fn matches(self, cell: BoardCell) -> bool {
+        match self {
+            Self::Empty => cell == BoardCell::Empty,
+            Self::Occupied => cell != BoardCell::Empty,
+            Self::Piece(side, pt) => cell == BoardCell::Piece(side, pt),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Quantifier {
+    Any,
+    All,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Cond {
+    target: Target,
+    quant: Quantifier,
+    /// 対象マスの集合。
+    squares: Bitboard,
+}
+
+impl Cond {
+    fn matches(&self, board: &Board) -> bool {
+        let mut it = self.squares.iter().map(|sq| board.at(sq.x(), sq.y()));
+        match self.quant {
+            Quantifier::Any => it.any(|cell| self.target.matches(cell)),
+            Quantifier::All => it.all(|cell| self.target.matches(cell)),
+        }
+    }
+}
+
+fn parse_cond(s: &str) -> Result<Cond> {
+    let idx = s
+        .find(['@', '='])
+        .ok_or_else(|| Error::pattern_error(format!("quantifier expected: {}", s)))?;
+    let (s_target, s_rest) = s.split_at(idx);
+    let quant = if s_rest.starts_with('@') {
+        Quantifier::Any
+    } else {
+        Quantifier::All
+    };
+
+    let target = parse_target(s_target)?;
+    let squares = parse_squares(&s_rest[1..])?;
+
+    Ok(Cond {
+        target,
+        quant,
+        squares,
+    })
+}
+
+fn parse_target(s: &str) -> Result<Target> {
+    let invalid = || Error::pattern_error(format!("invalid target: {}", s));
+
+    match s {
+        "." => return Ok(Target::Empty),
+        "*" => return Ok(Target::Occupied),
+        _ => {}
+    }
+
+    let (promo, s_piece) = match s.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let mut cs = s_piece.chars();
+    let c = cs.next().ok_or_else(invalid)?;
+    if cs.next().is_some() {
+        return Err(invalid());
+    }
+
+    let (side, mut pt) = decode::char_to_side_pt(c).ok_or_else(invalid)?;
+    if promo {
+        pt = pt.to_promoted().ok_or_else(invalid)?;
+    }
+
+    Ok(Target::Piece(side, pt))
+}
+
+fn parse_squares(s: &str) -> Result<Bitboard> {
+    let invalid = || Error::pattern_error(format!("invalid squares: {}", s));
+
+    let region = if s == "*" {
+        Region::all()
+    } else {
+        let (xs, rest) = parse_range(s, '1'..='9').ok_or_else(invalid)?;
+        let (ys, rest) = parse_range(rest, 'a'..='i').ok_or_else(invalid)?;
+        if !rest.is_empty() {
+            return Err(invalid());
+        }
+        Region::new(xs, ys)
+    };
+
+    Ok(region.squares().collect())
+}
+
+/// 筋または段の範囲 (`5`, `[6-8]`, `*`) をパースし、(0-based の範囲, 残りの文字列) を返す。
+fn parse_range(
+    s: &str,
+    chars: std::ops::RangeInclusive<char>,
+) -> Option<(std::ops::Range<u8>, &str)> {
+    let to_idx = |c: char| {
+        if chars.contains(&c) {
+            Some(c as u8 - *chars.start() as u8)
+        } else {
+            None
+        }
+    };
+
+    let mut cs = s.chars();
+    match cs.next()? {
+        '*' => Some((0..9, cs.as_str())),
+        '[' => {
+            let lo = to_idx(cs.next()?)?;
+            if cs.next()? != '-' {
+                return None;
+            }
+            let hi = to_idx(cs.next()?)?;
+            if cs.next()? != ']' || lo > hi {
+                return None;
+            }
+            Some((lo..hi + 1, cs.as_str()))
+        }
+        c => {
+            let idx = to_idx(c)?;
+            Some((idx..idx + 1, cs.as_str()))
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! 指し手生成の検証用の perft。
+
+use crate::*;
+
+/// 局面 `pos` から深さ `depth` までの合法手の列を数え上げ、末端の局面数を返す。
+///
+/// `depth` が 0 のときは 1 を返す。
+pub fn perft(pos: &Position, depth: u32) -> u64 {
+    let mut pos = pos.clone();
+    perft_impl(&mut pos, depth)
+}
+
+fn perft_impl(pos: &mut Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mvs = pos.legal_moves();
+    if depth == 1 {
+        return mvs.len() as u64;
+    }
+
+    mvs.into_iter()
+        .map(|mv| {
+            let undo = pos.do_move(mv).expect("internal error");
+            let n = perft_impl(pos, depth - 1);
+            pos.undo_move(undo);
+            n
+        })
+        .sum()
+}
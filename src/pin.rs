@@ -0,0 +1,62 @@
+//! ピンと開き王手の候補の検出。
+
+use crate::attack::{orient, ray, slides, Direction};
+use crate::bitboard::Bitboard;
+use crate::*;
+
+impl Position {
+    /// `side` 側の駒のうち、相手の走り駒によって自玉にピンされている駒のマスの集合を返す。
+    ///
+    /// `side` 側の玉がない場合は空。
+    pub fn pinned_pieces(&self, side: Side) -> Bitboard {
+        match self.board().king(side) {
+            Some(king) => self.board().blockers(king, side.flip(), side),
+            None => Bitboard::EMPTY,
+        }
+    }
+
+    /// `side` 側の駒のうち、動けば `side` 側の走り駒による開き王手になりうる駒のマスの集合を返す。
+    ///
+    /// 相手の玉がない場合は空。
+    pub fn discovered_check_candidates(&self, side: Side) -> Bitboard {
+        match self.board().king(side.flip()) {
+            Some(king) => self.board().blockers(king, side, side),
+            None => Bitboard::EMPTY,
+        }
+    }
+}
+
+impl Board {
+    /// マス `target` と `slider_side` 側の走り駒の間にちょうど1枚だけある
+    /// `blocker_side` 側の駒のマスの集合を返す。
+    pub(crate) fn blockers(
+        &self,
+        target: Square,
+        slider_side: Side,
+        blocker_side: Side,
+    ) -> Bitboard {
+        let mut res = Bitboard::EMPTY;
+
+        for &dir in Direction::ALL.iter() {
+            let mut pieces = ray(target, dir).filter_map(|sq| match self.cell(sq) {
+                BoardCell::Piece(side, pt) => Some((sq, side, pt)),
+                BoardCell::Empty => None,
+            });
+
+            let blocker = match pieces.next() {
+                Some((sq, side, _)) if side == blocker_side => sq,
+                _ => continue,
+            };
+            if let Some((_, side, pt)) = pieces.next() {
+                // 走り駒から target へ向かう方向に走れるか。
+                let toward = dir.reverse().delta();
+                let slides_toward = slides(pt).iter().any(|&d| orient(d, side) == toward);
+                if side == slider_side && slides_toward {
+                    res.insert(blocker);
+                }
+            }
+        }
+
+        res
+    }
+}
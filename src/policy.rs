@@ -0,0 +1,77 @@
+//! ポリシーネットワーク向けの、指し手と密なインデックスの間の全単射。
+//!
+//! インデックスは手番側から見た座標で計算する (後手番では盤面を 180 度回転する)。
+//! マス番号は `9 * y + x` (x = 0 が1筋、y = 0 が1段目)。
+//!
+//! * 0..13122: 駒を動かす手。`81 * (81 * 成り + 移動元) + 移動先` (成りは 0 または 1)
+//! * 13122..13689: 駒打ち。`13122 + 81 * 駒種 + 移動先` (駒種は歩 = 0, ..., 金 = 6)
+
+use crate::*;
+
+/// ポリシーインデックスの総数。
+pub const POLICY_SIZE: usize = 2 * 81 * 81 + 7 * 81;
+
+const N_NONDROP: usize = 2 * 81 * 81;
+
+const PTS_HAND: [PieceType; 7] = [
+    PieceType::Pawn,
+    PieceType::Lance,
+    PieceType::Knight,
+    PieceType::Silver,
+    PieceType::Bishop,
+    PieceType::Rook,
+    PieceType::Gold,
+];
+
+impl Move {
+    /// 手番 `side` から見たポリシーインデックス (0..POLICY_SIZE) を返す。
+    ///
+    /// 駒打ちの駒種が持駒になりえない駒の場合、panic する。
+    pub fn to_policy_index(self, side: Side) -> usize {
+        match self {
+            Move::Nondrop(nondrop) => {
+                let promo = usize::from(nondrop.is_promotion());
+                let src = relative_sq_idx(nondrop.src(), side);
+                let dst = relative_sq_idx(nondrop.dst(), side);
+                81 * (81 * promo + src) + dst
+            }
+            Move::Drop(drop) => {
+                let pt = drop.pt();
+                assert!(pt.is_hand(), "not hand piece: {:?}", pt);
+                N_NONDROP + 81 * pt as usize + relative_sq_idx(drop.dst(), side)
+            }
+        }
+    }
+
+    /// `to_policy_index()` の逆変換。`idx` が範囲外の場合、None を返す。
+    pub fn from_policy_index(idx: usize, side: Side) -> Option<Self> {
+        if idx < N_NONDROP {
+            let dst = relative_sq(idx % 81, side);
+            let src = relative_sq(idx / 81 % 81, side);
+            let is_promotion = idx / (81 * 81) == 1;
+            Some(Move::nondrop(src, dst, is_promotion))
+        } else if idx < POLICY_SIZE {
+            let idx = idx - N_NONDROP;
+            let dst = relative_sq(idx % 81, side);
+            Some(Move::drop(PTS_HAND[idx / 81], dst))
+        } else {
+            None
+        }
+    }
+}
+
+fn relative_sq_idx(sq: Square, side: Side) -> usize {
+    let idx = 9 * usize::from(sq.y()) + usize::from(sq.x());
+    match side {
+        Side::Sente => idx,
+        Side::Gote => 80 - idx,
+    }
+}
+
+fn relative_sq(idx: usize, side: Side) -> Square {
+    let idx = match side {
+        Side::Sente => idx,
+        Side::Gote => 80 - idx,
+    };
+    Square::new((idx % 9) as u8, (idx / 9) as u8)
+}
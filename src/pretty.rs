@@ -0,0 +1,134 @@
+//! デバッグ・ログ向けの局面の図示。
+
+use crate::codec::Codec;
+use crate::*;
+
+impl Position {
+    /// 局面を ASCII 文字のみの図にする。
+    ///
+    /// 駒は sfen と同じ文字 (先手は大文字、後手は小文字、成駒は "+" 付き) で表し、
+    /// 段は sfen と同じく a-i で表す。
+    ///
+    /// ```text
+    /// gote hand: -
+    ///   9  8  7  6  5  4  3  2  1
+    /// +---------------------------+
+    /// | l  n  s  g  k  g  s  n  l | a
+    /// | .  r  .  .  .  .  .  b  . | b
+    /// ...
+    /// | L  N  S  G  K  G  S  N  L | i
+    /// +---------------------------+
+    /// sente hand: -
+    /// sente to move, ply 1
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        let codec = &Codec::STANDARD;
+        let mut res = String::new();
+
+        let hand = |side: Side| {
+            let empty = Hand::empty();
+            let s = match side {
+                Side::Sente => encode::encode_hands(codec, self.hand(side), &empty),
+                Side::Gote => encode::encode_hands(codec, &empty, self.hand(side)),
+            };
+            format!("{} hand: {}\n", side.name(Lang::English), s)
+        };
+
+        res.push_str(&hand(Side::Gote));
+        for x in (0..9).rev() {
+            res.push_str("  ");
+            res.push(char::from(b'1' + x));
+        }
+        res.push('\n');
+        res.push_str("+---------------------------+\n");
+        for y in 0..9 {
+            res.push('|');
+            for x in (0..9).rev() {
+                match self.board.at(x, y) {
+                    BoardCell::Empty => res.push_str(" . "),
+                    BoardCell::Piece(side, pt) => {
+                        let s = codec.encode_piece(side, pt);
+                        res.push_str(&format!("{:>2} ", s));
+                    }
+                }
+            }
+            res.push_str("| ");
+            res.push(char::from(b'a' + y));
+            res.push('\n');
+        }
+        res.push_str("+---------------------------+\n");
+        res.push_str(&hand(Side::Sente));
+        res.push_str(&format!(
+            "{} to move, ply {}\n",
+            self.side.name(Lang::English),
+            self.ply
+        ));
+
+        res
+    }
+
+    /// 局面を漢字と罫線素片による図にする。
+    ///
+    /// 駒の表記は BOD 形式と同じ (後手の駒は "v" 付き)。
+    ///
+    /// ```text
+    /// 後手の持駒：なし
+    ///   ９ ８ ７ ６ ５ ４ ３ ２ １
+    /// ┌───────────────────────────┐
+    /// │v香v桂v銀v金v玉v金v銀v桂v香│一
+    /// │ ・v飛 ・ ・ ・ ・ ・v角 ・│二
+    /// ...
+    /// │ 香 桂 銀 金 玉 金 銀 桂 香│九
+    /// └───────────────────────────┘
+    /// 先手の持駒：なし
+    /// 先手番、1手目
+    /// ```
+    ///
+    /// 座標は全角で表記する。`to_unicode_with_style()` を参照。
+    pub fn to_unicode(&self) -> String {
+        self.to_unicode_with_style(CoordStyle::FullWidth)
+    }
+
+    /// 局面を、指定した座標の文字種で漢字と罫線素片による図にする。
+    ///
+    /// `to_unicode()` を参照。
+    pub fn to_unicode_with_style(&self, style: CoordStyle) -> String {
+        let mut res = String::new();
+
+        res.push_str(&bod::encode_hand(self, Side::Gote));
+        res.push(' ');
+        for x in (0..9).rev() {
+            match style {
+                CoordStyle::FullWidth => res.push(' '),
+                CoordStyle::Ascii => res.push_str("  "),
+            }
+            res.push(style.file(x));
+        }
+        res.push('\n');
+        res.push_str(&format!("┌{}┐\n", "─".repeat(27)));
+        for y in 0..9 {
+            res.push('│');
+            for x in (0..9).rev() {
+                match self.board.at(x, y) {
+                    BoardCell::Empty => res.push_str(" ・"),
+                    BoardCell::Piece(side, pt) => {
+                        res.push(if side == Side::Sente { ' ' } else { 'v' });
+                        res.push_str(pt.name(Lang::JapaneseShort));
+                    }
+                }
+            }
+            res.push('│');
+            res.push(style.rank(y));
+            res.push('\n');
+        }
+        res.push_str(&format!("└{}┘\n", "─".repeat(27)));
+        res.push_str(&bod::encode_hand(self, Side::Sente));
+        res.push_str(&format!(
+            "{}番、{}手目\n",
+            self.side.name(Lang::Japanese),
+            self.ply
+        ));
+
+        res
+    }
+}
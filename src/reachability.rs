@@ -0,0 +1,151 @@
+//! 平手の開始局面からの到達可能性の判定。
+
+use crate::movegen::{all_squares, can_promote, is_dead_square};
+use crate::*;
+
+impl Position {
+    /// 平手の開始局面から実戦で到達しうる局面かどうかを、ヒューリスティックに判定する。
+    ///
+    /// 以下のいずれかに該当する局面は到達不能とみなす:
+    ///
+    /// * `validate()` が失敗する
+    /// * 手番でない側の玉に王手がかかっている
+    /// * 手番側の玉に3枚以上の駒で王手がかかっている
+    /// * 手番と手数の偶奇が合わない (平手は手数 1 で先手番)
+    /// * 直前の手として考えられる手 (駒打ち、移動、移動と駒取り) が1つもない
+    ///
+    /// 最後の判定では直前の局面の候補を最大 `budget` 個まで調べる。
+    /// 上限に達した場合は到達可能とみなす。
+    /// `true` が返っても到達可能であるとは限らない。
+    pub fn is_reachable_from_startpos(&self, budget: usize) -> bool {
+        self.check_static_reachability() && self.has_retro_move(budget)
+    }
+
+    fn check_static_reachability(&self) -> bool {
+        if self.validate().is_err() {
+            return false;
+        }
+
+        let board = self.board();
+        let side = self.side();
+        let king_opp = board.king(side.flip()).expect("internal error");
+        if board.is_attacked(king_opp, side) {
+            return false;
+        }
+        let king = board.king(side).expect("internal error");
+        if board.attackers_to(king, side.flip()).len() >= 3 {
+            return false;
+        }
+
+        let sente_to_move = self.ply().rem_euclid(2) == 1;
+        sente_to_move == (side == Side::Sente)
+    }
+
+    /// 直前の手として考えられる手が存在するかどうかを返す。
+    fn has_retro_move(&self, budget: usize) -> bool {
+        let mover = self.side().flip();
+        let mut n_checked = 0;
+
+        // 直前の局面 prev で、手番でない側 (現在の手番側) に王手がかかっていないかを調べる。
+        // 調べた局面数が budget を超えたら true とする。
+        let mut check = |prev: &Position| -> bool {
+            n_checked += 1;
+            if n_checked > budget {
+                return true;
+            }
+            match prev.board().king(mover.flip()) {
+                Some(king) => !prev.board().is_attacked(king, mover),
+                None => false,
+            }
+        };
+
+        for dst in all_squares() {
+            let pt = match self.board().cell(dst) {
+                BoardCell::Piece(side, pt) if side == mover => pt,
+                _ => continue,
+            };
+
+            // 駒打ち
+            if pt.is_hand() {
+                let mut prev = self.retro_base();
+                prev.board.set(dst, BoardCell::Empty);
+                if prev.hands[mover as usize].add(pt, 1).is_some() && check(&prev) {
+                    return true;
+                }
+            }
+
+            // 移動 (成りを含む)
+            let mut pts_before = vec![pt];
+            if pt.to_unpromoted() != pt {
+                pts_before.push(pt.to_unpromoted());
+            }
+            for &pt_before in &pts_before {
+                for src in all_squares() {
+                    if self.board().cell(src) != BoardCell::Empty {
+                        continue;
+                    }
+                    if pt_before != pt && !can_promote(pt_before, src, dst, mover) {
+                        continue;
+                    }
+                    if is_dead_square(pt_before, src, mover) {
+                        continue;
+                    }
+
+                    let mut prev = self.retro_base();
+                    prev.board.set(dst, BoardCell::Empty);
+                    prev.board.set(src, BoardCell::Piece(mover, pt_before));
+                    if !prev
+                        .board()
+                        .attacks_from(src, mover, pt_before)
+                        .contains(&dst)
+                    {
+                        continue;
+                    }
+
+                    // 駒を取らなかった場合
+                    if check(&prev) {
+                        return true;
+                    }
+
+                    // 持駒にある駒を取った場合
+                    for (pt_cap, n) in self.hand(mover).enumerate() {
+                        if n == 0 {
+                            continue;
+                        }
+                        for &pt_on_board in [Some(pt_cap), pt_cap.to_promoted()].iter().flatten() {
+                            if is_dead_square(pt_on_board, dst, mover.flip()) {
+                                continue;
+                            }
+                            if pt_on_board == PieceType::Pawn
+                                && prev.has_pawn_on_file(mover.flip(), dst.x())
+                            {
+                                continue;
+                            }
+                            let mut prev = prev.clone();
+                            prev.board
+                                .set(dst, BoardCell::Piece(mover.flip(), pt_on_board));
+                            prev.hands[mover as usize]
+                                .sub(pt_cap, 1)
+                                .expect("internal error");
+                            if check(&prev) {
+                                return true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // 平手の開始局面そのもの (直前の手がない) の場合。
+        let (startpos, _) = decode("startpos").expect("internal error");
+        *self == startpos || n_checked > budget
+    }
+
+    /// 手番を戻し手数を 1 減らした局面を返す。
+    fn retro_base(&self) -> Position {
+        let mut prev = self.clone();
+        prev.side = self.side.flip();
+        prev.ply -= 1;
+        prev
+    }
+}
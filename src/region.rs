@@ -0,0 +1,80 @@
+//! 盤上の矩形領域。
+
+use std::ops::Range;
+
+use crate::*;
+
+/// 筋の範囲と段の範囲で表される盤上の矩形領域。
+///
+/// 範囲は 0-based で、`Square::new(x, y)` の `x`, `y` に対応する。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Region {
+    xs: Range<u8>,
+    ys: Range<u8>,
+}
+
+impl Region {
+    /// 筋の範囲 `xs`、段の範囲 `ys` の領域を作る。
+    ///
+    /// 範囲が盤外にはみ出す場合、panic する。
+    pub fn new(xs: Range<u8>, ys: Range<u8>) -> Self {
+        assert!(xs.end <= 9 && ys.end <= 9, "region out of board");
+        Self { xs, ys }
+    }
+
+    /// 盤面全体。
+    pub fn all() -> Self {
+        Self::new(0..9, 0..9)
+    }
+
+    /// 筋の範囲 `xs` の全ての段。
+    pub fn files(xs: Range<u8>) -> Self {
+        Self::new(xs, 0..9)
+    }
+
+    /// 段の範囲 `ys` の全ての筋。
+    pub fn ranks(ys: Range<u8>) -> Self {
+        Self::new(0..9, ys)
+    }
+
+    /// `side` 側の自陣 (手前の3段)。
+    pub fn camp(side: Side) -> Self {
+        match side {
+            Side::Sente => Self::ranks(6..9),
+            Side::Gote => Self::ranks(0..3),
+        }
+    }
+
+    /// `side` 側にとっての敵陣 (成れる領域)。
+    pub fn promotion_zone(side: Side) -> Self {
+        Self::camp(side.flip())
+    }
+
+    /// 中段 (四段目から六段目)。
+    pub fn center() -> Self {
+        Self::ranks(3..6)
+    }
+
+    pub fn contains(&self, sq: Square) -> bool {
+        self.xs.contains(&sq.x()) && self.ys.contains(&sq.y())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.xs.is_empty() || self.ys.is_empty()
+    }
+
+    /// 2つの領域の共通部分を返す。
+    pub fn intersection(&self, other: &Self) -> Self {
+        let xs = self.xs.start.max(other.xs.start)..self.xs.end.min(other.xs.end);
+        let ys = self.ys.start.max(other.ys.start)..self.ys.end.min(other.ys.end);
+        Self { xs, ys }
+    }
+
+    /// 領域内のマスを段ごとに返す。
+    pub fn squares(&self) -> impl Iterator<Item = Square> {
+        let xs = self.xs.clone();
+        self.ys
+            .clone()
+            .flat_map(move |y| xs.clone().map(move |x| Square::new(x, y)))
+    }
+}
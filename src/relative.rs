@@ -0,0 +1,266 @@
+//! 移動元のマスを持たない、相対的な指し手表現 (KI2 形式の "右", "上" など)。
+
+use crate::*;
+
+/// 駒の動作 (上, 引, 寄)。指す側から見た向きで表す。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Motion {
+    /// 上 (前に進む)
+    Up,
+    /// 引 (後ろに下がる)
+    Down,
+    /// 寄 (横に動く)
+    Sideways,
+}
+
+impl Motion {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Up => "上",
+            Self::Down => "引",
+            Self::Sideways => "寄",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Up, Self::Down, Self::Sideways]
+            .iter()
+            .copied()
+            .find(|m| m.name() == name)
+    }
+}
+
+/// 駒の相対位置 (左, 右, 直)。指す側から見た向きで表す。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RelativePos {
+    /// 左 (移動先へ動ける同種の駒のうち最も左の駒)
+    Left,
+    /// 右 (移動先へ動ける同種の駒のうち最も右の駒)
+    Right,
+    /// 直 (真っ直ぐ前に進む駒)。竜と馬には使わない。
+    Straight,
+}
+
+impl RelativePos {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Left => "左",
+            Self::Right => "右",
+            Self::Straight => "直",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Left, Self::Right, Self::Straight]
+            .iter()
+            .copied()
+            .find(|r| r.name() == name)
+    }
+}
+
+/// 移動先、駒種、および同種の駒を区別する修飾で表した指し手。
+///
+/// 修飾は動作 (上, 引, 寄) で区別できればそれのみ、できなければ相対位置 (左, 右, 直) を使い、
+/// それでも区別できなければ両方を使う。
+/// 移動元のマスを持たないので、区別が付く限り、移動元の駒の配置が異なる局面にも適用できる。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RelativeMove {
+    /// 動かす (打つ) 駒の、指す前の駒種。
+    pub pt: PieceType,
+    pub dst: Square,
+    /// 動作。同種の駒を区別するのに必要な場合のみ Some。
+    pub motion: Option<Motion>,
+    /// 相対位置。同種の駒を区別するのに必要な場合のみ Some。
+    pub relative: Option<RelativePos>,
+    /// 成るなら Some(true)、成れるが成らないなら Some(false)、成れないなら None。
+    pub promotion: Option<bool>,
+    pub is_drop: bool,
+}
+
+impl RelativeMove {
+    /// 局面 `pos` での合法手 `mv` を相対表現に変換する。
+    ///
+    /// `mv` が合法手でない場合、および修飾で区別できない場合はエラーを返す。
+    pub fn from_move(pos: &Position, mv: Move) -> Result<Self> {
+        let side = pos.side();
+        if !(pos.is_pseudo_legal(mv) && pos.is_legal(mv)) {
+            return Err(Error::move_error(format!(
+                "relative move: illegal move: {}",
+                mv
+            )));
+        }
+
+        let (src, dst, is_promotion) = match mv {
+            Move::Nondrop(nondrop) => (nondrop.src(), nondrop.dst(), nondrop.is_promotion()),
+            Move::Drop(drop) => {
+                return Ok(Self {
+                    pt: drop.pt(),
+                    dst: drop.dst(),
+                    motion: None,
+                    relative: None,
+                    promotion: None,
+                    is_drop: true,
+                })
+            }
+        };
+        let pt = match pos.board().cell(src) {
+            BoardCell::Piece(_, pt) => pt,
+            BoardCell::Empty => unreachable!(),
+        };
+
+        let promotion = if movegen::can_promote(pt, src, dst, side) {
+            Some(is_promotion)
+        } else {
+            None
+        };
+
+        let mut res = Self {
+            pt,
+            dst,
+            motion: None,
+            relative: None,
+            promotion,
+            is_drop: false,
+        };
+
+        let srcs = candidate_srcs(pos, pt, dst);
+        if srcs.len() <= 1 {
+            return Ok(res);
+        }
+
+        let motion = motion_of(side, src, dst);
+        let same_motion: Vec<_> = srcs
+            .iter()
+            .copied()
+            .filter(|&s| motion_of(side, s, dst) == motion)
+            .collect();
+
+        if same_motion.len() == 1 {
+            res.motion = Some(motion);
+        } else if !is_big(pt) && is_straight(side, src, dst) {
+            res.relative = Some(RelativePos::Straight);
+        } else if let Some(rel) = relative_of(side, src, &srcs) {
+            res.relative = Some(rel);
+        } else if let Some(rel) = relative_of(side, src, &same_motion) {
+            res.motion = Some(motion);
+            res.relative = Some(rel);
+        } else {
+            return Err(Error::move_error(format!(
+                "relative move: ambiguous move: {}",
+                mv
+            )));
+        }
+
+        Ok(res)
+    }
+
+    /// 局面 `pos` での指し手に変換する。
+    ///
+    /// 該当する合法手がない場合、および1つに定まらない場合はエラーを返す。
+    /// `is_drop` が false でも、移動できる駒がなく打てるなら駒打ちとみなす。
+    pub fn to_move(&self, pos: &Position) -> Result<Move> {
+        let side = pos.side();
+        let err = |msg: &str| Error::move_error(format!("relative move: {}: {:?}", msg, self));
+
+        let drop = Move::drop(self.pt, self.dst);
+        let can_drop = pos.is_pseudo_legal(drop) && pos.is_legal(drop);
+        if self.is_drop {
+            return if can_drop {
+                Ok(drop)
+            } else {
+                Err(err("illegal drop"))
+            };
+        }
+
+        let mut srcs = candidate_srcs(pos, self.pt, self.dst);
+        if srcs.is_empty() && can_drop {
+            return Ok(drop);
+        }
+
+        if self.relative == Some(RelativePos::Straight) {
+            srcs.retain(|&src| is_straight(side, src, self.dst));
+        }
+        if let Some(motion) = self.motion {
+            srcs.retain(|&src| motion_of(side, src, self.dst) == motion);
+        }
+        if let Some(rel @ (RelativePos::Left | RelativePos::Right)) = self.relative {
+            let all = srcs.clone();
+            srcs.retain(|&src| relative_of(side, src, &all) == Some(rel));
+        }
+
+        let src = match srcs[..] {
+            [src] => src,
+            [] => return Err(err("no such move")),
+            _ => return Err(err("ambiguous move")),
+        };
+
+        let is_promotion = self.promotion == Some(true);
+        let mv = Move::nondrop(src, self.dst, is_promotion);
+        if pos.is_pseudo_legal(mv) && pos.is_legal(mv) {
+            Ok(mv)
+        } else {
+            Err(err("illegal promotion"))
+        }
+    }
+}
+
+/// `dst` へ合法に移動できる手番側の駒 `pt` のマスを返す。
+fn candidate_srcs(pos: &Position, pt: PieceType, dst: Square) -> Vec<Square> {
+    let mut srcs: Vec<_> = pos
+        .moves_to(dst)
+        .into_iter()
+        .filter_map(|mv| match mv {
+            Move::Nondrop(nondrop) => Some(nondrop.src()),
+            Move::Drop(_) => None,
+        })
+        .filter(|&src| matches!(pos.board().cell(src), BoardCell::Piece(_, p) if p == pt))
+        .collect();
+    srcs.dedup();
+    srcs
+}
+
+/// 竜と馬 ("直" を使わない駒) かどうか。
+fn is_big(pt: PieceType) -> bool {
+    matches!(pt, PieceType::Dragon | PieceType::Horse)
+}
+
+/// `side` 側から見た (x, y)。x が大きいほど左、y が小さいほど前。
+fn oriented(side: Side, sq: Square) -> (i8, i8) {
+    let (x, y) = (sq.x() as i8, sq.y() as i8);
+    match side {
+        Side::Sente => (x, y),
+        Side::Gote => (8 - x, 8 - y),
+    }
+}
+
+fn motion_of(side: Side, src: Square, dst: Square) -> Motion {
+    let (_, ys) = oriented(side, src);
+    let (_, yd) = oriented(side, dst);
+    match yd.cmp(&ys) {
+        std::cmp::Ordering::Less => Motion::Up,
+        std::cmp::Ordering::Greater => Motion::Down,
+        std::cmp::Ordering::Equal => Motion::Sideways,
+    }
+}
+
+fn is_straight(side: Side, src: Square, dst: Square) -> bool {
+    let (xs, ys) = oriented(side, src);
+    let (xd, yd) = oriented(side, dst);
+    xs == xd && yd == ys - 1
+}
+
+/// `srcs` の中で `src` が唯一最も左 (右) にあれば `Left` (`Right`) を返す。
+fn relative_of(side: Side, src: Square, srcs: &[Square]) -> Option<RelativePos> {
+    let x = oriented(side, src).0;
+    let xs: Vec<_> = srcs.iter().map(|&s| oriented(side, s).0).collect();
+    let count = |v: i8| xs.iter().filter(|&&x| x == v).count();
+    let max = *xs.iter().max()?;
+    let min = *xs.iter().min()?;
+    if x == max && count(max) == 1 {
+        Some(RelativePos::Left)
+    } else if x == min && count(min) == 1 {
+        Some(RelativePos::Right)
+    } else {
+        None
+    }
+}
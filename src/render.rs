@@ -0,0 +1,161 @@
+//! 局面の SVG 画像の生成。`svg` feature で有効になる。
+
+use std::fmt::Write as _;
+
+use crate::*;
+
+/// SVG 画像の生成の設定。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SvgOptions {
+    /// 1マスの大きさ (px)。
+    pub cell_size: u32,
+    /// 強調表示する直前の指し手。移動元と移動先のマスに色を付ける。
+    pub last_move: Option<Move>,
+    /// 筋と段の座標を描くかどうか。
+    pub coordinates: bool,
+    /// 座標の文字種。
+    pub coord_style: CoordStyle,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 40,
+            last_move: None,
+            coordinates: true,
+            coord_style: CoordStyle::FullWidth,
+        }
+    }
+}
+
+const BOARD_COLOR: &str = "#f3d9a4";
+const HIGHLIGHT_COLOR: &str = "#f0a860";
+const PROMOTED_COLOR: &str = "#c00000";
+
+/// 局面を SVG 画像にする。
+///
+/// 盤面の上に後手の持駒、下に先手の持駒を描く。後手の駒は上下逆さに、成駒は赤で描く。
+pub fn svg(pos: &Position, opts: &SvgOptions) -> String {
+    let c = opts.cell_size;
+    let pad = c / 2;
+    // 盤面の左上の座標。上には後手の持駒の行と筋の座標の行がある。
+    let (ox, oy) = (pad, c + pad);
+    let width = ox + 9 * c + c;
+    let height = oy + 9 * c + pad + c;
+
+    let mut res = String::new();
+    let mut w = |s: String| res.push_str(&s);
+
+    w(format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="serif">"#,
+        width, height
+    ));
+    w("\n".to_owned());
+    w(format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+        width, height
+    ));
+
+    // 盤面と強調表示
+    w(format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+        ox,
+        oy,
+        9 * c,
+        9 * c,
+        BOARD_COLOR
+    ));
+    let cell_origin = |sq: Square| (ox + (8 - u32::from(sq.x())) * c, oy + u32::from(sq.y()) * c);
+    if let Some(mv) = opts.last_move {
+        let sqs = match mv {
+            Move::Nondrop(nd) => vec![nd.src(), nd.dst()],
+            Move::Drop(d) => vec![d.dst()],
+        };
+        for sq in sqs {
+            let (x, y) = cell_origin(sq);
+            w(format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                x, y, c, c, HIGHLIGHT_COLOR
+            ));
+        }
+    }
+
+    // 罫線
+    let mut path = String::new();
+    for i in 0..=9 {
+        let _ = write!(path, "M{} {}h{}", ox, oy + i * c, 9 * c);
+        let _ = write!(path, "M{} {}v{}", ox + i * c, oy, 9 * c);
+    }
+    w(format!(
+        "<path d=\"{}\" stroke=\"black\" stroke-width=\"1\" fill=\"none\"/>\n",
+        path
+    ));
+
+    // 座標
+    if opts.coordinates {
+        let font = c * 2 / 5;
+        for i in 0..9_u8 {
+            w(text(
+                ox + (8 - u32::from(i)) * c + c / 2,
+                oy - pad / 2,
+                font,
+                &opts.coord_style.file(i).to_string(),
+                "",
+            ));
+            w(text(
+                ox + 9 * c + pad,
+                oy + u32::from(i) * c + c / 2,
+                font,
+                &opts.coord_style.rank(i).to_string(),
+                "",
+            ));
+        }
+    }
+
+    // 駒
+    let font = c * 4 / 5;
+    for (sq, cell) in pos.board().iter() {
+        if let BoardCell::Piece(side, pt) = cell {
+            let (x, y) = cell_origin(sq);
+            let (cx, cy) = (x + c / 2, y + c / 2);
+            let mut attrs = String::new();
+            if pt != pt.to_unpromoted() {
+                let _ = write!(attrs, " fill=\"{}\"", PROMOTED_COLOR);
+            }
+            if side == Side::Gote {
+                let _ = write!(attrs, " transform=\"rotate(180 {} {})\"", cx, cy);
+            }
+            w(text(cx, cy, font, pt.name(Lang::JapaneseShort), &attrs));
+        }
+    }
+
+    // 持駒
+    let font = c / 2;
+    let hand = |side: Side| bod::encode_hand(pos, side).trim_end().to_owned();
+    w(text_start(ox, c / 2, font, &hand(Side::Gote)));
+    w(text_start(
+        ox,
+        oy + 9 * c + pad / 2 + c / 2,
+        font,
+        &hand(Side::Sente),
+    ));
+
+    res.push_str("</svg>\n");
+    res
+}
+
+/// (x, y) を中心とするテキスト要素。
+fn text(x: u32, y: u32, font: u32, s: &str, attrs: &str) -> String {
+    format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\"{}>{}</text>\n",
+        x, y, font, attrs, s
+    )
+}
+
+/// (x, y) を左端中央とするテキスト要素。
+fn text_start(x: u32, y: u32, font: u32, s: &str) -> String {
+    format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"{}\" dominant-baseline=\"central\">{}</text>\n",
+        x, y, font, s
+    )
+}
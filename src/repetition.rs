@@ -0,0 +1,79 @@
+//! 千日手の検出。
+
+use std::collections::HashMap;
+
+use crate::*;
+
+/// 千日手の種類。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RepetitionKind {
+    /// 通常の千日手 (引き分け)。
+    Draw,
+    /// 連続王手の千日手。王手をかけ続けた側 (負けになる側) を持つ。
+    PerpetualCheck(Side),
+}
+
+/// 検出された千日手。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Repetition {
+    /// 同一局面が4回目に現れた時点の指し手数 (この数の指し手を適用した局面で成立する)。
+    pub ply: usize,
+    /// 同一局面が最初に現れた時点の指し手数。
+    pub first_ply: usize,
+    pub kind: RepetitionKind,
+}
+
+/// 棋譜 (開始局面, 指し手リスト) を再生し、最初に成立した千日手を返す。
+///
+/// 同一局面 (盤面、持駒、手番が同じ局面) が4回現れたとき千日手とする。
+/// 同一局面の1回目から4回目までの間、一方の指し手が全て王手であれば連続王手の千日手とする。
+/// 途中で適用できない指し手があった場合、エラーを返す。
+pub fn detect_repetition(pos: &Position, mvs: &[Move]) -> Result<Option<Repetition>> {
+    let mut cur = pos.clone();
+    // is_check[i]: i 手目を適用した局面で王手がかかっているか (i 手目が王手か)。
+    let mut is_check = vec![false];
+    let mut occurrences: HashMap<_, Vec<usize>> = HashMap::new();
+    occurrences.insert(cur.key_without_ply(), vec![0]);
+
+    for (i, &mv) in mvs.iter().enumerate() {
+        cur.apply_move_in_place(mv)?;
+        let ply = i + 1;
+        is_check.push(cur.is_check());
+
+        let plies = occurrences.entry(cur.key_without_ply()).or_default();
+        plies.push(ply);
+        if plies.len() < 4 {
+            continue;
+        }
+
+        let first_ply = plies[0];
+        // first_ply + 1 手目から ply 手目までの指し手。k 手目の手番は開始局面の手番から交互。
+        let all_checks = |side: Side| {
+            (first_ply + 1..=ply)
+                .filter(|&k| mover(pos.side(), k) == side)
+                .all(|k| is_check[k])
+        };
+        let kind = [Side::Sente, Side::Gote]
+            .iter()
+            .copied()
+            .find(|&side| all_checks(side))
+            .map_or(RepetitionKind::Draw, RepetitionKind::PerpetualCheck);
+
+        return Ok(Some(Repetition {
+            ply,
+            first_ply,
+            kind,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// 開始局面の手番が `side0` のとき、`k` 手目 (1 始まり) を指す側を返す。
+fn mover(side0: Side, k: usize) -> Side {
+    if k % 2 == 1 {
+        side0
+    } else {
+        side0.flip()
+    }
+}
@@ -0,0 +1,103 @@
+//! serde 対応。
+//!
+//! `Position`, `Move`, `Square`, `Board` はそれぞれ sfen (指し手リストなし)、USI 形式の指し手、
+//! USI 形式のマス (`7g` など)、sfen の盤面部分の文字列としてシリアライズする。
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::codec::Codec;
+use crate::*;
+
+/// 文字列を経由して `FromStr` でデシリアライズする。
+fn deserialize_str<'de, D, T>(
+    deserializer: D,
+    expecting: &'static str,
+) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    struct StrVisitor<T> {
+        expecting: &'static str,
+        _marker: PhantomData<T>,
+    }
+
+    impl<'de, T> Visitor<'de> for StrVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.expecting)
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> std::result::Result<T, E> {
+            s.parse().map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_str(StrVisitor {
+        expecting,
+        _marker: PhantomData,
+    })
+}
+
+macro_rules! impl_serde_via_str {
+    ($ty:ty, $expecting:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                deserialize_str(deserializer, $expecting)
+            }
+        }
+    };
+}
+
+impl_serde_via_str!(Position, "an sfen string");
+impl_serde_via_str!(Move, "a USI move string");
+impl_serde_via_str!(Square, "a USI square string");
+
+/// `Board` の文字列表現 (sfen の盤面部分)。
+struct BoardStr(Board);
+
+impl FromStr for BoardStr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        decode::decode_board(&Codec::STANDARD, Variant::Standard, s).map(Self)
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode::encode_board(
+            &Codec::STANDARD,
+            Variant::Standard,
+            self,
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_str(deserializer, "an sfen board string").map(|BoardStr(board)| board)
+    }
+}
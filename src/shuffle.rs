@@ -0,0 +1,89 @@
+//! シャッフル将棋 (Fischer random 風) の開始局面生成。
+//!
+//! 平手の配置を基本とし、以下をシャッフルする:
+//!
+//! * 1段目 (先手は9段目) の玉以外の駒: 1・9筋, 2・8筋, 3・7筋, 4・6筋 の4組に
+//!   香・桂・銀・金を1種類ずつ左右対称に割り当てる (4! = 24 通り)。
+//! * 飛車と角の位置の入れ替え (2 通り)。
+//!
+//! 玉は5筋に固定し、後手の配置は先手の配置を 180 度回転したものとする。
+//! どの配置でも行き所のない駒は生じないので、全ての配置が合法である。
+
+use crate::mix::mix;
+use crate::*;
+
+/// 開始局面の総数。
+pub const COUNT: usize = 48;
+
+/// `idx` 番目 (0..COUNT) の開始局面を返す。0 番目は平手の開始局面。
+///
+/// `idx` が範囲外の場合、None を返す。
+pub fn position(idx: usize) -> Option<Position> {
+    if idx >= COUNT {
+        return None;
+    }
+
+    let pair_pts = nth_permutation(idx / 2);
+    let swap_rook_bishop = idx % 2 == 1;
+
+    // 先手から見た配置 (x: 0 が1筋)。
+    let back_rank = |x: u8| -> PieceType {
+        match x {
+            4 => PieceType::King,
+            _ => pair_pts[usize::from(x.min(8 - x))],
+        }
+    };
+    let rook_x = if swap_rook_bishop { 7 } else { 1 };
+    let bishop_x = 8 - rook_x;
+
+    let sente_cell = |x: u8, y: u8| -> Option<PieceType> {
+        match y {
+            8 => Some(back_rank(x)),
+            7 if x == rook_x => Some(PieceType::Rook),
+            7 if x == bishop_x => Some(PieceType::Bishop),
+            6 => Some(PieceType::Pawn),
+            _ => None,
+        }
+    };
+
+    let board = Board::new(|x, y| {
+        if let Some(pt) = sente_cell(x, y) {
+            BoardCell::Piece(Side::Sente, pt)
+        } else if let Some(pt) = sente_cell(8 - x, 8 - y) {
+            BoardCell::Piece(Side::Gote, pt)
+        } else {
+            BoardCell::Empty
+        }
+    });
+
+    Some(Position::new(
+        Side::Sente,
+        board,
+        Hand::new(|_| 0),
+        Hand::new(|_| 0),
+        1,
+    ))
+}
+
+/// シード値から決定的に選んだ開始局面を返す。
+pub fn random(seed: u64) -> Position {
+    let idx = (mix(seed) % COUNT as u64) as usize;
+    position(idx).expect("internal error")
+}
+
+/// 1・9筋, 2・8筋, 3・7筋, 4・6筋 の組に割り当てる駒種の、`idx` 番目 (0..24) の順列を返す。
+fn nth_permutation(mut idx: usize) -> [PieceType; 4] {
+    let mut rest = vec![
+        PieceType::Lance,
+        PieceType::Knight,
+        PieceType::Silver,
+        PieceType::Gold,
+    ];
+    let mut res = [PieceType::Lance; 4];
+    for (i, pt) in res.iter_mut().enumerate() {
+        let fact: usize = (1..4 - i).product();
+        *pt = rest.remove(idx / fact);
+        idx %= fact;
+    }
+    res
+}
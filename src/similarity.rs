@@ -0,0 +1,63 @@
+//! 局面間の類似度。
+
+use crate::*;
+
+impl Position {
+    /// 他の局面との類似度を 0.0..=1.0 で返す。
+    ///
+    /// 盤上の駒 (マスごと) と持駒 (陣営・駒種ごとの枚数) を要素とする多重集合の
+    /// Jaccard 係数を計算する。手番と手数は考慮しない。
+    pub fn similarity(&self, other: &Position) -> f32 {
+        let mut n_common = 0;
+        let mut n_union = 0;
+
+        for y in 0..9 {
+            for x in 0..9 {
+                let lhs = self.board().at(x, y);
+                let rhs = other.board().at(x, y);
+                match (lhs, rhs) {
+                    (BoardCell::Empty, BoardCell::Empty) => {}
+                    _ if lhs == rhs => {
+                        n_common += 1;
+                        n_union += 1;
+                    }
+                    _ => n_union += 1,
+                }
+            }
+        }
+
+        for &side in [Side::Sente, Side::Gote].iter() {
+            let lhs = self.hand(side).enumerate();
+            let rhs = other.hand(side).enumerate();
+            for ((_, n_lhs), (_, n_rhs)) in lhs.zip(rhs) {
+                n_common += u32::from(n_lhs.min(n_rhs));
+                n_union += u32::from(n_lhs.max(n_rhs));
+            }
+        }
+
+        if n_union == 0 {
+            1.0
+        } else {
+            n_common as f32 / n_union as f32
+        }
+    }
+}
+
+/// `corpus` の中から `query` との類似度が高い局面を最大 `k` 個選び、
+/// (corpus 内のインデックス, 類似度) を類似度の降順で返す。
+pub fn knn<'a>(
+    query: &Position,
+    corpus: impl IntoIterator<Item = &'a Position>,
+    k: usize,
+) -> Vec<(usize, f32)> {
+    let mut scores: Vec<_> = corpus
+        .into_iter()
+        .map(|pos| query.similarity(pos))
+        .enumerate()
+        .collect();
+
+    scores.sort_by(|lhs, rhs| rhs.1.partial_cmp(&lhs.1).expect("internal error"));
+    scores.truncate(k);
+
+    scores
+}
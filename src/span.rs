@@ -0,0 +1,157 @@
+//! エディタ連携向けのトークン位置情報。
+
+/// 入力文字列中のバイト範囲 `start..end`。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TokenKind {
+    /// `startpos`
+    Startpos,
+    /// `sfen`
+    SfenKeyword,
+    /// 盤面の1行 (`/` は含まない)。
+    BoardRow,
+    /// 手番 (`b`, `w`)。
+    Side,
+    /// 持駒の1要素 (`2P`, `b` など)、または持駒なしを表す `-`。
+    HandGroup,
+    /// 手数。
+    Ply,
+    /// `moves`
+    MovesKeyword,
+    /// 指し手1つ。
+    Move,
+    /// 上記のいずれとも解釈できない位置にあるトークン。
+    Unknown,
+}
+
+/// sfen 文字列をトークン単位に分割し、(位置, 種類) のリストを返す。
+///
+/// 字句レベルの分割のみを行い、各トークンの内容の妥当性はチェックしない
+/// (例えば不正な盤面行も `BoardRow` となる)。構造上ありえない位置にあるトークンは
+/// `Unknown` となる。
+pub fn parse_spans(sfen: &str) -> Vec<(Span, TokenKind)> {
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    enum State {
+        Magic,
+        Board,
+        Side,
+        Hands,
+        Ply,
+        MovesKeyword,
+        Moves,
+        Garbage,
+    }
+
+    let mut res = Vec::new();
+    let mut state = State::Magic;
+
+    for (start, token) in split_whitespace_indices(sfen) {
+        let span = Span::new(start, start + token.len());
+        state = match state {
+            State::Magic => match token {
+                "startpos" => {
+                    res.push((span, TokenKind::Startpos));
+                    State::MovesKeyword
+                }
+                "sfen" => {
+                    res.push((span, TokenKind::SfenKeyword));
+                    State::Board
+                }
+                _ => {
+                    res.push((span, TokenKind::Unknown));
+                    State::Garbage
+                }
+            },
+            State::Board => {
+                let mut row_start = start;
+                for row in token.split('/') {
+                    res.push((
+                        Span::new(row_start, row_start + row.len()),
+                        TokenKind::BoardRow,
+                    ));
+                    row_start += row.len() + 1;
+                }
+                State::Side
+            }
+            State::Side => {
+                res.push((span, TokenKind::Side));
+                State::Hands
+            }
+            State::Hands => {
+                res.extend(hand_group_spans(start, token).map(|sp| (sp, TokenKind::HandGroup)));
+                State::Ply
+            }
+            State::Ply => {
+                res.push((span, TokenKind::Ply));
+                State::MovesKeyword
+            }
+            State::MovesKeyword => {
+                if token == "moves" {
+                    res.push((span, TokenKind::MovesKeyword));
+                    State::Moves
+                } else {
+                    res.push((span, TokenKind::Unknown));
+                    State::Garbage
+                }
+            }
+            State::Moves => {
+                res.push((span, TokenKind::Move));
+                State::Moves
+            }
+            State::Garbage => {
+                res.push((span, TokenKind::Unknown));
+                State::Garbage
+            }
+        };
+    }
+
+    res
+}
+
+/// ASCII 空白で区切られたトークンを (開始位置, トークン) の形で列挙する。
+pub(crate) fn split_whitespace_indices(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split(|c: char| c.is_ascii_whitespace())
+        .scan(0, |pos, token| {
+            let start = *pos;
+            *pos += token.len() + 1;
+            Some((start, token))
+        })
+        .filter(|(_, token)| !token.is_empty())
+}
+
+/// 持駒トークンを要素 (数字列 + 1文字) ごとに分割する。
+fn hand_group_spans(start: usize, token: &str) -> impl Iterator<Item = Span> + '_ {
+    let mut group_start = 0;
+    token.char_indices().filter_map(move |(i, c)| {
+        let end = i + c.len_utf8();
+        if c.is_ascii_digit() && end != token.len() {
+            return None;
+        }
+        let span = Span::new(start + group_start, start + end);
+        group_start = end;
+        Some(span)
+    })
+}
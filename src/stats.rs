@@ -0,0 +1,160 @@
+//! エンジン対局結果の統計処理 (Elo 差、LOS、SPRT)。
+
+/// 勝ち・引き分け・負けの数。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Wdl {
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+}
+
+impl Wdl {
+    pub fn new(wins: u64, draws: u64, losses: u64) -> Self {
+        Self {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    pub fn games(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 得点率 (勝ち = 1, 引き分け = 0.5) を返す。対局数が 0 の場合は NaN。
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    /// 得点率から推定した Elo 差を返す。
+    pub fn elo(&self) -> f64 {
+        score_to_elo(self.score())
+    }
+
+    /// Elo 差の信頼区間 (下限, 上限) を返す。`confidence` は 0.95 など。
+    ///
+    /// 得点率の正規近似に基づく。
+    pub fn elo_interval(&self, confidence: f64) -> (f64, f64) {
+        let n = self.games() as f64;
+        let score = self.score();
+        let stdev = (self.score_variance() / n).sqrt();
+        let z = normal_quantile(0.5 + confidence / 2.0);
+
+        (
+            score_to_elo(score - z * stdev),
+            score_to_elo(score + z * stdev),
+        )
+    }
+
+    /// LOS (likelihood of superiority) を返す。引き分けは無視する。
+    ///
+    /// 勝ちも負けもない場合は 0.5。
+    pub fn los(&self) -> f64 {
+        if self.wins + self.losses == 0 {
+            return 0.5;
+        }
+        let (w, l) = (self.wins as f64, self.losses as f64);
+        0.5 * (1.0 + erf((w - l) / (2.0 * (w + l)).sqrt()))
+    }
+
+    /// 1局あたりの得点の分散を返す。
+    fn score_variance(&self) -> f64 {
+        let n = self.games() as f64;
+        let (w, d) = (self.wins as f64 / n, self.draws as f64 / n);
+        let score = self.score();
+        w + d / 4.0 - score * score
+    }
+}
+
+/// SPRT の判定結果。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SprtDecision {
+    /// H0 (Elo 差 = elo0) を採択。
+    AcceptH0,
+    /// H1 (Elo 差 = elo1) を採択。
+    AcceptH1,
+    /// 判定保留 (対局を続ける)。
+    Continue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    /// 対数尤度比。
+    pub llr: f64,
+}
+
+impl Sprt {
+    /// 第1種の過誤率 `alpha`、第2種の過誤率 `beta` で判定する。
+    pub fn decide(&self, alpha: f64, beta: f64) -> SprtDecision {
+        let (lower, upper) = sprt_bounds(alpha, beta);
+        if self.llr >= upper {
+            SprtDecision::AcceptH1
+        } else if self.llr <= lower {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+/// 仮説 H0: Elo 差 = `elo0`、H1: Elo 差 = `elo1` に対する SPRT の対数尤度比を計算する。
+///
+/// 得点の正規近似による GSPRT (fishtest と同じ方式) を用いる。
+/// 対局数が 0 の場合や、全局が同じ結果で得点の分散が 0 の場合は llr を 0 とする。
+pub fn sprt(wdl: &Wdl, elo0: f64, elo1: f64) -> Sprt {
+    if wdl.games() == 0 {
+        return Sprt { llr: 0.0 };
+    }
+    let var = wdl.score_variance();
+    if var <= 0.0 {
+        return Sprt { llr: 0.0 };
+    }
+
+    let n = wdl.games() as f64;
+    let score = wdl.score();
+    let (s0, s1) = (elo_to_score(elo0), elo_to_score(elo1));
+
+    Sprt {
+        llr: n * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * var),
+    }
+}
+
+/// SPRT の (下限, 上限) 閾値を返す。
+pub fn sprt_bounds(alpha: f64, beta: f64) -> (f64, f64) {
+    ((beta / (1.0 - alpha)).ln(), ((1.0 - beta) / alpha).ln())
+}
+
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf(-elo / 400.0))
+}
+
+pub fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// 誤差関数。Abramowitz and Stegun 7.1.26 による近似 (絶対誤差 1.5e-7 以下)。
+fn erf(x: f64) -> f64 {
+    let sign = x.signum();
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    sign * (1.0 - poly * (-x * x).exp())
+}
+
+/// 標準正規分布の分位点。二分法で `erf` を逆算する。
+fn normal_quantile(p: f64) -> f64 {
+    let cdf = |x: f64| 0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2));
+    let (mut lo, mut hi) = (-10.0, 10.0);
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if cdf(mid) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
@@ -0,0 +1,69 @@
+//! 対局やベンチマークの開始局面として使える定跡手順集。
+//!
+//! `suites` feature で有効になる。
+
+use crate::*;
+
+/// 名前付きの開始手順。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Opening {
+    pub name: &'static str,
+    /// 平手からの指し手 (USI 形式、空白区切り)。
+    pub moves: &'static str,
+}
+
+impl Opening {
+    /// (平手の開始局面, 指し手リスト) を返す。
+    pub fn decode(&self) -> (Position, Vec<Move>) {
+        decode(format!("startpos moves {}", self.moves)).expect("internal error")
+    }
+
+    /// 開始手順を全て指した後の局面を返す。
+    pub fn position(&self) -> Position {
+        let (mut pos, mvs) = self.decode();
+        for mv in mvs {
+            pos.apply_move_in_place(mv).expect("internal error");
+        }
+        pos
+    }
+}
+
+/// 主要な戦型の序盤数手。
+pub const STANDARD_OPENINGS: &[Opening] = &[
+    Opening {
+        name: "相掛かり",
+        moves: "2g2f 8c8d 2f2e 8d8e 6i7h 4a3b 2e2d 2c2d 2h2d P*2c",
+    },
+    Opening {
+        name: "角換わり",
+        moves: "7g7f 8c8d 2g2f 8d8e 8h7g 3c3d 7i8h 4a3b 7g2b+ 3a2b 8h7g",
+    },
+    Opening {
+        name: "横歩取り",
+        moves: "7g7f 3c3d 2g2f 8c8d 2f2e 8d8e 6i7h 4a3b 2e2d 2c2d 2h2d 8e8f 8g8f 8b8f 2d3d",
+    },
+    Opening {
+        name: "矢倉",
+        moves: "7g7f 8c8d 6i7h 3c3d 6g6f 7a6b 5g5f 5c5d 3i4h 3a4b 4i5h 4a3b",
+    },
+    Opening {
+        name: "四間飛車",
+        moves: "7g7f 3c3d 6g6f 8c8d 2h6h",
+    },
+    Opening {
+        name: "中飛車",
+        moves: "7g7f 3c3d 5g5f 8c8d 2h5h",
+    },
+    Opening {
+        name: "三間飛車",
+        moves: "7g7f 8c8d 2h7h",
+    },
+    Opening {
+        name: "石田流",
+        moves: "7g7f 3c3d 7f7e 8c8d 2h7h",
+    },
+    Opening {
+        name: "ゴキゲン中飛車",
+        moves: "7g7f 3c3d 2g2f 5c5d 2f2e 8b5b",
+    },
+];
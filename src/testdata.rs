@@ -0,0 +1,143 @@
+//! 回帰テスト用の sfen 文字列と、その期待されるデコード結果の集合。
+//!
+//! `testdata` feature で有効になる。このクレートをラップする下流のクレートが、
+//! 自身の回帰テストに含めることを想定している。期待値は互換性を保って維持される。
+
+/// 期待されるデコード結果。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Expected {
+    /// デコードに成功し、`encode()` で再エンコードするとこの文字列になる。
+    Sfen(&'static str),
+    /// デコードに失敗する。
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Case {
+    pub name: &'static str,
+    pub input: &'static str,
+    pub expected: Expected,
+}
+
+/// 回帰テスト用の入力と期待値の一覧を返す。
+pub fn testdata() -> &'static [Case] {
+    CASES
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "startpos",
+        input: "startpos",
+        expected: Expected::Sfen("sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1"),
+    },
+    Case {
+        name: "startpos with moves",
+        input: "startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e",
+        expected: Expected::Sfen("sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d 8h2b+ 3a2b B*4e"),
+    },
+    Case {
+        name: "irregular whitespace",
+        input: "  sfen 4k4/9/9/9/9/9/9/9/4K4 \t b  -   1 ",
+        expected: Expected::Sfen("sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1"),
+    },
+    Case {
+        name: "all pieces in hand, noncanonical order",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4 b 18P4L4N4S4G2B2R 1",
+        expected: Expected::Sfen("sfen 4k4/9/9/9/9/9/9/9/4K4 b 2R2B4G4S4N4L18P 1"),
+    },
+    Case {
+        name: "hands of both sides with two-digit counts",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4 w 8p10PRr 1",
+        expected: Expected::Sfen("sfen 4k4/9/9/9/9/9/9/9/4K4 w R10Pr8p 1"),
+    },
+    Case {
+        name: "all promoted pieces",
+        input: "sfen +l+n+s+b+r+p2k/9/9/9/9/9/9/9/+P+L+N+S+B+R2K b - 1",
+        expected: Expected::Sfen("sfen +l+n+s+b+r+p2k/9/9/9/9/9/9/9/+P+L+N+S+B+R2K b - 1"),
+    },
+    Case {
+        name: "promotion flag is not checked against the piece",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1 moves 5i5h+ 5a5b+",
+        expected: Expected::Sfen("sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1 moves 5i5h+ 5a5b+"),
+    },
+    Case {
+        name: "large ply",
+        input: "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 2147483647",
+        expected: Expected::Sfen("sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 2147483647"),
+    },
+    Case {
+        name: "long game",
+        input: "startpos moves 5g5f 7a6b 5i5h 8b9b 9g9f 1a1b 7i7h 9b8b 8g8f 8b9b 6g6f 9b8b 9i9g 6a5b 4i4h 8c8d 5h6g 8d8e 6i5h 5a4b 4h5g 9c9d 7g7f 7c7d 2h4h 7d7e 5g4f 4b3b 3i2h 5b5a 4h4i 8a9c 2h3i 2b1a 3i2h 8e8f 4f5e 7e7f 8h7g 6b7a 2h3i 3c3d 5h6h 1a2b 6g5h 9a9b 5e4d 9c8e 7g9i 3d3e 7h8g 9d9e 5h5i 9b9d 6h7g 7f7g 1g1f G*5g 4d5d 8b8a 2i1g 8a8b 5d6c 4a4b 6c5c 3b4a 1g2e 8b9b P*8h 9b7b 8i7g 5a5b 4i4h 5g4g 4h2h 3e3f P*7h 7b7g+ P*4f N*4d 9f9e P*6d 1i1g P*9h 1f1e 8f8g 2h5h S*2d 4f4e P*7b 1e1d 6d6e 2e1c 7g6g 5h6h 4g5g 5c5b 4a3b 6h1h 8g8h+ 1h2h 2d2e G*6a 1b1c 6a6b 3f3g 5f5e N*7e P*3e 3b3c 5i4i 9h9i+ P*8i 4d3f 1d1c 4b3b 6b6a B*3h 4i5i 3h2i 2h6h 8e9g+ 1g1e L*5a 2g2f 2e1d 6h5h 2i4g+ L*8d 8h9h 8d8b+ 7b7c 3i3h 9g8g 3h2g 3c2d 4e4d 7a6b 1e1d P*1g 7h7g P*5f 8b7b 6g6i 5i6i 1g1h+ 1c1b 6b6c R*3h 9i8i",
+        expected: Expected::Sfen("sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 5g5f 7a6b 5i5h 8b9b 9g9f 1a1b 7i7h 9b8b 8g8f 8b9b 6g6f 9b8b 9i9g 6a5b 4i4h 8c8d 5h6g 8d8e 6i5h 5a4b 4h5g 9c9d 7g7f 7c7d 2h4h 7d7e 5g4f 4b3b 3i2h 5b5a 4h4i 8a9c 2h3i 2b1a 3i2h 8e8f 4f5e 7e7f 8h7g 6b7a 2h3i 3c3d 5h6h 1a2b 6g5h 9a9b 5e4d 9c8e 7g9i 3d3e 7h8g 9d9e 5h5i 9b9d 6h7g 7f7g 1g1f G*5g 4d5d 8b8a 2i1g 8a8b 5d6c 4a4b 6c5c 3b4a 1g2e 8b9b P*8h 9b7b 8i7g 5a5b 4i4h 5g4g 4h2h 3e3f P*7h 7b7g+ P*4f N*4d 9f9e P*6d 1i1g P*9h 1f1e 8f8g 2h5h S*2d 4f4e P*7b 1e1d 6d6e 2e1c 7g6g 5h6h 4g5g 5c5b 4a3b 6h1h 8g8h+ 1h2h 2d2e G*6a 1b1c 6a6b 3f3g 5f5e N*7e P*3e 3b3c 5i4i 9h9i+ P*8i 4d3f 1d1c 4b3b 6b6a B*3h 4i5i 3h2i 2h6h 8e9g+ 1g1e L*5a 2g2f 2e1d 6h5h 2i4g+ L*8d 8h9h 8d8b+ 7b7c 3i3h 9g8g 3h2g 3c2d 4e4d 7a6b 1e1d P*1g 7h7g P*5f 8b7b 6g6i 5i6i 1g1h+ 1c1b 6b6c R*3h 9i8i"),
+    },
+    Case {
+        name: "empty input",
+        input: "",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "unknown magic",
+        input: "position startpos",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "board row overflow",
+        input: "sfen 4k5/9/9/9/9/9/9/9/4K4 b - 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "too many board rows",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4/9 b - 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "too few board rows",
+        input: "sfen 4k4/9/9/9/9/9/9/4K4 b - 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "short board row",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K3 b - 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "double promotion marker",
+        input: "sfen 4k4/9/9/9/9/9/9/9/++P3K4 b - 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "promoted gold",
+        input: "sfen 4k4/9/9/9/9/9/9/9/+G3K4 b - 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "king in hand",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4 b K 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "hand count overflow",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4 b 256P 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "hand count overflow across groups",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4 b 200P100P 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "invalid side",
+        input: "sfen 4k4/9/9/9/9/9/9/9/4K4 x - 1",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "truncated move",
+        input: "startpos moves 7g7",
+        expected: Expected::Error,
+    },
+    Case {
+        name: "missing moves keyword",
+        input: "startpos 7g7f",
+        expected: Expected::Error,
+    },
+];
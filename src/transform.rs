@@ -0,0 +1,65 @@
+//! 盤面の左右反転と 180 度回転。
+
+use crate::*;
+
+impl Square {
+    /// 左右反転したマス (1筋と9筋を入れ替える) を返す。
+    pub fn mirror_horizontal(self) -> Self {
+        Self::new(8 - self.x(), self.y())
+    }
+
+    /// 盤を 180 度回転させたときのマスを返す。
+    pub fn rotate180(self) -> Self {
+        Self::new(8 - self.x(), 8 - self.y())
+    }
+}
+
+impl Move {
+    /// `Position::mirror_horizontal()` した局面での対応する指し手を返す。
+    pub fn mirror_horizontal(self) -> Self {
+        self.map_squares(Square::mirror_horizontal)
+    }
+
+    /// `Position::rotate180()` した局面での対応する指し手を返す。
+    pub fn rotate180(self) -> Self {
+        self.map_squares(Square::rotate180)
+    }
+
+    fn map_squares(self, f: impl Fn(Square) -> Square) -> Self {
+        match self {
+            Move::Nondrop(nd) => Move::nondrop(f(nd.src()), f(nd.dst()), nd.is_promotion()),
+            Move::Drop(d) => Move::drop(d.pt(), f(d.dst())),
+        }
+    }
+}
+
+impl Position {
+    /// 盤面を左右反転した局面を返す。手番、持駒、手数は変わらない。
+    pub fn mirror_horizontal(&self) -> Self {
+        let board = Board::new(|x, y| self.board.at(8 - x, y));
+        Self::new(
+            self.side,
+            board,
+            self.hands[0].clone(),
+            self.hands[1].clone(),
+            self.ply,
+        )
+    }
+
+    /// 盤面を 180 度回転させ、先後を入れ替えた局面を返す。
+    ///
+    /// 駒の持ち主、持駒、手番が入れ替わる。手数は変わらない。
+    pub fn rotate180(&self) -> Self {
+        let board = Board::new(|x, y| match self.board.at(8 - x, 8 - y) {
+            BoardCell::Empty => BoardCell::Empty,
+            BoardCell::Piece(side, pt) => BoardCell::Piece(side.flip(), pt),
+        });
+        Self::new(
+            self.side.flip(),
+            board,
+            self.hands[1].clone(),
+            self.hands[0].clone(),
+            self.ply,
+        )
+    }
+}
@@ -0,0 +1,64 @@
+//! 詰将棋の「残り駒全部」の慣習のサポート。
+//!
+//! 詰将棋の局面では、一方の持駒を明示せず「盤上と他方の持駒にない駒を全て持つ」とすることが多い。
+
+use crate::*;
+
+/// 平手の駒の枚数 (玉を除く、`Hand::PTS` の順)。
+const MAX_HAND_COUNTS: [u8; 7] = [18, 4, 4, 4, 2, 2, 4];
+
+impl Position {
+    /// 盤上にも両者の持駒にもない駒 (玉を除く) を持駒の形で返す。
+    ///
+    /// 平手の枚数を超える駒がある駒種は 0 枚とする。
+    pub fn remaining_pieces(&self) -> Hand {
+        let mut res = Hand::empty();
+        for (i, &pt) in Hand::PTS.iter().enumerate() {
+            let on_board = self
+                .board
+                .iter()
+                .filter(
+                    |&(_, cell)| matches!(cell, BoardCell::Piece(_, p) if p.to_unpromoted() == pt),
+                )
+                .count();
+            let in_hands =
+                usize::from(self.hands[0].count(pt)) + usize::from(self.hands[1].count(pt));
+            let rest = usize::from(MAX_HAND_COUNTS[i]).saturating_sub(on_board + in_hands);
+            res.add(pt, rest as u8).expect("internal error");
+        }
+        res
+    }
+
+    /// `remaining_pieces()` の駒を全て `side` 側の持駒に加える。
+    pub fn fill_hand_with_remaining(&mut self, side: Side) {
+        for (pt, n) in self.remaining_pieces().enumerate() {
+            self.hands[side as usize]
+                .add(pt, n)
+                .expect("internal error");
+        }
+    }
+}
+
+/// `side` 側の持駒を省略した sfen をパースし、`side` 側に残り駒を全て持たせた局面を返す。
+///
+/// sfen に `side` 側の持駒が書かれていれば、それに残り駒を加える。
+pub fn decode(sfen: impl AsRef<str>, side: Side) -> Result<(Position, Vec<Move>)> {
+    let (mut pos, mvs) = crate::decode(sfen)?;
+    pos.fill_hand_with_remaining(side);
+    Ok((pos, mvs))
+}
+
+/// `side` 側の持駒を省略した sfen を返す。`decode()` の逆変換。
+///
+/// `side` 側の持駒がちょうど残り駒全部でない場合、省略できないのでエラーを返す。
+pub fn encode(pos: &Position, mvs: &[Move], side: Side) -> Result<String> {
+    let mut omitted = pos.clone();
+    omitted.hands[side as usize] = Hand::empty();
+    if omitted.remaining_pieces() != *pos.hand(side) {
+        return Err(Error::encode_error(format!(
+            "tsume: {} hand is not all remaining pieces",
+            side.name(Lang::English)
+        )));
+    }
+    Ok(crate::encode(&omitted, mvs))
+}
@@ -0,0 +1,95 @@
+//! Web 上の将棋盤で局面を開くための URL の生成とパース。
+
+use crate::*;
+
+const LISHOGI_ORIGIN: &str = "https://lishogi.org";
+
+/// lishogi の検討画面の URL を返す。
+///
+/// 例: `https://lishogi.org/analysis/lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL_b_-_1`
+pub fn lishogi_analysis_url(pos: &Position) -> String {
+    format!("{}/analysis/{}", LISHOGI_ORIGIN, lishogi_path_sfen(pos))
+}
+
+/// lishogi の盤面編集画面の URL を返す。
+pub fn lishogi_editor_url(pos: &Position) -> String {
+    format!("{}/editor/{}", LISHOGI_ORIGIN, lishogi_path_sfen(pos))
+}
+
+/// lishogi の検討画面または盤面編集画面の URL から局面を取り出す。
+///
+/// オリジン部分は省略可能 (`/analysis/...` の形も受け付ける)。
+/// クエリ文字列とフラグメントは無視する。
+pub fn parse_lishogi_url(url: impl AsRef<str>) -> Result<Position> {
+    let url = url.as_ref();
+    let invalid = || {
+        Error::decode_error(
+            ErrorCode::Url,
+            format!("url: not a lishogi position url: {}", url),
+        )
+    };
+
+    let path = url.strip_prefix(LISHOGI_ORIGIN).unwrap_or(url);
+    let path = path.split(['?', '#']).next().expect("internal error");
+    let s_sfen = path
+        .strip_prefix("/analysis/")
+        .or_else(|| path.strip_prefix("/editor/"))
+        .ok_or_else(invalid)?;
+
+    let s_sfen = percent_decode(s_sfen)
+        .ok_or_else(invalid)?
+        .replace('_', " ");
+    let (pos, _) = decode(format!("sfen {}", s_sfen))?;
+
+    Ok(pos)
+}
+
+/// 局面の sfen をクエリ文字列の値として使えるようパーセントエンコードして返す。
+///
+/// `sfen` キーワードは含まない。`?sfen=` などのキーと組み合わせて使う。
+pub fn query_value(pos: &Position) -> String {
+    percent_encode(sfen_body(pos).as_str())
+}
+
+/// `sfen` キーワードを除いた局面部分の sfen を返す。
+fn sfen_body(pos: &Position) -> String {
+    let sfen = encode(pos, &[]);
+    sfen.strip_prefix("sfen ")
+        .expect("internal error")
+        .to_owned()
+}
+
+fn lishogi_path_sfen(pos: &Position) -> String {
+    percent_encode(&sfen_body(pos).replace(' ', "_"))
+}
+
+/// RFC 3986 の非予約文字と `/` 以外をパーセントエンコードする。
+fn percent_encode(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                res.push(char::from(b))
+            }
+            _ => res.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    res
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bs = s.as_bytes();
+    let mut res = Vec::with_capacity(bs.len());
+    let mut i = 0;
+    while i < bs.len() {
+        if bs[i] == b'%' {
+            let hex = std::str::from_utf8(bs.get(i + 1..i + 3)?).ok()?;
+            res.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            res.push(bs[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(res).ok()
+}
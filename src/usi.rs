@@ -0,0 +1,176 @@
+//! USI プロトコルのエンジン出力 ("bestmove", "info") のパース。
+//!
+//! GUI から送る "position" コマンドのパースは `decode_usi_position()` を参照。
+
+use std::time::Duration;
+
+use crate::*;
+
+/// エンジンの出力1行。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Message {
+    BestMove(BestMove),
+    Info(Info),
+    /// "bestmove", "info" 以外の行 ("readyok" など)。前後の空白は除かれる。
+    Other(String),
+}
+
+/// "bestmove" コマンドの内容。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BestMove {
+    /// 指し手と、あれば予想手 ("ponder")。
+    Move(Move, Option<Move>),
+    /// "bestmove resign"
+    Resign,
+    /// "bestmove win" (入玉宣言勝ち)
+    Win,
+}
+
+/// 評価値。エンジンの手番から見た値。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Score {
+    /// センチポーン単位の評価値。
+    Cp(i32),
+    /// 詰みまでの手数。負なら詰まされる側。
+    Mate(i32),
+    /// 手数不明の詰み ("mate +" / "mate -")。true なら詰ます側。
+    MateUnknown(bool),
+}
+
+/// 評価値が上界・下界であることを表す。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Bound {
+    Lower,
+    Upper,
+}
+
+/// "info" コマンドの内容。含まれない項目は None (`pv` は空) となる。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub bound: Option<Bound>,
+    pub currmove: Option<Move>,
+    pub pv: Vec<Move>,
+    /// "string" 以降の文字列。
+    pub string: Option<String>,
+}
+
+/// エンジンの出力1行をパースする。
+pub fn decode_message(line: impl AsRef<str>) -> Result<Message> {
+    let line = line.as_ref().trim();
+    match line.split_ascii_whitespace().next() {
+        Some("bestmove") => decode_bestmove(line).map(Message::BestMove),
+        Some("info") => decode_info(line).map(Message::Info),
+        _ => Ok(Message::Other(line.to_owned())),
+    }
+}
+
+/// "bestmove 7g7f ponder 3c3d" のような行をパースする。
+pub fn decode_bestmove(line: impl AsRef<str>) -> Result<BestMove> {
+    let line = line.as_ref();
+    let err = || {
+        Error::decode_error(
+            ErrorCode::Usi,
+            format!("usi: invalid bestmove: {}", line.trim()),
+        )
+    };
+
+    let tokens: Vec<_> = line.split_ascii_whitespace().collect();
+    match tokens[..] {
+        ["bestmove", "resign"] => Ok(BestMove::Resign),
+        ["bestmove", "win"] => Ok(BestMove::Win),
+        ["bestmove", mv] => Ok(BestMove::Move(decode_move(mv)?, None)),
+        ["bestmove", mv, "ponder", ponder] => {
+            Ok(BestMove::Move(decode_move(mv)?, Some(decode_move(ponder)?)))
+        }
+        _ => Err(err()),
+    }
+}
+
+/// "info depth 20 score cp 85 pv 7g7f 3c3d" のような行をパースする。
+///
+/// "pv" と "string" は行末までを値とする。未知のキーワードは次の既知のキーワードまで読み飛ばす。
+pub fn decode_info(line: impl AsRef<str>) -> Result<Info> {
+    let line = line.as_ref().trim();
+    let err = || Error::decode_error(ErrorCode::Usi, format!("usi: invalid info: {}", line));
+
+    let rest = line
+        .strip_prefix("info")
+        .filter(|rest| rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_whitespace()))
+        .ok_or_else(err)?;
+
+    let mut info = Info::default();
+    let mut rest = rest.trim_start();
+    while let Some((key, tail)) = split_token(rest) {
+        rest = tail;
+        let mut value = || -> Result<&str> {
+            let (value, tail) = split_token(rest).ok_or_else(err)?;
+            rest = tail;
+            Ok(value)
+        };
+        match key {
+            "depth" => info.depth = Some(value()?.parse().map_err(|_| err())?),
+            "seldepth" => info.seldepth = Some(value()?.parse().map_err(|_| err())?),
+            "time" => {
+                let ms: u64 = value()?.parse().map_err(|_| err())?;
+                info.time = Some(Duration::from_millis(ms));
+            }
+            "nodes" => info.nodes = Some(value()?.parse().map_err(|_| err())?),
+            "nps" => info.nps = Some(value()?.parse().map_err(|_| err())?),
+            "hashfull" => info.hashfull = Some(value()?.parse().map_err(|_| err())?),
+            "multipv" => info.multipv = Some(value()?.parse().map_err(|_| err())?),
+            "currmove" => info.currmove = Some(decode_move(value()?)?),
+            "score" => {
+                let score = match value()? {
+                    "cp" => Score::Cp(value()?.parse().map_err(|_| err())?),
+                    "mate" => match value()? {
+                        "+" => Score::MateUnknown(true),
+                        "-" => Score::MateUnknown(false),
+                        s => Score::Mate(s.parse().map_err(|_| err())?),
+                    },
+                    _ => return Err(err()),
+                };
+                info.score = Some(score);
+            }
+            "lowerbound" => info.bound = Some(Bound::Lower),
+            "upperbound" => info.bound = Some(Bound::Upper),
+            "pv" => {
+                info.pv = rest
+                    .split_ascii_whitespace()
+                    .map(decode_move)
+                    .collect::<Result<_>>()?;
+                break;
+            }
+            "string" => {
+                info.string = Some(rest.to_owned());
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(info)
+}
+
+/// 先頭のトークンと、その後の空白を除いた残りを返す。
+fn split_token(s: &str) -> Option<(&str, &str)> {
+    if s.is_empty() {
+        return None;
+    }
+    let (token, rest) = s
+        .split_once(|c: char| c.is_ascii_whitespace())
+        .unwrap_or((s, ""));
+    Some((token, rest.trim_start()))
+}
+
+fn decode_move(s: &str) -> Result<Move> {
+    s.parse()
+        .map_err(|_| Error::decode_error(ErrorCode::Usi, format!("usi: invalid move: {}", s)))
+}
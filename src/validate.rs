@@ -0,0 +1,108 @@
+//! 局面の妥当性検査。
+
+use thiserror::Error;
+
+use crate::movegen::{all_squares, is_dead_square};
+use crate::*;
+
+/// 玉以外の駒種ごとの枚数の上限 (成駒は元の駒として数える)。
+const MAX_COUNTS: [(PieceType, usize); 7] = [
+    (PieceType::Pawn, 18),
+    (PieceType::Lance, 4),
+    (PieceType::Knight, 4),
+    (PieceType::Silver, 4),
+    (PieceType::Bishop, 2),
+    (PieceType::Rook, 2),
+    (PieceType::Gold, 4),
+];
+
+/// `Position::validate()` が検出する問題。
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ValidationError {
+    /// 駒種 `pt` (成駒を含む) の枚数が平手の駒の枚数を超えている。
+    #[error("too many pieces: {pt:?} x {count}")]
+    TooManyPieces { pt: PieceType, count: usize },
+
+    #[error("{side:?} king is missing")]
+    MissingKing { side: Side },
+
+    #[error("{side:?} has {count} kings")]
+    DuplicateKing { side: Side, count: usize },
+
+    /// `side` 側の歩が筋 `x` に2枚以上ある。
+    #[error("{side:?} has two pawns on file {}", x + 1)]
+    DoublePawn { side: Side, x: u8 },
+
+    /// 行き所のない駒がある。
+    #[error("{side:?} {pt:?} at ({}, {}) can never move", sq.x(), sq.y())]
+    DeadPiece {
+        sq: Square,
+        side: Side,
+        pt: PieceType,
+    },
+}
+
+impl Position {
+    /// 局面が本将棋の局面として妥当かどうかを検査し、見つかった問題を全て返す。
+    ///
+    /// 駒の枚数、各陣営の玉がちょうど1枚あること、二歩、行き所のない駒を調べる。
+    /// 王手放置などの手番に関わる検査は行わない。
+    pub fn validate(&self) -> std::result::Result<(), Vec<ValidationError>> {
+        let board = self.board();
+        let mut errors = Vec::new();
+
+        for &(pt, max) in MAX_COUNTS.iter() {
+            let n_board = all_squares()
+                .filter(|&sq| matches!(board.cell(sq), BoardCell::Piece(_, p) if p.to_unpromoted() == pt))
+                .count();
+            let n_hand: usize = if pt.is_hand() {
+                [Side::Sente, Side::Gote]
+                    .iter()
+                    .map(|&side| usize::from(self.hand(side).count(pt)))
+                    .sum()
+            } else {
+                0
+            };
+            let count = n_board + n_hand;
+            if count > max {
+                errors.push(ValidationError::TooManyPieces { pt, count });
+            }
+        }
+
+        for &side in [Side::Sente, Side::Gote].iter() {
+            let count = all_squares()
+                .filter(|&sq| board.cell(sq) == BoardCell::Piece(side, PieceType::King))
+                .count();
+            match count {
+                0 => errors.push(ValidationError::MissingKing { side }),
+                1 => {}
+                _ => errors.push(ValidationError::DuplicateKing { side, count }),
+            }
+        }
+
+        for &side in [Side::Sente, Side::Gote].iter() {
+            for x in 0..9 {
+                let n_pawn = (0..9)
+                    .filter(|&y| board.at(x, y) == BoardCell::Piece(side, PieceType::Pawn))
+                    .count();
+                if n_pawn >= 2 {
+                    errors.push(ValidationError::DoublePawn { side, x });
+                }
+            }
+        }
+
+        for sq in all_squares() {
+            if let BoardCell::Piece(side, pt) = board.cell(sq) {
+                if is_dead_square(pt, sq, side) {
+                    errors.push(ValidationError::DeadPiece { sq, side, pt });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
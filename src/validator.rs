@@ -0,0 +1,213 @@
+//! sfen の一括検証。Web API などに組み込むためのもの。
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::*;
+
+/// 一括検証の設定。
+///
+/// 1回の呼び出しで処理する件数と1件の長さに上限を設ける。
+/// `rate_limit` を設定すると、時間あたりの検証件数も制限する。
+#[derive(Clone, Debug)]
+pub struct Service {
+    /// 1回の `validate()` で受け付ける最大件数。超過分は `Status::Rejected` となる。
+    pub max_batch: usize,
+    /// 1件の最大バイト数。超過したものは `Status::Rejected` となる。
+    pub max_len: usize,
+    /// 時間あたりの検証件数の制限。None なら制限しない。
+    /// トークンが足りない分は `Status::Rejected` となる。
+    pub rate_limit: Option<RateLimiter>,
+    /// 指し手リストを局面上で再生して合法性を検査するかどうか。
+    pub replay: bool,
+    /// ワーカースレッド数。
+    pub threads: NonZeroUsize,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self {
+            max_batch: 10000,
+            max_len: 8192,
+            rate_limit: None,
+            replay: true,
+            threads: std::thread::available_parallelism()
+                .unwrap_or_else(|_| NonZeroUsize::new(1).expect("internal error")),
+        }
+    }
+}
+
+/// トークンバケット方式の流量制限。
+///
+/// 最大 `capacity` 個のトークンを持ち、毎秒 `refill_per_sec` 個ずつ補充される。
+/// 1件の検証ごとに1個を消費する。clone したものは同じバケットを共有する。
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    /// トークンが満杯の状態で作る。
+    pub fn new(capacity: u32, refill_per_sec: f64) -> Self {
+        Self {
+            capacity: f64::from(capacity),
+            refill_per_sec,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: f64::from(capacity),
+                last: Instant::now(),
+            })),
+        }
+    }
+
+    /// 最大 `n` 個のトークンを消費し、消費できた個数を返す。
+    pub fn acquire(&self, n: usize) -> usize {
+        self.acquire_at(n, Instant::now())
+    }
+
+    pub(crate) fn acquire_at(&self, n: usize, now: Instant) -> usize {
+        let mut bucket = self
+            .bucket
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let elapsed = now.saturating_duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last = bucket.last.max(now);
+
+        let n = n.min(bucket.tokens as usize);
+        bucket.tokens -= n as f64;
+        n
+    }
+}
+
+/// 1件の検証結果の区分。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Status {
+    /// 問題なし。
+    Ok,
+    /// デコードはできたが、局面が妥当でないか非合法手を含む。
+    Invalid,
+    /// デコードできない。
+    Malformed,
+    /// 件数、長さ、流量の上限により検証しなかった。
+    Rejected,
+}
+
+/// 1件の検証結果。
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// 入力中のインデックス。
+    pub index: usize,
+    pub status: Status,
+    /// デコード結果を再エンコードした sfen。デコードできなかった場合は None。
+    pub sfen: Option<String>,
+    /// 検出した問題のメッセージ。
+    pub errors: Vec<String>,
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `inputs` の各 sfen を検証し、入力と同じ順で結果を返す。
+    ///
+    /// 各入力は `decode()` でデコードし、`Position::validate()` で局面を検査する。
+    /// `replay` が true なら、さらに `decode_strict()` と同様に指し手リストの合法性を検査する。
+    pub fn validate<S>(&self, inputs: &[S]) -> Vec<Report>
+    where
+        S: AsRef<str> + Sync,
+    {
+        let n_batch = inputs.len().min(self.max_batch);
+        let n_accepted = match &self.rate_limit {
+            Some(rate_limit) => rate_limit.acquire(n_batch),
+            None => n_batch,
+        };
+        let n_thread = self.threads.get();
+        let chunk_len = ((n_accepted + n_thread - 1) / n_thread).max(1);
+
+        let mut reports: Vec<Report> = std::thread::scope(|scope| {
+            let handles: Vec<_> = inputs[..n_accepted]
+                .chunks(chunk_len)
+                .enumerate()
+                .map(|(i, chunk)| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .enumerate()
+                            .map(|(j, input)| self.validate_one(i * chunk_len + j, input.as_ref()))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("internal error"))
+                .collect()
+        });
+
+        let rejected = |index, msg: &str| Report {
+            index,
+            status: Status::Rejected,
+            sfen: None,
+            errors: vec![msg.to_owned()],
+        };
+        reports.extend((n_accepted..n_batch).map(|index| rejected(index, "rate limit exceeded")));
+        reports.extend(
+            (n_batch..inputs.len()).map(|index| rejected(index, "batch size limit exceeded")),
+        );
+
+        reports
+    }
+
+    fn validate_one(&self, index: usize, input: &str) -> Report {
+        let report = |status, sfen, errors| Report {
+            index,
+            status,
+            sfen,
+            errors,
+        };
+
+        if input.len() > self.max_len {
+            return report(
+                Status::Rejected,
+                None,
+                vec!["length limit exceeded".to_owned()],
+            );
+        }
+
+        let (pos, mvs) = match decode(input) {
+            Ok(x) => x,
+            Err(e) => return report(Status::Malformed, None, vec![e.to_string()]),
+        };
+        let sfen = Some(encode(&pos, &mvs));
+
+        let mut errors: Vec<String> = match pos.validate() {
+            Ok(()) => Vec::new(),
+            Err(es) => es.iter().map(ToString::to_string).collect(),
+        };
+        if self.replay {
+            if let Err(e) = decode_strict(input) {
+                errors.push(e.to_string());
+            }
+        }
+
+        let status = if errors.is_empty() {
+            Status::Ok
+        } else {
+            Status::Invalid
+        };
+        report(status, sfen, errors)
+    }
+}
@@ -0,0 +1,94 @@
+//! 盤の大きさが異なる変則将棋の sfen。
+//!
+//! 盤面は `Board` (9x9) の1筋から `width()` 筋、一段目から `height()` 段目までの領域に置く。
+//! 指し手のマス名もそのまま ("5e4d" など) 使える。
+//! 指し手生成や局面の検査などは本将棋のルールで行われるので、変則将棋には使えない。
+
+use crate::codec::Codec;
+use crate::*;
+
+/// 将棋の種類。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Variant {
+    /// 本将棋 (9x9)。
+    #[default]
+    Standard,
+    /// 5五将棋 (5x5)。
+    Minishogi,
+}
+
+impl Variant {
+    /// 盤の筋数。
+    pub fn width(self) -> u8 {
+        match self {
+            Self::Standard => 9,
+            Self::Minishogi => 5,
+        }
+    }
+
+    /// 盤の段数。
+    pub fn height(self) -> u8 {
+        match self {
+            Self::Standard => 9,
+            Self::Minishogi => 5,
+        }
+    }
+
+    /// マス `sq` が盤の範囲内かどうかを返す。
+    pub fn contains(self, sq: Square) -> bool {
+        sq.x() < self.width() && sq.y() < self.height()
+    }
+
+    /// "startpos" が表す開始局面の sfen。
+    pub(crate) fn startpos_sfen(self) -> &'static str {
+        match self {
+            Self::Standard => {
+                "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1"
+            }
+            Self::Minishogi => "sfen rbsgk/4p/5/P4/KGSBR b - 1",
+        }
+    }
+}
+
+/// 指定した種類の将棋の sfen をパースして (局面、指し手リスト) を返す。
+///
+/// 盤面の段数、各段のマス数が盤の大きさと一致しない場合や、指し手のマスが盤の範囲外の場合は
+/// エラーを返す。それ以外の合法性チェックは一切行わない。
+pub fn decode_variant(variant: Variant, sfen: impl AsRef<str>) -> Result<(Position, Vec<Move>)> {
+    decode::decode_with_variant(&Codec::STANDARD, variant, sfen.as_ref())
+}
+
+/// 局面と指し手リストを、指定した種類の将棋の sfen にする。
+///
+/// 盤の範囲外に駒がある場合や、指し手のマスが盤の範囲外の場合はエラーを返す。
+pub fn encode_variant(variant: Variant, pos: &Position, mvs: &[Move]) -> Result<String> {
+    if pos
+        .board()
+        .iter()
+        .any(|(sq, cell)| cell != BoardCell::Empty && !variant.contains(sq))
+    {
+        return Err(Error::encode_error(format!(
+            "variant: piece outside of {:?} board",
+            variant
+        )));
+    }
+    if let Some(&mv) = mvs.iter().find(|&&mv| !move_in_board(variant, mv)) {
+        return Err(Error::encode_error(format!(
+            "variant: move outside of {:?} board: {}",
+            variant, mv
+        )));
+    }
+    Ok(encode::encode_with_variant(
+        &Codec::STANDARD,
+        variant,
+        pos,
+        mvs,
+    ))
+}
+
+pub(crate) fn move_in_board(variant: Variant, mv: Move) -> bool {
+    match mv {
+        Move::Nondrop(nd) => variant.contains(nd.src()) && variant.contains(nd.dst()),
+        Move::Drop(d) => variant.contains(d.dst()),
+    }
+}
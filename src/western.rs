@@ -0,0 +1,83 @@
+//! 英語圏の書籍などで使われる Hodges 式の指し手表記 ("P-7f", "Bx2b+", "S*5e")。
+
+use crate::movegen::can_promote;
+use crate::*;
+
+impl PieceType {
+    /// Hodges 式の駒の略号 ("P", "+B" など)。
+    fn western_symbol(self) -> &'static str {
+        match self {
+            Self::Pawn => "P",
+            Self::Lance => "L",
+            Self::Knight => "N",
+            Self::Silver => "S",
+            Self::Bishop => "B",
+            Self::Rook => "R",
+            Self::Gold => "G",
+            Self::King => "K",
+            Self::ProPawn => "+P",
+            Self::ProLance => "+L",
+            Self::ProKnight => "+N",
+            Self::ProSilver => "+S",
+            Self::Horse => "+B",
+            Self::Dragon => "+R",
+        }
+    }
+}
+
+impl Move {
+    /// 局面 `pos` でのこの指し手を Hodges 式の表記 ("P-7f", "Bx2b+", "S*5e" など) にする。
+    ///
+    /// 移動は "-"、駒取りは "x"、駒打ちは "*" で表す。成りには "+"、成れるのに成らない手には
+    /// "=" を付ける。同じ駒種の駒が複数同じマスに移動できる場合、"G6a-5b" のように移動元を付ける。
+    /// 非合法手であればエラーを返す。
+    pub fn to_western(&self, pos: &Position) -> Result<String> {
+        if !(pos.is_pseudo_legal(*self) && pos.is_legal(*self)) {
+            return Err(Error::move_error(format!(
+                "western: illegal move: {}",
+                encode::encode_move(*self)
+            )));
+        }
+
+        let nd = match *self {
+            Move::Nondrop(nd) => nd,
+            Move::Drop(d) => {
+                return Ok(format!(
+                    "{}*{}",
+                    d.pt().western_symbol(),
+                    d.dst().name(Lang::English)
+                ))
+            }
+        };
+
+        let pt = match pos.board().cell(nd.src()) {
+            BoardCell::Piece(_, pt) => pt,
+            BoardCell::Empty => unreachable!("internal error"),
+        };
+
+        let mut res = pt.western_symbol().to_owned();
+        let ambiguous = pos.moves_to(nd.dst()).into_iter().any(|mv| match mv {
+            Move::Nondrop(other) => {
+                other.src() != nd.src()
+                    && pos.board().cell(other.src()) == pos.board().cell(nd.src())
+            }
+            Move::Drop(_) => false,
+        });
+        if ambiguous {
+            res.push_str(&nd.src().name(Lang::English));
+        }
+        res.push(if pos.board().cell(nd.dst()) == BoardCell::Empty {
+            '-'
+        } else {
+            'x'
+        });
+        res.push_str(&nd.dst().name(Lang::English));
+        if nd.is_promotion() {
+            res.push('+');
+        } else if can_promote(pt, nd.src(), nd.dst(), pos.side()) {
+            res.push('=');
+        }
+
+        Ok(res)
+    }
+}